@@ -0,0 +1,28 @@
+//! Compares the size and encoding time of the IRC payloads sent to the
+//! frontend. Run with `cargo bench --features bench --bench payload_size`.
+
+use hyperion_lib::bench::measure_payloads;
+
+const FIXTURE: &str = include_str!("fixtures/chat.log");
+const ITERATIONS: usize = 1000;
+
+fn main() {
+    for batch_size in [1, 10, 100] {
+        // Repeat the fixture so every batch is full
+        let input = FIXTURE.repeat(batch_size.div_ceil(FIXTURE.lines().count()));
+        let reports = measure_payloads(&input, batch_size, ITERATIONS);
+        let baseline = reports[0].bytes as f64;
+
+        println!("batch size {batch_size}:");
+
+        for report in reports {
+            println!(
+                "  {:<20} {:>9} bytes {:>6.1}% {:>10.2?}",
+                report.name,
+                report.bytes,
+                report.bytes as f64 / baseline * 100.0,
+                report.elapsed,
+            );
+        }
+    }
+}
@@ -1,19 +1,83 @@
+use std::sync::Arc;
+
 use anyhow::anyhow;
 use futures::TryStreamExt;
-use serde::{Deserialize, Serialize};
+use futures::future::join_all;
+use serde::Serialize;
 use serde_json::json;
 use tauri::{AppHandle, Emitter, Manager, State, async_runtime};
 use tokio::sync::Mutex;
 use tracing::Instrument;
 use twitch_api::eventsub::EventType;
-use twitch_api::twitch_oauth2::{AccessToken, UserToken};
+use twitch_api::twitch_oauth2::{AccessToken, RefreshToken, UserToken};
 
 use crate::AppState;
 use crate::error::Error;
+use crate::eventsub::chat::{CHAT_EVENTS, ChatSource, ChatSources};
+use crate::eventsub::client::SubscriptionResult;
+use crate::eventsub::{EventSubClient, catalog};
+use crate::history::MessageBuffer;
+
+#[derive(Clone, Serialize)]
+struct SubscriptionResults<'a> {
+    channel: &'a str,
+    results: &'a [SubscriptionResult],
+}
+
+/// Sends the outcome of subscribing to the events of a channel to the
+/// frontend.
+fn emit_subscription_results(
+    app_handle: &AppHandle,
+    channel: &str,
+    results: &[SubscriptionResult],
+) {
+    let payload = SubscriptionResults { channel, results };
+
+    if let Err(err) = app_handle.emit("subscriptionresults", payload) {
+        tracing::error!(%err, "Failed to emit subscription results");
+    }
+}
+
+/// Subscribes to the events of a channel and reads its chat from EventSub if
+/// any chat events are included, falling back to IRC if they can't all be
+/// subscribed to.
+async fn subscribe_channel(
+    app_handle: &AppHandle,
+    eventsub: &EventSubClient,
+    channel: &str,
+    events: Vec<(EventType, serde_json::Value)>,
+) {
+    let sources = app_handle.state::<ChatSources>();
+
+    let source = if events.iter().any(|(event, _)| CHAT_EVENTS.contains(event)) {
+        ChatSource::EventSub
+    } else {
+        ChatSource::Irc
+    };
+
+    // Switch before subscribing so no messages are lost between the
+    // subscriptions being created and IRC being ignored
+    sources.set(channel, source);
+
+    let subs_ref = events.iter().map(|(event, cond)| (*event, cond)).collect();
+
+    let results = eventsub.subscribe_all(channel, subs_ref).await;
+    emit_subscription_results(app_handle, channel, &results);
+
+    let chat_failed = results
+        .iter()
+        .any(|result| CHAT_EVENTS.contains(&result.event) && result.error.is_some());
 
-#[derive(Debug, Deserialize)]
-pub struct Response<T> {
-    pub data: T,
+    if chat_failed {
+        tracing::warn!("Falling back to IRC for chat in {channel}");
+        sources.set(channel, ChatSource::Irc);
+
+        // Drop the chat subscriptions that did succeed since their messages
+        // would be ignored anyway
+        if let Err(err) = eventsub.unsubscribe_events(channel, &CHAT_EVENTS).await {
+            tracing::error!(%err, "Failed to remove chat subscriptions for {channel}");
+        }
+    }
 }
 
 pub fn get_access_token(state: &AppState) -> Result<&UserToken, Error> {
@@ -27,44 +91,84 @@ pub fn get_access_token(state: &AppState) -> Result<&UserToken, Error> {
 pub struct TokenInfo {
     user_id: String,
     access_token: String,
+    refresh_token: Option<String>,
+}
+
+impl From<&UserToken> for TokenInfo {
+    fn from(token: &UserToken) -> Self {
+        Self {
+            user_id: token.user_id.to_string(),
+            access_token: token.access_token.as_str().to_string(),
+            refresh_token: token
+                .refresh_token
+                .as_ref()
+                .map(|token| token.as_str().to_string()),
+        }
+    }
 }
 
+/// Validates a token and makes it the current token. The current token is
+/// kept if validation fails.
 pub async fn set_access_token(
     state: State<'_, Mutex<AppState>>,
     token: String,
+    refresh_token: Option<String>,
 ) -> Option<TokenInfo> {
-    let mut state = state.lock().await;
+    let helix = state.lock().await.helix.clone();
+
+    let validated = UserToken::from_existing(
+        &helix,
+        AccessToken::from(token),
+        refresh_token.map(RefreshToken::from),
+        None,
+    )
+    .await;
+
+    match validated {
+        Ok(token) => Some(replace_token(&mut *state.lock().await, token)),
+        Err(err) => {
+            tracing::warn!(%err, "Failed to validate access token");
+            None
+        }
+    }
+}
 
-    state.token = UserToken::from_token(&state.helix, AccessToken::from(token))
-        .await
-        .ok();
+/// Makes an already validated token the current token.
+pub fn replace_token(state: &mut AppState, token: UserToken) -> TokenInfo {
+    let raw_token = token.access_token.as_str();
+    tracing::debug!("Set access token to {}", raw_token);
 
-    if let Some(ref token) = state.token {
-        let raw_token = token.access_token.as_str();
-        tracing::debug!("Set access token to {}", raw_token);
+    // Connected clients use the new token for any connection or subscription
+    // made from now on
+    if let Some(ref irc) = state.irc {
+        irc.set_token(raw_token.to_string());
+    }
 
-        Some(TokenInfo {
-            user_id: token.user_id.to_string(),
-            access_token: raw_token.to_string(),
-        })
-    } else {
-        None
+    if let Some(ref eventsub) = state.eventsub {
+        eventsub.set_token(Arc::new(token.clone()));
     }
+
+    let info = TokenInfo::from(&token);
+    state.token = Some(token);
+
+    info
 }
 
-#[tracing::instrument(skip(state, is_mod))]
+#[tracing::instrument(skip(app_handle, state, is_mod))]
 #[tauri::command]
 pub async fn join(
+    app_handle: AppHandle,
     state: State<'_, Mutex<AppState>>,
     id: String,
     stv_id: Option<String>,
     set_id: Option<String>,
     login: String,
     is_mod: bool,
+    chat_source: Option<ChatSource>,
 ) -> Result<(), Error> {
     tracing::info!("Joining {login}");
 
-    let (token, irc, eventsub, seventv) = {
+    let (token, irc, eventsub, seventv, bttv, ffz) = {
         let state = state.lock().await;
         let token = get_access_token(&state)?;
 
@@ -78,6 +182,8 @@ pub async fn join(
             irc,
             state.eventsub.clone(),
             state.seventv.clone(),
+            state.bttv.clone(),
+            state.ffz.clone(),
         )
     };
 
@@ -87,102 +193,107 @@ pub async fn join(
     async_runtime::spawn(
         async move {
             if let Some(eventsub) = eventsub {
-                let ch_cond = json!({
-                    "broadcaster_user_id": id_clone
-                });
-
-                let ch_with_user_cond = json!({
-                    "broadcaster_user_id": id_clone,
-                    "user_id": token.user_id
-                });
-
-                let ch_with_mod_cond = json!({
-                    "broadcaster_user_id": id_clone,
-                    "moderator_user_id": token.user_id
-                });
-
-                use EventType as Ev;
-
-                let mut events = vec![
-                    (Ev::ChannelChatUserMessageHold, &ch_with_user_cond),
-                    (Ev::ChannelChatUserMessageUpdate, &ch_with_user_cond),
-                    (Ev::ChannelSubscriptionEnd, &ch_cond),
-                    (Ev::ChannelUpdate, &ch_cond),
-                    (Ev::StreamOffline, &ch_cond),
-                    (Ev::StreamOnline, &ch_cond),
-                ];
-
-                if is_mod {
-                    let mod_events = vec![
-                        (Ev::AutomodMessageHold, &ch_with_mod_cond),
-                        (Ev::AutomodMessageUpdate, &ch_with_mod_cond),
-                        (Ev::ChannelModerate, &ch_with_mod_cond),
-                        (Ev::ChannelSuspiciousUserMessage, &ch_with_mod_cond),
-                        (Ev::ChannelSuspiciousUserUpdate, &ch_with_mod_cond),
-                        (Ev::ChannelUnbanRequestCreate, &ch_with_mod_cond),
-                        (Ev::ChannelUnbanRequestResolve, &ch_with_mod_cond),
-                        (Ev::ChannelWarningAcknowledge, &ch_with_mod_cond),
-                    ];
-
-                    events.extend(mod_events)
-                }
-
-                if let Err(err) = eventsub.subscribe_all(login_clone.as_str(), events).await {
-                    tracing::error!(%err, "Failed to batch subscribe to EventSub events");
-                }
-            }
+                let mut events = catalog::events_for(&token, &id_clone, is_mod);
 
-            if let Some(seventv) = seventv {
-                let channel_cond = json!({
-                    "ctx": "channel",
-                    "platform": "TWITCH",
-                    "id": id_clone
-                });
-
-                seventv
-                    .subscribe(&login_clone, "cosmetic.create", &channel_cond)
-                    .await;
-
-                seventv
-                    .subscribe(&login_clone, "entitlement.create", &channel_cond)
-                    .await;
-
-                if let Some(ref set_id) = set_id {
-                    seventv
-                        .subscribe(&login_clone, "emote_set.*", &json!({ "object_id": set_id }))
-                        .await;
+                if chat_source == Some(ChatSource::EventSub) {
+                    events.extend(catalog::chat_events_for(&token, &id_clone));
                 }
 
-                if let Some(ref stv_id) = stv_id {
-                    seventv
-                        .subscribe(&login_clone, "user.update", &json!({ "object_id": stv_id }))
-                        .await;
-                }
+                subscribe_channel(&app_handle, &eventsub, &login_clone, events).await;
             }
         }
         .in_current_span(),
     );
 
-    irc.join(login);
+    irc.join(login.clone());
+
+    if let Some(bttv) = bttv {
+        bttv.subscribe(&login, &id).await;
+    }
+
+    if let Some(ffz) = ffz {
+        ffz.subscribe(&login, &id).await;
+    }
+
+    if let Some(seventv) = seventv {
+        let channel_cond = json!({
+            "ctx": "channel",
+            "platform": "TWITCH",
+            "id": id
+        });
+
+        let mut subscriptions = vec![
+            ("cosmetic.create", channel_cond.clone()),
+            ("entitlement.create", channel_cond),
+        ];
+
+        if let Some(set_id) = set_id {
+            subscriptions.push(("emote_set.*", json!({ "object_id": set_id })));
+        }
+
+        if let Some(stv_id) = stv_id {
+            subscriptions.push(("user.update", json!({ "object_id": stv_id })));
+        }
+
+        let futures = subscriptions
+            .iter()
+            .map(|(event, condition)| seventv.subscribe(&login, event, condition));
+
+        join_all(futures)
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+    }
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn leave(state: State<'_, Mutex<AppState>>, channel: String) -> Result<(), Error> {
+pub async fn leave(
+    state: State<'_, Mutex<AppState>>,
+    buffer: State<'_, MessageBuffer>,
+    sources: State<'_, ChatSources>,
+    channel: String,
+) -> Result<(), Error> {
     tracing::info!("Leaving {channel}");
 
-    let state = state.lock().await;
+    buffer.remove(&channel).await;
+    sources.remove(&channel);
 
-    if let Some(ref eventsub) = state.eventsub {
-        eventsub.unsubscribe_all(&channel).await?;
+    let (eventsub, seventv, bttv, ffz, irc) = {
+        let state = state.lock().await;
+
+        (
+            state.eventsub.clone(),
+            state.seventv.clone(),
+            state.bttv.clone(),
+            state.ffz.clone(),
+            state.irc.clone(),
+        )
+    };
+
+    // Failing to clean up one source shouldn't keep the others subscribed
+    if let Some(eventsub) = eventsub
+        && let Err(err) = eventsub.unsubscribe_all(&channel).await
+    {
+        tracing::error!(%err, "Failed to unsubscribe from EventSub events");
     }
 
-    if let Some(ref seventv) = state.seventv {
-        seventv.unsubscribe_all(&channel).await;
+    if let Some(seventv) = seventv
+        && let Err(err) = seventv.unsubscribe_all(&channel).await
+    {
+        tracing::error!(%err, "Failed to unsubscribe from 7TV events");
     }
 
-    if let Some(ref irc) = state.irc {
+    if let Some(bttv) = bttv {
+        bttv.unsubscribe(&channel).await;
+    }
+
+    if let Some(ffz) = ffz {
+        ffz.unsubscribe(&channel).await;
+    }
+
+    if let Some(irc) = irc {
         irc.part(channel);
     }
 
@@ -190,20 +301,37 @@ pub async fn leave(state: State<'_, Mutex<AppState>>, channel: String) -> Result
 }
 
 #[tauri::command]
-pub async fn rejoin(state: State<'_, Mutex<AppState>>, channel: String) -> Result<(), Error> {
+pub async fn rejoin(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    id: String,
+    channel: String,
+    chat_source: Option<ChatSource>,
+) -> Result<(), Error> {
     tracing::info!("Rejoining {channel}");
 
-    let (eventsub, irc) = {
+    let (token, eventsub, irc) = {
         let state = state.lock().await;
+        let token = get_access_token(&state)?;
 
-        (state.eventsub.clone(), state.irc.clone())
+        (token.clone(), state.eventsub.clone(), state.irc.clone())
     };
 
     if let Some(eventsub) = eventsub {
-        let subscriptions = eventsub.unsubscribe_all(&channel).await?;
-        let subs_ref: Vec<_> = subscriptions.iter().map(|(e, c)| (*e, c)).collect();
+        // Chat events are resubscribed based on the requested source since
+        // they may have been dropped after falling back to IRC
+        let mut events: Vec<_> = eventsub
+            .unsubscribe_all(&channel)
+            .await?
+            .into_iter()
+            .filter(|(event, _)| !CHAT_EVENTS.contains(event))
+            .collect();
+
+        if chat_source == Some(ChatSource::EventSub) {
+            events.extend(catalog::chat_events_for(&token, &id));
+        }
 
-        eventsub.subscribe_all(&channel, subs_ref).await?;
+        subscribe_channel(&app_handle, &eventsub, &channel, events).await;
     }
 
     if let Some(irc) = irc {
@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::{self, JoinHandle};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::Instrument;
+
+use super::{APP_CLIENT_ID, ErrorResponse, OAUTH_API, TokenResponse};
+use crate::api::set_access_token;
+use crate::error::Error;
+use crate::{AppState, HTTP};
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+#[derive(Deserialize)]
+struct DeviceCode {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    expires_in: u64,
+    interval: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct DeviceCodeInfo<'a> {
+    user_code: &'a str,
+    verification_uri: &'a str,
+    expires_in: u64,
+}
+
+/// The device code login waiting for authorization, if any.
+#[derive(Default)]
+pub struct DeviceLogin(Mutex<Option<JoinHandle<()>>>);
+
+fn emit_failure(app_handle: &AppHandle, message: &str) {
+    if let Err(err) = app_handle.emit("deviceloginfailed", message) {
+        tracing::error!(%err, "Failed to emit device login failure");
+    }
+}
+
+/// Starts a device code login, emitting the code the user enters at the
+/// verification url and the resulting token once they authorize the app.
+#[tracing::instrument(skip_all)]
+#[tauri::command]
+pub async fn start_device_login(
+    app_handle: AppHandle,
+    login: State<'_, DeviceLogin>,
+    scopes: Vec<String>,
+) -> Result<(), Error> {
+    let scopes = scopes.join(" ");
+
+    let code: DeviceCode = HTTP
+        .post(format!("{OAUTH_API}/device"))
+        .form(&[("client_id", APP_CLIENT_ID), ("scopes", scopes.as_str())])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    tracing::info!("Requested device code, waiting for authorization");
+
+    let info = DeviceCodeInfo {
+        user_code: &code.user_code,
+        verification_uri: &code.verification_uri,
+        expires_in: code.expires_in,
+    };
+
+    if let Err(err) = app_handle.emit("devicecode", info) {
+        tracing::error!(%err, "Failed to emit device code");
+    }
+
+    let handle = async_runtime::spawn(poll(app_handle.clone(), code, scopes).in_current_span());
+
+    if let Some(previous) = login.0.lock().await.replace(handle) {
+        previous.abort();
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_device_login(login: State<'_, DeviceLogin>) -> Result<(), Error> {
+    if let Some(handle) = login.0.lock().await.take() {
+        handle.abort();
+        tracing::info!("Cancelled device login");
+    }
+
+    Ok(())
+}
+
+/// Polls for a token at the interval given by Twitch until the user
+/// authorizes or denies the app, or the device code expires.
+async fn poll(app_handle: AppHandle, code: DeviceCode, scopes: String) {
+    let deadline = Instant::now() + Duration::from_secs(code.expires_in);
+    let mut interval = Duration::from_secs(code.interval.max(1));
+
+    while Instant::now() < deadline {
+        tokio::time::sleep(interval).await;
+
+        let response = HTTP
+            .post(format!("{OAUTH_API}/token"))
+            .form(&[
+                ("client_id", APP_CLIENT_ID),
+                ("scopes", scopes.as_str()),
+                ("device_code", code.device_code.as_str()),
+                ("grant_type", DEVICE_CODE_GRANT),
+            ])
+            .send()
+            .await;
+
+        let response = match response {
+            Ok(response) => response,
+            Err(err) => {
+                tracing::warn!(%err, "Failed to poll for device authorization");
+                continue;
+            }
+        };
+
+        if response.status().is_success() {
+            match response.json::<TokenResponse>().await {
+                Ok(token) => authorize(&app_handle, token).await,
+                Err(err) => {
+                    tracing::error!(%err, "Failed to parse device token");
+                    emit_failure(&app_handle, &err.to_string());
+                }
+            }
+
+            return;
+        }
+
+        let message = response
+            .json::<ErrorResponse>()
+            .await
+            .map(|error| error.message)
+            .unwrap_or_default();
+
+        match message.as_str() {
+            "authorization_pending" => (),
+            "slow_down" => interval += Duration::from_secs(5),
+            _ => {
+                tracing::error!("Device authorization failed: {message}");
+                emit_failure(&app_handle, &message);
+
+                return;
+            }
+        }
+    }
+
+    tracing::warn!("Device code expired before authorization");
+    emit_failure(&app_handle, "The device code expired");
+}
+
+async fn authorize(app_handle: &AppHandle, token: TokenResponse) {
+    let state = app_handle.state::<Mutex<AppState>>();
+
+    match set_access_token(state, token.access_token, token.refresh_token).await {
+        Some(token_info) => {
+            tracing::info!("Device authorized");
+
+            if let Err(err) = app_handle.emit("tokeninfo", token_info) {
+                tracing::error!(%err, "Failed to emit token info");
+            }
+        }
+        None => emit_failure(app_handle, "The token could not be validated"),
+    }
+}
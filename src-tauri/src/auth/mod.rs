@@ -0,0 +1,40 @@
+mod device;
+mod validate;
+
+pub use device::{DeviceLogin, cancel_device_login, start_device_login};
+use serde::Deserialize;
+use tauri::State;
+use tokio::sync::Mutex;
+use twitch_api::twitch_oauth2::Scope;
+pub use validate::{refresh_existing, spawn_validator};
+
+use crate::AppState;
+use crate::api::TokenInfo;
+use crate::error::Error;
+
+/// Client id of the application users authorize, as opposed to the client id
+/// of the Twitch website used for unauthenticated requests.
+const APP_CLIENT_ID: &str = "2z7vk7rabefjdhey6m5cxfxsbspw7c";
+
+const OAUTH_API: &str = "https://id.twitch.tv/oauth2";
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    /// Seconds until the token expires.
+    expires_in: Option<u64>,
+    scope: Option<Vec<Scope>>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    message: String,
+}
+
+/// The token the backend currently authenticates with, which may have been
+/// refreshed since it was stored.
+#[tauri::command]
+pub async fn get_token_info(state: State<'_, Mutex<AppState>>) -> Result<Option<TokenInfo>, Error> {
+    Ok(state.lock().await.token.as_ref().map(TokenInfo::from))
+}
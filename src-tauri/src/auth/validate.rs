@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tauri::{AppHandle, Emitter, Manager, async_runtime};
+use tokio::sync::Mutex;
+use tracing::Instrument;
+use twitch_api::HelixClient;
+use twitch_api::twitch_oauth2::{AccessToken, ClientSecret, RefreshToken, TwitchToken, UserToken};
+
+use super::{APP_CLIENT_ID, OAUTH_API, TokenResponse};
+use crate::api::replace_token;
+use crate::error::Error;
+use crate::{AppState, HTTP};
+
+/// How often the token is validated, as required by Twitch.
+const VALIDATE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How long before it expires a token is refreshed.
+const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Delay before validating again when Twitch couldn't be reached.
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+struct Validation {
+    expires_in: u64,
+}
+
+/// Validates an access token, returning how long it remains valid for or
+/// `None` if it expired or was revoked. Tokens that never expire are valid
+/// for zero seconds.
+async fn validate(access_token: &str) -> Result<Option<Duration>, Error> {
+    let response = HTTP
+        .get(format!("{OAUTH_API}/validate"))
+        .header("Authorization", format!("OAuth {access_token}"))
+        .send()
+        .await?;
+
+    if response.status() == StatusCode::UNAUTHORIZED {
+        return Ok(None);
+    }
+
+    let validation: Validation = response.error_for_status()?.json().await?;
+
+    Ok(Some(Duration::from_secs(validation.expires_in)))
+}
+
+async fn refresh(refresh_token: &str) -> Result<TokenResponse, Error> {
+    Ok(HTTP
+        .post(format!("{OAUTH_API}/token"))
+        .form(&[
+            ("client_id", APP_CLIENT_ID),
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+/// Exchanges a refresh token for a new token, used when the stored token
+/// expired while the app was closed.
+pub async fn refresh_existing(
+    helix: &HelixClient<'static, reqwest::Client>,
+    refresh_token: &RefreshToken,
+) -> Option<UserToken> {
+    let token = match refresh(refresh_token.as_str()).await {
+        Ok(token) => token,
+        Err(err) => {
+            tracing::warn!(%err, "Failed to refresh stored token");
+            return None;
+        }
+    };
+
+    UserToken::from_existing(
+        helix,
+        AccessToken::from(token.access_token),
+        token.refresh_token.map(RefreshToken::from),
+        None,
+    )
+    .await
+    .ok()
+}
+
+/// Validates the current token and refreshes it if it expired or is about
+/// to, returning the delay before the next check.
+async fn check(app_handle: &AppHandle) -> Duration {
+    let state = app_handle.state::<Mutex<AppState>>();
+
+    let Some(token) = state.lock().await.token.clone() else {
+        return VALIDATE_INTERVAL;
+    };
+
+    match validate(token.access_token.as_str()).await {
+        Ok(Some(expires_in)) if expires_in.is_zero() => return VALIDATE_INTERVAL,
+        Ok(Some(expires_in)) if expires_in > REFRESH_MARGIN => {
+            tracing::debug!("Token is valid for {}s", expires_in.as_secs());
+            return VALIDATE_INTERVAL.min(expires_in - REFRESH_MARGIN);
+        }
+        Ok(_) => tracing::info!("Token expired or is about to, refreshing"),
+        Err(err) => {
+            tracing::warn!(%err, "Failed to validate token");
+            return RETRY_DELAY;
+        }
+    }
+
+    let refreshed = match token.refresh_token {
+        Some(ref refresh_token) => refresh(refresh_token.as_str()).await,
+        None => Err(Error::Generic(anyhow!("No refresh token"))),
+    };
+
+    let token_info = match refreshed {
+        // The refreshed token belongs to the same user, so it's used as is
+        // rather than validated again, which could fail transiently and log
+        // the user out
+        Ok(refreshed) => {
+            let refreshed = UserToken::from_existing_unchecked(
+                AccessToken::from(refreshed.access_token),
+                refreshed.refresh_token.map(RefreshToken::from),
+                token.client_id().clone(),
+                None::<ClientSecret>,
+                token.login.clone(),
+                token.user_id.clone(),
+                refreshed.scope.or_else(|| Some(token.scopes().to_vec())),
+                refreshed.expires_in.map(Duration::from_secs),
+            );
+
+            Some(replace_token(&mut *state.lock().await, refreshed))
+        }
+        // Only give up on the token once Twitch rejects the refresh token
+        Err(Error::Http(err)) if err.status().is_none_or(|status| status.is_server_error()) => {
+            tracing::warn!(%err, "Failed to reach Twitch to refresh token");
+            return RETRY_DELAY;
+        }
+        Err(err) => {
+            tracing::warn!(%err, "Failed to refresh token");
+            None
+        }
+    };
+
+    match token_info {
+        Some(token_info) => {
+            tracing::info!("Refreshed token");
+
+            if let Err(err) = app_handle.emit("tokenrefreshed", token_info) {
+                tracing::error!(%err, "Failed to emit refreshed token");
+            }
+        }
+        None => {
+            tracing::error!("Token expired and could not be refreshed");
+
+            if let Err(err) = app_handle.emit("tokenexpired", ()) {
+                tracing::error!(%err, "Failed to emit token expiry");
+            }
+        }
+    }
+
+    VALIDATE_INTERVAL
+}
+
+/// Spawns a task validating the token on a schedule and refreshing it when
+/// it expires.
+pub fn spawn_validator(app_handle: AppHandle) {
+    async_runtime::spawn(
+        async move {
+            loop {
+                let delay = check(&app_handle).await;
+                tokio::time::sleep(delay).await;
+            }
+        }
+        .in_current_span(),
+    );
+}
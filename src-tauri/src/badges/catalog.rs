@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_svelte::ManagerExt;
+use tokio::sync::Mutex;
+use twitch_api::HelixClient;
+use twitch_api::twitch_oauth2::UserToken;
+
+use super::{Badge, Cheermote, helix};
+use crate::error::Error;
+use crate::irc::message::{self as irc, Cheer, ServerMessage};
+use crate::seventv::message::{BadgeData, CosmeticData, DispatchBody, EntitlementKind};
+use crate::{AppState, cache};
+
+const GLOBAL_KEY: &str = "badges:global";
+const SEVENTV_KEY: &str = "badges:seventv";
+
+/// How long badges and cheermotes are cached for. Twitch adds new global
+/// badges fairly often, so this is lower than the global emote cache.
+const TTL: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+
+/// Loads an entry from the cache or fetches it from Helix if it isn't cached
+/// or `force` is set. Returns `None` if there is no token to fetch it with.
+async fn load<T, F>(
+    app_handle: &AppHandle,
+    key: &str,
+    force: bool,
+    fetch: impl FnOnce(HelixClient<'static, reqwest::Client>, UserToken) -> F,
+) -> Result<Option<T>, Error>
+where
+    T: Serialize + DeserializeOwned,
+    F: Future<Output = Result<T, Error>>,
+{
+    if !force && let Some(value) = cache::read(app_handle, key) {
+        return Ok(Some(value));
+    }
+
+    let (helix, token) = {
+        let state = app_handle.state::<Mutex<AppState>>();
+        let state = state.lock().await;
+
+        (state.helix.clone(), state.token.clone())
+    };
+
+    let Some(token) = token else {
+        return Ok(None);
+    };
+
+    let value = fetch(helix, token).await?;
+    cache::write(app_handle, key, &value, TTL);
+
+    Ok(Some(value))
+}
+
+fn index(badges: &[Badge]) -> HashMap<String, Badge> {
+    badges
+        .iter()
+        .map(|badge| (badge.key(), badge.clone()))
+        .collect()
+}
+
+#[derive(Default)]
+struct SevenTvBadges {
+    /// Badges keyed by their cosmetic id.
+    badges: HashMap<String, Badge>,
+    /// Cosmetic ids of the badges owned by each Twitch user.
+    users: HashMap<String, Vec<String>>,
+}
+
+fn seventv_setting(app_handle: &AppHandle) -> bool {
+    app_handle
+        .svelte()
+        .get_raw("settings", "chat.badges.seventv")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(true)
+}
+
+/// Chat badges and cheermotes, used to resolve the badges and cheers of
+/// messages outside the webview.
+///
+/// Channel badges take precedence over global badges with the same set and
+/// version.
+pub struct BadgeCatalog {
+    global: RwLock<HashMap<String, Badge>>,
+    /// Badges keyed by the Twitch id of the channel.
+    channels: RwLock<HashMap<String, HashMap<String, Badge>>>,
+    /// Cheermotes keyed by the Twitch id of the channel.
+    cheermotes: RwLock<HashMap<String, Vec<Cheermote>>>,
+    seventv: RwLock<SevenTvBadges>,
+    /// The `chat.badges.seventv` setting, kept up to date by a watcher on the
+    /// settings store once it has been created.
+    seventv_enabled: AtomicBool,
+    watching_settings: AtomicBool,
+}
+
+impl Default for BadgeCatalog {
+    fn default() -> Self {
+        Self {
+            global: RwLock::default(),
+            channels: RwLock::default(),
+            cheermotes: RwLock::default(),
+            seventv: RwLock::default(),
+            seventv_enabled: AtomicBool::new(true),
+            watching_settings: AtomicBool::new(false),
+        }
+    }
+}
+
+impl BadgeCatalog {
+    /// Loads the global badges, along with the 7TV badges seen previously.
+    /// The loaded badges are kept if there's no token to fetch them with.
+    pub async fn load_global(
+        &self,
+        app_handle: &AppHandle,
+        force: bool,
+    ) -> Result<Vec<Badge>, Error> {
+        if let Some(badges) = cache::read::<HashMap<String, Badge>>(app_handle, SEVENTV_KEY) {
+            let mut seventv = self.seventv.write().unwrap();
+
+            for (id, badge) in badges {
+                seventv.badges.entry(id).or_insert(badge);
+            }
+        }
+
+        let loaded = load(app_handle, GLOBAL_KEY, force, |client, token| async move {
+            helix::global_badges(&client, &token).await
+        })
+        .await?;
+
+        let Some(badges) = loaded else {
+            return Ok(self.global.read().unwrap().values().cloned().collect());
+        };
+
+        *self.global.write().unwrap() = index(&badges);
+
+        tracing::info!("Loaded {} global badges", badges.len());
+
+        Ok(badges)
+    }
+
+    pub async fn load_channel(
+        &self,
+        app_handle: &AppHandle,
+        id: &str,
+        force: bool,
+    ) -> Result<Vec<Badge>, Error> {
+        let loaded = load(
+            app_handle,
+            &format!("badges:{id}"),
+            force,
+            |client, token| async move { helix::channel_badges(&client, &token, id).await },
+        )
+        .await?;
+
+        let Some(badges) = loaded else {
+            let channels = self.channels.read().unwrap();
+            let badges = channels
+                .get(id)
+                .map(|badges| badges.values().cloned().collect());
+
+            return Ok(badges.unwrap_or_default());
+        };
+
+        self.channels
+            .write()
+            .unwrap()
+            .insert(id.to_string(), index(&badges));
+
+        tracing::info!("Loaded {} badges for {id}", badges.len());
+
+        Ok(badges)
+    }
+
+    pub async fn load_cheermotes(
+        &self,
+        app_handle: &AppHandle,
+        id: &str,
+        force: bool,
+    ) -> Result<Vec<Cheermote>, Error> {
+        let loaded = load(
+            app_handle,
+            &format!("cheermotes:{id}"),
+            force,
+            |client, token| async move { helix::cheermotes(&client, &token, id).await },
+        )
+        .await?;
+
+        let Some(cheermotes) = loaded else {
+            let cheermotes = self.cheermotes.read().unwrap().get(id).cloned();
+
+            return Ok(cheermotes.unwrap_or_default());
+        };
+
+        self.cheermotes
+            .write()
+            .unwrap()
+            .insert(id.to_string(), cheermotes.clone());
+
+        tracing::info!("Loaded {} cheermotes for {id}", cheermotes.len());
+
+        Ok(cheermotes)
+    }
+
+    /// Fills in the title and image of a badge shown in a channel.
+    fn resolve_badge(&self, channel_id: &str, badge: &mut irc::Badge) {
+        let key = format!("{}:{}", badge.name, badge.version);
+
+        let channel = self
+            .channels
+            .read()
+            .unwrap()
+            .get(channel_id)
+            .and_then(|badges| badges.get(&key).cloned());
+
+        let Some(resolved) = channel.or_else(|| self.global.read().unwrap().get(&key).cloned())
+        else {
+            return;
+        };
+
+        badge.title = Some(resolved.title);
+        badge.image_url = Some(resolved.image_url);
+    }
+
+    /// Finds the cheers in a message and the cheermote tier they fall in.
+    fn resolve_cheers(&self, channel_id: &str, text: &str) -> Vec<Cheer> {
+        let cheermotes = self.cheermotes.read().unwrap();
+
+        let Some(cheermotes) = cheermotes.get(channel_id) else {
+            return vec![];
+        };
+
+        text.split_whitespace()
+            .filter_map(|word| {
+                let prefix = word.trim_end_matches(|c: char| c.is_ascii_digit());
+                let bits: u64 = word[prefix.len()..].parse().ok().filter(|&bits| bits > 0)?;
+
+                let cheermote = cheermotes
+                    .iter()
+                    .find(|cheermote| cheermote.prefix.eq_ignore_ascii_case(prefix))?;
+
+                let tier = cheermote
+                    .tiers
+                    .iter()
+                    .filter(|tier| tier.bits <= bits)
+                    .max_by_key(|tier| tier.bits)?;
+
+                Some(Cheer {
+                    prefix: cheermote.prefix.clone(),
+                    bits,
+                    color: tier.color.clone(),
+                    srcset: tier
+                        .images
+                        .iter()
+                        .map(|image| format!("{} {}x", image.url, image.dpi_scale))
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
+    fn seventv_badges(&self, user_id: &str) -> Vec<irc::Badge> {
+        let seventv = self.seventv.read().unwrap();
+
+        let Some(ids) = seventv.users.get(user_id) else {
+            return vec![];
+        };
+
+        ids.iter()
+            .filter_map(|id| seventv.badges.get(id))
+            .map(|badge| irc::Badge {
+                name: badge.set_id.clone(),
+                version: badge.version.clone(),
+                title: Some(badge.title.clone()),
+                image_url: Some(badge.image_url.clone()),
+            })
+            .collect()
+    }
+
+    /// Resolves the badges and cheers of a chat message with the loaded
+    /// catalogs.
+    pub fn enrich(&self, app_handle: &AppHandle, message: &mut ServerMessage) {
+        let ServerMessage::Privmsg(privmsg) = message else {
+            return;
+        };
+
+        for badge in &mut privmsg.badges {
+            self.resolve_badge(&privmsg.channel_id, badge);
+        }
+
+        if let Some(ref mut source) = privmsg.source {
+            for badge in &mut source.badges {
+                self.resolve_badge(&source.channel_id, badge);
+            }
+        }
+
+        if privmsg.bits.is_some() {
+            privmsg.cheers = self.resolve_cheers(&privmsg.channel_id, &privmsg.message_text);
+        }
+
+        if self.seventv_enabled(app_handle) {
+            privmsg.seventv_badges = self.seventv_badges(&privmsg.sender.id);
+        }
+    }
+
+    fn seventv_enabled(&self, app_handle: &AppHandle) -> bool {
+        if !self.watching_settings.load(Ordering::Relaxed) {
+            self.watch_settings(app_handle);
+        }
+
+        self.seventv_enabled.load(Ordering::Relaxed)
+    }
+
+    /// Reads the setting and watches the settings store for changes to it.
+    /// The store only exists once the frontend has loaded it, so this is
+    /// retried until it succeeds.
+    fn watch_settings(&self, app_handle: &AppHandle) {
+        self.seventv_enabled
+            .store(seventv_setting(app_handle), Ordering::Relaxed);
+
+        let watched = app_handle.svelte().watch("settings", |app_handle| {
+            app_handle
+                .state::<BadgeCatalog>()
+                .seventv_enabled
+                .store(seventv_setting(&app_handle), Ordering::Relaxed);
+
+            Ok(())
+        });
+
+        if watched.is_ok() {
+            self.watching_settings.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Tracks 7TV badges and the users they're assigned to. Badges are cached
+    /// so messages can be resolved before they're received again.
+    pub fn apply_seventv(&self, app_handle: &AppHandle, body: &DispatchBody) {
+        match body {
+            DispatchBody::CosmeticCreate(cosmetic) => {
+                let CosmeticData::Badge(ref data) = cosmetic.data else {
+                    return;
+                };
+
+                let Some(badge) = seventv_badge(&cosmetic.id, data) else {
+                    return;
+                };
+
+                let badges = {
+                    let mut seventv = self.seventv.write().unwrap();
+
+                    // Cosmetics are sent again on every subscription
+                    if seventv.badges.insert(cosmetic.id.clone(), badge).is_some() {
+                        return;
+                    }
+
+                    seventv.badges.clone()
+                };
+
+                cache::write(app_handle, SEVENTV_KEY, &badges, TTL);
+            }
+            DispatchBody::EntitlementCreate(entitlement) => {
+                if !matches!(entitlement.kind, EntitlementKind::Badge) {
+                    return;
+                }
+
+                let Some(twitch) = entitlement
+                    .user
+                    .connections
+                    .iter()
+                    .find(|connection| connection.platform == "TWITCH")
+                else {
+                    return;
+                };
+
+                let mut seventv = self.seventv.write().unwrap();
+                let badges = seventv.users.entry(twitch.id.clone()).or_default();
+
+                if !badges.contains(&entitlement.ref_id) {
+                    badges.push(entitlement.ref_id.clone());
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn seventv_badge(id: &str, data: &BadgeData) -> Option<Badge> {
+    let file = data
+        .host
+        .files
+        .iter()
+        .find(|file| file.name.starts_with("4x"))?;
+
+    Some(Badge {
+        set_id: "7tv".into(),
+        version: id.to_string(),
+        title: data.name.clone(),
+        description: data.tooltip.clone(),
+        image_url: format!("https:{}/{}", data.host.url, file.name),
+    })
+}
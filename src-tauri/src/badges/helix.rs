@@ -0,0 +1,88 @@
+use twitch_api::HelixClient;
+use twitch_api::helix::bits::{self, GetCheermotesRequest};
+use twitch_api::helix::chat::{BadgeSet, GetChannelChatBadgesRequest, GetGlobalChatBadgesRequest};
+use twitch_api::twitch_oauth2::UserToken;
+use twitch_api::types::UserIdRef;
+
+use super::{Badge, Cheermote, CheermoteImage, CheermoteTier};
+use crate::error::Error;
+
+type Helix = HelixClient<'static, reqwest::Client>;
+
+impl From<bits::get_cheermotes::Tiers> for CheermoteTier {
+    fn from(tier: bits::get_cheermotes::Tiers) -> Self {
+        let animated = tier.images.dark.animated;
+
+        let images = [
+            (animated.url_1x, 1.0),
+            (animated.url_1_5x, 1.5),
+            (animated.url_2x, 2.0),
+            (animated.url_3x, 3.0),
+            (animated.url_4x, 4.0),
+        ]
+        .into_iter()
+        .map(|(url, dpi_scale)| CheermoteImage { url, dpi_scale })
+        .collect();
+
+        Self {
+            id: tier.id,
+            bits: tier.min_bits.try_into().unwrap_or_default(),
+            color: tier.color,
+            images,
+        }
+    }
+}
+
+fn into_badges(sets: Vec<BadgeSet>) -> Vec<Badge> {
+    sets.into_iter()
+        .flat_map(|set| {
+            set.versions.into_iter().map(move |version| Badge {
+                set_id: set.set_id.to_string(),
+                version: version.id.to_string(),
+                title: version.title,
+                description: version.description,
+                image_url: version.image_url_4x,
+            })
+        })
+        .collect()
+}
+
+pub async fn global_badges(helix: &Helix, token: &UserToken) -> Result<Vec<Badge>, Error> {
+    let request = GetGlobalChatBadgesRequest::new();
+    let sets = helix.req_get(request, token).await?.data;
+
+    Ok(into_badges(sets))
+}
+
+pub async fn channel_badges(
+    helix: &Helix,
+    token: &UserToken,
+    id: &str,
+) -> Result<Vec<Badge>, Error> {
+    let request = GetChannelChatBadgesRequest::broadcaster_id(UserIdRef::from_str(id));
+    let sets = helix.req_get(request, token).await?.data;
+
+    Ok(into_badges(sets))
+}
+
+/// Fetches the cheermotes usable in a channel, including global cheermotes.
+pub async fn cheermotes(
+    helix: &Helix,
+    token: &UserToken,
+    id: &str,
+) -> Result<Vec<Cheermote>, Error> {
+    let request = GetCheermotesRequest::broadcaster_id(UserIdRef::from_str(id));
+    let cheermotes = helix.req_get(request, token).await?.data;
+
+    Ok(cheermotes
+        .into_iter()
+        .map(|cheermote| Cheermote {
+            prefix: cheermote.prefix,
+            tiers: cheermote
+                .tiers
+                .into_iter()
+                .map(CheermoteTier::from)
+                .collect(),
+        })
+        .collect())
+}
@@ -0,0 +1,78 @@
+pub mod catalog;
+mod helix;
+
+pub use catalog::BadgeCatalog;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::error::Error;
+
+/// A badge in the shape used by the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Badge {
+    pub set_id: String,
+    pub version: String,
+    pub title: String,
+    pub description: String,
+    pub image_url: String,
+}
+
+impl Badge {
+    fn key(&self) -> String {
+        format!("{}:{}", self.set_id, self.version)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheermoteImage {
+    pub url: String,
+    pub dpi_scale: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheermoteTier {
+    pub id: String,
+    /// Minimum number of bits to use the tier.
+    pub bits: u64,
+    pub color: String,
+    /// Animated images of the tier for the dark theme, ordered by pixel
+    /// density.
+    pub images: Vec<CheermoteImage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cheermote {
+    pub prefix: String,
+    pub tiers: Vec<CheermoteTier>,
+}
+
+#[tauri::command]
+pub async fn fetch_global_badges(
+    app_handle: AppHandle,
+    catalog: State<'_, BadgeCatalog>,
+    force: bool,
+) -> Result<Vec<Badge>, Error> {
+    catalog.load_global(&app_handle, force).await
+}
+
+#[tauri::command]
+pub async fn fetch_channel_badges(
+    app_handle: AppHandle,
+    catalog: State<'_, BadgeCatalog>,
+    id: String,
+    force: bool,
+) -> Result<Vec<Badge>, Error> {
+    catalog.load_channel(&app_handle, &id, force).await
+}
+
+#[tauri::command]
+pub async fn fetch_cheermotes(
+    app_handle: AppHandle,
+    catalog: State<'_, BadgeCatalog>,
+    id: String,
+    force: bool,
+) -> Result<Vec<Cheermote>, Error> {
+    catalog.load_cheermotes(&app_handle, &id, force).await
+}
@@ -0,0 +1,82 @@
+//! Entry points for the benchmarks in `benches/`, which can't reach the
+//! private modules of the crate otherwise.
+
+use std::time::{Duration, Instant};
+
+use tauri::ipc::InvokeResponseBody;
+
+use crate::ipc::{Encoding, PayloadOptions};
+use crate::irc::message::{IrcMessage, ServerMessage};
+
+pub struct PayloadReport {
+    pub name: &'static str,
+    pub bytes: usize,
+    pub elapsed: Duration,
+}
+
+const PROFILES: [(&str, PayloadOptions); 4] = [
+    (
+        "json (with raw)",
+        PayloadOptions {
+            raw: true,
+            encoding: Encoding::Json,
+        },
+    ),
+    (
+        "json",
+        PayloadOptions {
+            raw: false,
+            encoding: Encoding::Json,
+        },
+    ),
+    (
+        "msgpack (with raw)",
+        PayloadOptions {
+            raw: true,
+            encoding: Encoding::MessagePack,
+        },
+    ),
+    (
+        "msgpack",
+        PayloadOptions {
+            raw: false,
+            encoding: Encoding::MessagePack,
+        },
+    ),
+];
+
+/// Parses the raw IRC lines in `input` and encodes them `iterations` times in
+/// batches of `batch_size` with every payload profile. The first report is the
+/// profile used before payloads could be slimmed.
+pub fn measure_payloads(input: &str, batch_size: usize, iterations: usize) -> Vec<PayloadReport> {
+    let messages: Vec<ServerMessage> = input
+        .lines()
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| ServerMessage::try_from(IrcMessage::parse(line).ok()?).ok())
+        .collect();
+
+    PROFILES
+        .iter()
+        .map(|(name, options)| {
+            let start = Instant::now();
+            let mut bytes = 0;
+
+            for _ in 0..iterations {
+                bytes = 0;
+
+                for batch in messages.chunks(batch_size) {
+                    bytes += match options.encode_irc(batch).unwrap() {
+                        InvokeResponseBody::Json(json) => json.len(),
+                        InvokeResponseBody::Raw(raw) => raw.len(),
+                    };
+                }
+            }
+
+            PayloadReport {
+                name,
+                bytes,
+                elapsed: start.elapsed() / iterations as u32,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::{Mutex, mpsc};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::error::Error;
+use crate::socket::{self, Socket, SocketHandler};
+
+const BTTV_WS_URI: &str = "wss://sockets.betterttv.net/ws";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Emote {
+    pub id: String,
+    pub code: String,
+    #[serde(default)]
+    pub animated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmoteRef {
+    pub id: String,
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebSocketMessage {
+    name: String,
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmoteCreate {
+    channel: String,
+    emote: Emote,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmoteUpdate {
+    channel: String,
+    emote: EmoteRef,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EmoteDelete {
+    channel: String,
+    emote_id: String,
+}
+
+/// A change to the emotes of a channel, keyed by the Twitch id of the
+/// channel.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum Event {
+    #[serde(rename = "bttv.emote_create")]
+    EmoteCreate { channel_id: String, emote: Emote },
+    #[serde(rename = "bttv.emote_update")]
+    EmoteUpdate { channel_id: String, emote: EmoteRef },
+    #[serde(rename = "bttv.emote_delete")]
+    EmoteDelete {
+        channel_id: String,
+        emote_id: String,
+    },
+}
+
+impl Event {
+    /// Parses a message from the socket, returning `None` for anything other
+    /// than an emote change.
+    fn parse(text: &str) -> Result<Option<Self>, serde_json::Error> {
+        use serde_json::from_value;
+
+        fn channel_id(channel: &str) -> Option<String> {
+            channel.strip_prefix("twitch:").map(str::to_string)
+        }
+
+        let WebSocketMessage { name, data } = serde_json::from_str(text)?;
+
+        let event = match name.as_str() {
+            "emote_create" => {
+                let EmoteCreate { channel, emote } = from_value(data)?;
+
+                channel_id(&channel).map(|channel_id| Self::EmoteCreate { channel_id, emote })
+            }
+            "emote_update" => {
+                let EmoteUpdate { channel, emote } = from_value(data)?;
+
+                channel_id(&channel).map(|channel_id| Self::EmoteUpdate { channel_id, emote })
+            }
+            "emote_delete" => {
+                let EmoteDelete { channel, emote_id } = from_value(data)?;
+
+                channel_id(&channel).map(|channel_id| Self::EmoteDelete {
+                    channel_id,
+                    emote_id,
+                })
+            }
+            _ => None,
+        };
+
+        Ok(event)
+    }
+}
+
+fn room_message(name: &str, id: &str) -> Message {
+    let payload = json!({
+        "name": name,
+        "data": {
+            "name": format!("twitch:{id}")
+        }
+    });
+
+    Message::Text(payload.to_string().into())
+}
+
+pub struct BttvClient {
+    /// Twitch ids of the joined channels keyed by login.
+    channels: Mutex<HashMap<String, String>>,
+    sender: mpsc::UnboundedSender<Event>,
+    socket: Socket,
+}
+
+impl SocketHandler for BttvClient {
+    const NAME: &'static str = "BetterTTV";
+    const URL: &'static str = BTTV_WS_URI;
+
+    fn socket(&self) -> &Socket {
+        &self.socket
+    }
+
+    async fn on_connect(&self) -> Vec<Message> {
+        self.channels
+            .lock()
+            .await
+            .values()
+            .map(|id| room_message("join_channel", id))
+            .collect()
+    }
+
+    async fn on_text(&self, text: &str) -> bool {
+        let event = match Event::parse(text) {
+            Ok(Some(event)) => event,
+            Ok(None) => return true,
+            Err(err) => {
+                tracing::warn!(%err, "Failed to parse BetterTTV message");
+                return true;
+            }
+        };
+
+        if let Err(err) = self.sender.send(event) {
+            tracing::error!(%err, "Error sending payload");
+        }
+
+        true
+    }
+}
+
+impl BttvClient {
+    pub fn new() -> (mpsc::UnboundedReceiver<Event>, Self) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        let client = Self {
+            channels: Mutex::new(HashMap::new()),
+            sender,
+            socket: Socket::new(),
+        };
+
+        (receiver, client)
+    }
+
+    #[tracing::instrument(name = "bttv_connect", skip_all)]
+    pub async fn connect(self: Arc<Self>) -> Result<(), Error> {
+        socket::connect(self).await
+    }
+
+    pub fn connected(&self) -> bool {
+        self.socket.connected()
+    }
+
+    #[tracing::instrument(name = "bttv_subscribe", skip(self))]
+    pub async fn subscribe(&self, channel: &str, id: &str) {
+        self.channels
+            .lock()
+            .await
+            .insert(channel.to_string(), id.to_string());
+
+        self.socket.send(room_message("join_channel", id));
+    }
+
+    pub async fn unsubscribe(&self, channel: &str) {
+        let id = self.channels.lock().await.remove(channel);
+
+        if let Some(id) = id {
+            self.socket.send(room_message("part_channel", &id));
+        }
+    }
+}
@@ -0,0 +1,60 @@
+pub mod client;
+
+use std::sync::Arc;
+
+pub use client::BttvClient;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager, State, Webview, async_runtime};
+use tokio::sync::Mutex;
+
+use crate::AppState;
+use crate::emotes::EmoteRegistry;
+use crate::error::Error;
+
+#[tauri::command]
+pub async fn connect_bttv(
+    app_handle: AppHandle,
+    webview: Webview,
+    state: State<'_, Mutex<AppState>>,
+    channel: Channel,
+) -> Result<(), Error> {
+    let mut state = state.lock().await;
+    let subscribers = state.subscribers.bttv.clone();
+
+    subscribers.attach(webview.label(), channel);
+
+    // The client reconnects on its own, so it's reused even if it's currently
+    // disconnected.
+    if state.bttv.is_some() {
+        tracing::info!("Reusing existing BetterTTV connection");
+        return Ok(());
+    }
+
+    let registry_handle = app_handle.clone();
+    let (mut incoming, client) = BttvClient::new();
+    let client = Arc::new(client);
+
+    state.bttv = Some(client.clone());
+    drop(state);
+
+    async_runtime::spawn(async move {
+        if client.clone().connect().await.is_err() {
+            let state = app_handle.state::<Mutex<AppState>>();
+            let mut state = state.lock().await;
+
+            state.bttv = None;
+        };
+    });
+
+    async_runtime::spawn(async move {
+        while let Some(event) = incoming.recv().await {
+            registry_handle
+                .state::<EmoteRegistry>()
+                .apply_bttv(&registry_handle, &event);
+
+            subscribers.send(event);
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tauri::AppHandle;
+use tauri_plugin_cache::{CacheExt, SetItemOptions};
+
+/// Reads an entry from the cache, treating entries that fail to deserialize
+/// as missing.
+pub fn read<T: DeserializeOwned>(app_handle: &AppHandle, key: &str) -> Option<T> {
+    let value = match app_handle.cache().get(key) {
+        Ok(value) => value?,
+        Err(err) => {
+            tracing::warn!(%err, "Failed to read {key} from cache");
+            return None;
+        }
+    };
+
+    // Entries written by older versions are refetched
+    serde_json::from_value(value).ok()
+}
+
+pub fn write<T: Serialize>(app_handle: &AppHandle, key: &str, value: &T, ttl: Duration) {
+    let options = SetItemOptions {
+        ttl: Some(ttl.as_secs()),
+        ..Default::default()
+    };
+
+    let value = match serde_json::to_value(value) {
+        Ok(value) => value,
+        Err(err) => {
+            tracing::error!(%err, "Failed to serialize {key}");
+            return;
+        }
+    };
+
+    if let Err(err) = app_handle
+        .cache()
+        .set(key.to_string(), value, Some(options))
+    {
+        tracing::warn!(%err, "Failed to write {key} to cache");
+    }
+}
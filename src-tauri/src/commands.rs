@@ -1,24 +1,26 @@
-use serde::Deserialize;
+use serde::Serialize;
 use sysinfo::System;
-use tauri::{AppHandle, Emitter, Manager, async_runtime};
+use tauri::{AppHandle, Emitter, Manager, State, async_runtime};
 use tauri_plugin_cache::CacheExt;
+use tokio::sync::Mutex;
 use tracing::Instrument;
 
-use crate::HTTP;
+use crate::badges::BadgeCatalog;
 use crate::error::Error;
-use crate::irc::message::{IrcMessage, ServerMessage};
-
-#[derive(Debug, Deserialize)]
-struct RecentMessages {
-    #[serde(default)]
-    messages: Vec<String>,
-}
+use crate::history::providers::{
+    self, CachedProvider, HistoryProvider, LocalProvider, RecentMessagesProvider,
+};
+use crate::images::ImageCache;
+use crate::{AppState, eventsub, irc, seventv};
 
 #[tracing::instrument(skip(app_handle))]
 #[tauri::command]
-pub async fn fetch_recent_messages(app_handle: AppHandle, channel: String, limit: u32) {
-    const BASE_URL: &str = "https://recent-messages.robotty.de/api/v2/recent-messages";
-
+pub async fn fetch_recent_messages(
+    app_handle: AppHandle,
+    channel: String,
+    limit: u32,
+    custom_url: Option<String>,
+) {
     // Return early to prevent wakeups
     if limit == 0 {
         tracing::debug!("History limit is 0, skipping request");
@@ -27,49 +29,39 @@ pub async fn fetch_recent_messages(app_handle: AppHandle, channel: String, limit
 
     async_runtime::spawn(
         async move {
-            let response: RecentMessages = HTTP
-                .get(format!("{BASE_URL}/{channel}?limit={limit}",))
-                .send()
-                .await?
-                .json()
-                .await?;
-
-            tracing::info!("Fetched {} recent messages", response.messages.len());
-
-            let server_messages: Vec<_> = response
-                .messages
-                .into_iter()
-                .filter_map(|msg| {
-                    let irc_message = match IrcMessage::parse(&msg) {
-                        Ok(msg) => msg,
-                        Err(err) => {
-                            tracing::warn!(%err, "Failed to parse IRC message");
-                            return None;
-                        }
-                    };
-
-                    match ServerMessage::try_from(irc_message) {
-                        Ok(server_msg) => Some(server_msg),
-                        Err(err) => {
-                            tracing::warn!(%err, "Failed to convert to ServerMessage");
-                            None
-                        }
-                    }
-                })
-                .collect();
+            let mut providers: Vec<Box<dyn HistoryProvider>> = Vec::new();
+
+            if let Some(url) = custom_url.filter(|url| !url.is_empty()) {
+                providers.push(Box::new(CachedProvider::new(
+                    RecentMessagesProvider::custom(url),
+                )));
+            }
+
+            providers.push(Box::new(CachedProvider::new(
+                RecentMessagesProvider::robotty(),
+            )));
+            providers.push(Box::new(LocalProvider::new(app_handle.clone())));
+
+            let mut server_messages = providers::fetch_merged(&providers, &channel, limit).await;
+            let catalog = app_handle.state::<BadgeCatalog>();
+
+            for message in &mut server_messages {
+                catalog.enrich(&app_handle, message);
+            }
+
+            tracing::info!("Fetched {} recent messages", server_messages.len());
 
             app_handle.emit("recentmessages", server_messages).unwrap();
-            Ok::<_, Error>(())
         }
         .in_current_span(),
     );
 }
 
 #[tauri::command]
-pub fn get_cache_size(app_handle: AppHandle) -> i64 {
+pub async fn get_cache_size(app_handle: AppHandle) -> i64 {
     let cache_path = app_handle.cache().get_cache_file_path();
 
-    match std::fs::metadata(cache_path) {
+    let size = match std::fs::metadata(cache_path) {
         Ok(metadata) => {
             let size = metadata.len() as i64;
 
@@ -80,7 +72,14 @@ pub fn get_cache_size(app_handle: AppHandle) -> i64 {
             tracing::error!(%error, "Failed to get cache size");
             0
         }
-    }
+    };
+
+    size + app_handle.state::<ImageCache>().size().await as i64
+}
+
+#[tauri::command]
+pub async fn clear_image_cache(cache: State<'_, ImageCache>) -> Result<(), Error> {
+    cache.clear().await
 }
 
 fn format_bytes(bytes: u64) -> String {
@@ -132,3 +131,48 @@ pub fn get_debug_info(app_handle: AppHandle) -> String {
 
     format!("{app_info}\n{os_info}\n{chip_info}\n{cpu_info}\n{mem_info}\n{wv_info}")
 }
+
+/// Live state of every connection, with `None` for clients that aren't
+/// connected.
+#[derive(Serialize)]
+pub struct ConnectionStatus {
+    irc: Option<irc::client::PoolStatus>,
+    eventsub: Option<eventsub::client::ConnectionStatus>,
+    seventv: Option<seventv::client::ConnectionStatus>,
+}
+
+#[tauri::command]
+pub async fn get_connection_status(
+    state: State<'_, Mutex<AppState>>,
+) -> Result<ConnectionStatus, Error> {
+    let (irc, eventsub, seventv) = {
+        let state = state.lock().await;
+
+        (
+            state.irc.clone(),
+            state.eventsub.clone(),
+            state.seventv.clone(),
+        )
+    };
+
+    let irc = match irc {
+        Some(client) => Some(client.status().await),
+        None => None,
+    };
+
+    let eventsub = match eventsub {
+        Some(client) => Some(client.connection_status().await),
+        None => None,
+    };
+
+    let seventv = match seventv {
+        Some(client) => Some(client.connection_status().await),
+        None => None,
+    };
+
+    Ok(ConnectionStatus {
+        irc,
+        eventsub,
+        seventv,
+    })
+}
@@ -0,0 +1,106 @@
+pub mod providers;
+pub mod registry;
+
+use std::fmt;
+
+pub use registry::EmoteRegistry;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::error::Error;
+
+/// Emote providers, ordered from lowest to highest precedence when emotes in
+/// the same scope share a name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum EmoteProvider {
+    FrankerFaceZ,
+    #[serde(rename = "BetterTTV")]
+    BetterTtv,
+    #[serde(rename = "7TV")]
+    SevenTv,
+    Twitch,
+}
+
+impl fmt::Display for EmoteProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::FrankerFaceZ => "FrankerFaceZ",
+            Self::BetterTtv => "BetterTTV",
+            Self::SevenTv => "7TV",
+            Self::Twitch => "Twitch",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// An emote in the shape used by the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Emote {
+    pub provider: EmoteProvider,
+    pub id: String,
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// Candidate urls for each pixel density, in `srcset` syntax.
+    pub srcset: Vec<String>,
+    /// Whether the emote is overlaid on the previous emote.
+    #[serde(default)]
+    pub zero_width: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChannelEmotes {
+    /// Id of the active 7TV emote set of the channel.
+    pub emote_set_id: Option<String>,
+    pub emotes: Vec<Emote>,
+}
+
+/// A part of a message, either plain text or an emote with the zero-width
+/// emotes overlaid on it.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Fragment {
+    Text {
+        value: String,
+    },
+    Emote {
+        value: String,
+        emote: Emote,
+        layers: Vec<Emote>,
+    },
+}
+
+#[tauri::command]
+pub async fn fetch_global_emotes(
+    app_handle: AppHandle,
+    registry: State<'_, EmoteRegistry>,
+    force: bool,
+) -> Result<Vec<Emote>, Error> {
+    Ok(registry.load_global(&app_handle, force).await)
+}
+
+#[tauri::command]
+pub async fn fetch_channel_emotes(
+    app_handle: AppHandle,
+    registry: State<'_, EmoteRegistry>,
+    id: String,
+    force: bool,
+) -> Result<ChannelEmotes, Error> {
+    Ok(registry.load_channel(&app_handle, &id, force).await)
+}
+
+#[tauri::command]
+pub fn get_channel_emotes(registry: State<'_, EmoteRegistry>, id: String) -> Vec<Emote> {
+    registry.channel_emotes(&id)
+}
+
+#[tauri::command]
+pub fn resolve_emotes(
+    registry: State<'_, EmoteRegistry>,
+    channel_id: String,
+    text: String,
+) -> Vec<Fragment> {
+    registry.resolve(&channel_id, &text)
+}
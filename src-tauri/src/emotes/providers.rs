@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use twitch_api::HelixClient;
+use twitch_api::twitch_oauth2::UserToken;
+use twitch_api::types::UserId;
+
+use super::{Emote, EmoteProvider};
+use crate::error::Error;
+use crate::ffz::client::{EmoteSet, FFZ_API, Room};
+use crate::seventv::message::EmoteChange;
+use crate::{HTTP, bttv, ffz};
+
+const BTTV_API: &str = "https://api.betterttv.net/3";
+const SEVENTV_API: &str = "https://7tv.io/v3";
+const TWITCH_CDN: &str = "https://static-cdn.jtvnw.net/emoticons/v2";
+
+/// BetterTTV emotes that are overlaid on the previous emote.
+const BTTV_ZERO_WIDTH: [&str; 8] = [
+    "CandyCane",
+    "cvHazmat",
+    "cvMask",
+    "IceCold",
+    "ReinDeer",
+    "SantaHat",
+    "SoSnowy",
+    "TopHat",
+];
+
+/// Active emote flag marking an emote as zero-width in a 7TV emote set.
+const SEVENTV_ACTIVE_ZERO_WIDTH: u32 = 1;
+
+/// Emote flag marking an emote as zero-width by default on 7TV.
+const SEVENTV_ZERO_WIDTH: u32 = 1 << 8;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BttvUser {
+    channel_emotes: Vec<bttv::client::Emote>,
+    shared_emotes: Vec<bttv::client::Emote>,
+}
+
+#[derive(Deserialize)]
+struct FfzGlobal {
+    default_sets: Vec<u64>,
+    sets: HashMap<String, EmoteSet>,
+}
+
+#[derive(Deserialize)]
+struct SevenTvSet {
+    id: String,
+    emotes: Option<Vec<EmoteChange>>,
+}
+
+#[derive(Deserialize)]
+struct SevenTvUser {
+    emote_set: Option<SevenTvSet>,
+}
+
+impl From<bttv::client::Emote> for Emote {
+    fn from(emote: bttv::client::Emote) -> Self {
+        let srcset = (1..=3)
+            .map(|n| format!("https://cdn.betterttv.net/emote/{}/{n}x {n}x", emote.id))
+            .collect();
+
+        Self {
+            provider: EmoteProvider::BetterTtv,
+            zero_width: BTTV_ZERO_WIDTH.contains(&emote.code.as_str()),
+            id: emote.id,
+            name: emote.code,
+            width: 28,
+            height: 28,
+            srcset,
+        }
+    }
+}
+
+impl From<ffz::client::Emote> for Emote {
+    fn from(emote: ffz::client::Emote) -> Self {
+        let mut urls: Vec<_> = emote.urls.into_iter().collect();
+        urls.sort_by_key(|(density, _)| density.parse::<u32>().unwrap_or_default());
+
+        Self {
+            provider: EmoteProvider::FrankerFaceZ,
+            id: emote.id.to_string(),
+            name: emote.name,
+            width: emote.width,
+            height: emote.height,
+            srcset: urls
+                .into_iter()
+                .map(|(density, url)| format!("{url} {density}x"))
+                .collect(),
+            zero_width: emote.modifier,
+        }
+    }
+}
+
+/// Converts an emote of a 7TV emote set, returning `None` if it's missing its
+/// data.
+pub fn seventv_emote(emote: &EmoteChange) -> Option<Emote> {
+    let data = emote.data.as_ref()?;
+
+    let files = ["webp", "png", "gif"].into_iter().find_map(|format| {
+        let mut files: Vec<_> = data
+            .host
+            .files
+            .iter()
+            .filter(|file| file.format.eq_ignore_ascii_case(format))
+            .collect();
+
+        files.sort_by_key(|file| file.width);
+        (!files.is_empty()).then_some(files)
+    })?;
+
+    let srcset = files
+        .iter()
+        .map(|file| {
+            let density = file.name.split('.').next().unwrap_or_default();
+            format!("https:{}/{} {density}", data.host.url, file.name)
+        })
+        .collect();
+
+    Some(Emote {
+        provider: EmoteProvider::SevenTv,
+        id: emote.id.clone(),
+        name: emote.name.clone(),
+        width: files[0].width,
+        height: files[0].height,
+        srcset,
+        zero_width: emote.flags & SEVENTV_ACTIVE_ZERO_WIDTH != 0
+            || data.flags & SEVENTV_ZERO_WIDTH != 0,
+    })
+}
+
+fn twitch_emote(id: String, name: String) -> Emote {
+    let srcset = (1..=3)
+        .map(|n| format!("{TWITCH_CDN}/{id}/default/dark/{n}.0 {n}x"))
+        .collect();
+
+    Emote {
+        provider: EmoteProvider::Twitch,
+        id,
+        name,
+        width: 28,
+        height: 28,
+        srcset,
+        zero_width: false,
+    }
+}
+
+/// Fetches a resource, treating a 404 as the channel not having any emotes
+/// with the provider.
+async fn get_optional<T: DeserializeOwned>(url: String) -> Result<Option<T>, Error> {
+    let response = HTTP.get(url).send().await?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    Ok(Some(response.error_for_status()?.json().await?))
+}
+
+async fn get<T: DeserializeOwned>(url: String) -> Result<T, Error> {
+    Ok(HTTP
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+pub async fn twitch_global(
+    helix: &HelixClient<'static, reqwest::Client>,
+    token: &UserToken,
+) -> Result<Vec<Emote>, Error> {
+    let emotes = helix.get_global_emotes(token).await?;
+
+    Ok(emotes
+        .into_iter()
+        .map(|emote| twitch_emote(emote.id.to_string(), emote.name))
+        .collect())
+}
+
+pub async fn twitch_channel(
+    helix: &HelixClient<'static, reqwest::Client>,
+    token: &UserToken,
+    id: &str,
+) -> Result<Vec<Emote>, Error> {
+    let user_id = UserId::from(id.to_string());
+    let emotes = helix.get_channel_emotes_from_id(&user_id, token).await?;
+
+    Ok(emotes
+        .into_iter()
+        .map(|emote| twitch_emote(emote.id.to_string(), emote.name))
+        .collect())
+}
+
+pub async fn ffz_global() -> Result<Vec<Emote>, Error> {
+    let FfzGlobal {
+        default_sets,
+        mut sets,
+    } = get(format!("{FFZ_API}/set/global")).await?;
+
+    Ok(default_sets
+        .into_iter()
+        .filter_map(|id| sets.remove(&id.to_string()))
+        .flat_map(|set| set.emoticons)
+        .map(Emote::from)
+        .collect())
+}
+
+pub async fn ffz_channel(id: &str) -> Result<Vec<Emote>, Error> {
+    let room: Option<Room> = get_optional(format!("{FFZ_API}/room/id/{id}")).await?;
+
+    Ok(room
+        .map(Room::into_emotes)
+        .unwrap_or_default()
+        .into_iter()
+        .map(Emote::from)
+        .collect())
+}
+
+pub async fn bttv_global() -> Result<Vec<Emote>, Error> {
+    let emotes: Vec<bttv::client::Emote> = get(format!("{BTTV_API}/cached/emotes/global")).await?;
+
+    Ok(emotes.into_iter().map(Emote::from).collect())
+}
+
+pub async fn bttv_channel(id: &str) -> Result<Vec<Emote>, Error> {
+    let user: Option<BttvUser> =
+        get_optional(format!("{BTTV_API}/cached/users/twitch/{id}")).await?;
+
+    Ok(user
+        .map(|user| {
+            user.channel_emotes
+                .into_iter()
+                .chain(user.shared_emotes)
+                .map(Emote::from)
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+fn seventv_set(set: SevenTvSet) -> Vec<Emote> {
+    set.emotes
+        .unwrap_or_default()
+        .iter()
+        .filter_map(seventv_emote)
+        .collect()
+}
+
+pub async fn seventv_global() -> Result<Vec<Emote>, Error> {
+    let set = get(format!("{SEVENTV_API}/emote-sets/global")).await?;
+
+    Ok(seventv_set(set))
+}
+
+/// Fetches the active 7TV emote set of a channel along with its id.
+pub async fn seventv_channel(id: &str) -> Result<(Option<String>, Vec<Emote>), Error> {
+    let user: Option<SevenTvUser> =
+        get_optional(format!("{SEVENTV_API}/users/twitch/{id}")).await?;
+
+    let Some(set) = user.and_then(|user| user.emote_set) else {
+        return Ok((None, vec![]));
+    };
+
+    Ok((Some(set.id.clone()), seventv_set(set)))
+}
@@ -0,0 +1,612 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use futures::future::OptionFuture;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_svelte::ManagerExt;
+use tokio::sync::Mutex;
+use twitch_api::HelixClient;
+use twitch_api::twitch_oauth2::UserToken;
+
+use super::providers::{self, seventv_emote};
+use super::{ChannelEmotes, Emote, EmoteProvider, Fragment};
+use crate::error::Error;
+use crate::seventv::message::{ChangeMap, EmoteChange};
+use crate::{AppState, bttv, cache, ffz};
+
+/// How long global emotes are cached for.
+const GLOBAL_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How long the emotes of a channel are cached for. Live updates refresh the
+/// cache in the meantime.
+const CHANNEL_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Whether the provider is enabled in the settings. Twitch emotes are always
+/// loaded.
+fn is_enabled(app_handle: &AppHandle, provider: EmoteProvider) -> bool {
+    let key = match provider {
+        EmoteProvider::FrankerFaceZ => "chat.emotes.ffz",
+        EmoteProvider::BetterTtv => "chat.emotes.bttv",
+        EmoteProvider::SevenTv => "chat.emotes.seventv",
+        EmoteProvider::Twitch => return true,
+    };
+
+    app_handle
+        .svelte()
+        .get_raw("settings", key)
+        .and_then(|value| value.as_bool())
+        .unwrap_or(true)
+}
+
+/// The key emotes of a scope are cached under, which includes the enabled
+/// providers so toggling one doesn't load emotes cached without it.
+fn cache_key(app_handle: &AppHandle, scope: &str) -> String {
+    let providers = [
+        EmoteProvider::FrankerFaceZ,
+        EmoteProvider::BetterTtv,
+        EmoteProvider::SevenTv,
+        EmoteProvider::Twitch,
+    ]
+    .into_iter()
+    .filter(|&provider| is_enabled(app_handle, provider))
+    .map(|provider| provider.to_string())
+    .collect::<Vec<_>>()
+    .join(",");
+
+    format!("emotes:{scope}:{providers}")
+}
+
+/// Twitch emotes can't be fetched without a token, which is treated as a
+/// failure so the previous emotes are kept.
+fn no_token() -> Error {
+    Error::Generic(anyhow!("No access token"))
+}
+
+async fn helix_client(
+    app_handle: &AppHandle,
+) -> (HelixClient<'static, reqwest::Client>, Option<UserToken>) {
+    let state = app_handle.state::<Mutex<AppState>>();
+    let state = state.lock().await;
+
+    (state.helix.clone(), state.token.clone())
+}
+
+/// The emotes available in a scope, grouped by provider.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Emotes {
+    /// Id of the active 7TV emote set of a channel.
+    emote_set_id: Option<String>,
+    providers: BTreeMap<EmoteProvider, Vec<Emote>>,
+    /// Emotes keyed by name after applying precedence rules.
+    #[serde(skip)]
+    resolved: HashMap<String, Emote>,
+    /// The key the emotes are cached under, only set if every enabled
+    /// provider was loaded.
+    #[serde(skip)]
+    cache_key: Option<String>,
+}
+
+impl Emotes {
+    fn rebuild(&mut self) {
+        self.resolved.clear();
+
+        // Providers are iterated from lowest to highest precedence
+        for emotes in self.providers.values() {
+            for emote in emotes {
+                self.resolved.insert(emote.name.clone(), emote.clone());
+            }
+        }
+    }
+
+    /// Replaces the emotes of a provider with the outcome of fetching them,
+    /// keeping the previous emotes if the request failed. Returns whether the
+    /// emotes of the provider are up to date.
+    fn merge(
+        &mut self,
+        provider: EmoteProvider,
+        result: Option<Result<Vec<Emote>, Error>>,
+    ) -> bool {
+        match result {
+            Some(Ok(emotes)) => {
+                self.providers.insert(provider, emotes);
+            }
+            Some(Err(err)) => {
+                tracing::warn!(%err, "Failed to fetch {provider} emotes");
+                return false;
+            }
+            None => {
+                self.providers.remove(&provider);
+            }
+        }
+
+        true
+    }
+
+    /// Caches the emotes if every enabled provider was loaded. Otherwise the
+    /// emotes are fetched again next time instead of persisting the gaps.
+    fn persist(&mut self, app_handle: &AppHandle, key: String, complete: bool, ttl: Duration) {
+        if complete {
+            cache::write(app_handle, &key, self, ttl);
+            self.cache_key = Some(key);
+        } else {
+            tracing::warn!("Not caching {key} since some providers failed");
+            self.cache_key = None;
+        }
+    }
+
+    fn list(&self) -> Vec<Emote> {
+        self.resolved.values().cloned().collect()
+    }
+
+    fn provider(&mut self, provider: EmoteProvider) -> &mut Vec<Emote> {
+        self.providers.entry(provider).or_default()
+    }
+}
+
+/// Global and channel emotes from every provider, used to resolve emotes in
+/// text outside the webview.
+///
+/// Channel emotes take precedence over global emotes. Within the same scope,
+/// Twitch emotes take precedence over 7TV, BetterTTV and then FrankerFaceZ
+/// emotes.
+#[derive(Default)]
+pub struct EmoteRegistry {
+    global: RwLock<Emotes>,
+    /// Emotes keyed by the Twitch id of the channel.
+    channels: RwLock<HashMap<String, Emotes>>,
+}
+
+impl EmoteRegistry {
+    /// Loads the global emotes from the cache or the providers if they aren't
+    /// cached or `force` is set.
+    pub async fn load_global(&self, app_handle: &AppHandle, force: bool) -> Vec<Emote> {
+        let key = cache_key(app_handle, "global");
+
+        let cached = (!force)
+            .then(|| cache::read::<Emotes>(app_handle, &key))
+            .flatten();
+
+        let mut emotes = match cached {
+            Some(mut emotes) => {
+                emotes.cache_key = Some(key);
+                emotes
+            }
+            None => {
+                let mut emotes = self.global.read().unwrap().clone();
+                let enabled = |provider| is_enabled(app_handle, provider);
+
+                let (helix, token) = helix_client(app_handle).await;
+
+                let (twitch, ffz, bttv, seventv) = tokio::join!(
+                    async {
+                        Some(match token {
+                            Some(ref token) => providers::twitch_global(&helix, token).await,
+                            None => Err(no_token()),
+                        })
+                    },
+                    OptionFuture::from(
+                        enabled(EmoteProvider::FrankerFaceZ).then(providers::ffz_global)
+                    ),
+                    OptionFuture::from(
+                        enabled(EmoteProvider::BetterTtv).then(providers::bttv_global)
+                    ),
+                    OptionFuture::from(
+                        enabled(EmoteProvider::SevenTv).then(providers::seventv_global)
+                    ),
+                );
+
+                let complete = [
+                    emotes.merge(EmoteProvider::Twitch, twitch),
+                    emotes.merge(EmoteProvider::FrankerFaceZ, ffz),
+                    emotes.merge(EmoteProvider::BetterTtv, bttv),
+                    emotes.merge(EmoteProvider::SevenTv, seventv),
+                ];
+
+                emotes.persist(app_handle, key, !complete.contains(&false), GLOBAL_TTL);
+                emotes
+            }
+        };
+
+        emotes.rebuild();
+
+        let list = emotes.list();
+        *self.global.write().unwrap() = emotes;
+
+        tracing::info!("Loaded {} global emotes", list.len());
+
+        list
+    }
+
+    /// Loads the emotes of a channel from the cache or the providers if they
+    /// aren't cached or `force` is set.
+    pub async fn load_channel(
+        &self,
+        app_handle: &AppHandle,
+        id: &str,
+        force: bool,
+    ) -> ChannelEmotes {
+        let key = cache_key(app_handle, id);
+
+        let cached = (!force)
+            .then(|| cache::read::<Emotes>(app_handle, &key))
+            .flatten();
+
+        let mut emotes = match cached {
+            Some(mut emotes) => {
+                emotes.cache_key = Some(key);
+                emotes
+            }
+            None => {
+                let mut emotes = self
+                    .channels
+                    .read()
+                    .unwrap()
+                    .get(id)
+                    .cloned()
+                    .unwrap_or_default();
+
+                let enabled = |provider| is_enabled(app_handle, provider);
+
+                let (helix, token) = helix_client(app_handle).await;
+
+                let (twitch, ffz, bttv, seventv) = tokio::join!(
+                    async {
+                        Some(match token {
+                            Some(ref token) => providers::twitch_channel(&helix, token, id).await,
+                            None => Err(no_token()),
+                        })
+                    },
+                    OptionFuture::from(
+                        enabled(EmoteProvider::FrankerFaceZ).then(|| providers::ffz_channel(id))
+                    ),
+                    OptionFuture::from(
+                        enabled(EmoteProvider::BetterTtv).then(|| providers::bttv_channel(id))
+                    ),
+                    OptionFuture::from(
+                        enabled(EmoteProvider::SevenTv).then(|| providers::seventv_channel(id))
+                    ),
+                );
+
+                if seventv.is_none() {
+                    emotes.emote_set_id = None;
+                }
+
+                let seventv = seventv.map(|result| {
+                    result.map(|(set_id, set)| {
+                        emotes.emote_set_id = set_id;
+                        set
+                    })
+                });
+
+                let complete = [
+                    emotes.merge(EmoteProvider::Twitch, twitch),
+                    emotes.merge(EmoteProvider::FrankerFaceZ, ffz),
+                    emotes.merge(EmoteProvider::BetterTtv, bttv),
+                    emotes.merge(EmoteProvider::SevenTv, seventv),
+                ];
+
+                emotes.persist(app_handle, key, !complete.contains(&false), CHANNEL_TTL);
+                emotes
+            }
+        };
+
+        emotes.rebuild();
+
+        let result = ChannelEmotes {
+            emote_set_id: emotes.emote_set_id.clone(),
+            emotes: emotes.list(),
+        };
+
+        self.channels
+            .write()
+            .unwrap()
+            .insert(id.to_string(), emotes);
+
+        tracing::info!("Loaded {} emotes for {id}", result.emotes.len());
+
+        result
+    }
+
+    /// The emotes of a channel, excluding global emotes.
+    pub fn channel_emotes(&self, id: &str) -> Vec<Emote> {
+        self.channels
+            .read()
+            .unwrap()
+            .get(id)
+            .map(Emotes::list)
+            .unwrap_or_default()
+    }
+
+    /// Looks up an emote usable in a channel by name.
+    pub fn get(&self, channel_id: &str, name: &str) -> Option<Emote> {
+        let channel = self
+            .channels
+            .read()
+            .unwrap()
+            .get(channel_id)
+            .and_then(|emotes| emotes.resolved.get(name).cloned());
+
+        channel.or_else(|| self.global.read().unwrap().resolved.get(name).cloned())
+    }
+
+    /// Splits text into fragments of plain text and emotes. Zero-width emotes
+    /// are layered on the preceding emote or shown on their own if there is
+    /// none.
+    pub fn resolve(&self, channel_id: &str, text: &str) -> Vec<Fragment> {
+        fn push_text(fragments: &mut Vec<Fragment>, text: &str) {
+            if let Some(Fragment::Text { value }) = fragments.last_mut() {
+                value.push_str(text);
+            } else {
+                fragments.push(Fragment::Text {
+                    value: text.to_string(),
+                });
+            }
+        }
+
+        let mut fragments = Vec::new();
+
+        for (i, word) in text.split(' ').enumerate() {
+            if i > 0 {
+                push_text(&mut fragments, " ");
+            }
+
+            if word.is_empty() {
+                continue;
+            }
+
+            let Some(emote) = self.get(channel_id, word) else {
+                push_text(&mut fragments, word);
+                continue;
+            };
+
+            if emote.zero_width {
+                let base = fragments.iter_mut().rev().find(|fragment| {
+                    !matches!(fragment, Fragment::Text { value } if value.trim().is_empty())
+                });
+
+                if let Some(Fragment::Emote { layers, .. }) = base {
+                    layers.push(emote);
+                    continue;
+                }
+            }
+
+            fragments.push(Fragment::Emote {
+                value: word.to_string(),
+                emote,
+                layers: vec![],
+            });
+        }
+
+        fragments
+    }
+
+    /// Applies a change to the emotes of a channel and persists them if they
+    /// were cached.
+    fn update(&self, app_handle: &AppHandle, id: &str, update: impl FnOnce(&mut Emotes)) {
+        let emotes = {
+            let mut channels = self.channels.write().unwrap();
+
+            let Some(emotes) = channels.get_mut(id) else {
+                return;
+            };
+
+            update(emotes);
+            emotes.rebuild();
+            emotes.clone()
+        };
+
+        if let Some(ref key) = emotes.cache_key {
+            cache::write(app_handle, key, &emotes, CHANNEL_TTL);
+        }
+    }
+
+    /// Applies an update to the active 7TV emote set of a channel.
+    pub fn apply_seventv(&self, app_handle: &AppHandle, changes: &ChangeMap<EmoteChange>) {
+        let id = self
+            .channels
+            .read()
+            .unwrap()
+            .iter()
+            .find(|(_, emotes)| emotes.emote_set_id.as_ref() == Some(&changes.id))
+            .map(|(id, _)| id.clone());
+
+        let Some(id) = id else {
+            return;
+        };
+
+        self.update(app_handle, &id, |emotes| {
+            let set = emotes.provider(EmoteProvider::SevenTv);
+
+            // Changes may be received again after resubscribing, so pushed
+            // emotes replace any with the same id
+            for change in changes.pushed.iter().flatten() {
+                if let Some(emote) = change.value.as_ref().and_then(seventv_emote) {
+                    set.retain(|existing| existing.id != emote.id);
+                    set.push(emote);
+                }
+            }
+
+            for change in changes.pulled.iter().flatten() {
+                if let Some(ref old) = change.old_value {
+                    set.retain(|emote| emote.id != old.id);
+                }
+            }
+
+            for change in changes.updated.iter().flatten() {
+                let (Some(old), Some(new)) = (&change.old_value, &change.value) else {
+                    continue;
+                };
+
+                let Some(emote) = set.iter_mut().find(|emote| emote.id == old.id) else {
+                    continue;
+                };
+
+                // Updates without emote data only carry the new name
+                match seventv_emote(new) {
+                    Some(updated) => *emote = updated,
+                    None => emote.name = new.name.clone(),
+                }
+            }
+        });
+    }
+
+    pub fn apply_bttv(&self, app_handle: &AppHandle, event: &bttv::client::Event) {
+        use bttv::client::Event;
+
+        match event {
+            Event::EmoteCreate { channel_id, emote } => {
+                self.update(app_handle, channel_id, |emotes| {
+                    emotes
+                        .provider(EmoteProvider::BetterTtv)
+                        .push(emote.clone().into());
+                });
+            }
+            Event::EmoteUpdate { channel_id, emote } => {
+                self.update(app_handle, channel_id, |emotes| {
+                    let set = emotes.provider(EmoteProvider::BetterTtv);
+
+                    if let Some(existing) = set.iter_mut().find(|e| e.id == emote.id) {
+                        existing.name = emote.code.clone();
+                    }
+                });
+            }
+            Event::EmoteDelete {
+                channel_id,
+                emote_id,
+            } => {
+                self.update(app_handle, channel_id, |emotes| {
+                    emotes
+                        .provider(EmoteProvider::BetterTtv)
+                        .retain(|emote| emote.id != *emote_id);
+                });
+            }
+        }
+    }
+
+    pub fn apply_ffz(&self, app_handle: &AppHandle, event: &ffz::client::Event) {
+        let ffz::client::Event::RoomUpdate { channel_id, emotes } = event;
+
+        self.update(app_handle, channel_id, |channel| {
+            *channel.provider(EmoteProvider::FrankerFaceZ) =
+                emotes.iter().cloned().map(Emote::from).collect();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn emote(provider: EmoteProvider, id: &str, name: &str, zero_width: bool) -> Emote {
+        Emote {
+            provider,
+            id: id.to_string(),
+            name: name.to_string(),
+            width: 28,
+            height: 28,
+            srcset: vec![],
+            zero_width,
+        }
+    }
+
+    fn emotes(list: Vec<Emote>) -> Emotes {
+        let mut emotes = Emotes::default();
+
+        for emote in list {
+            emotes.provider(emote.provider).push(emote);
+        }
+
+        emotes.rebuild();
+        emotes
+    }
+
+    fn registry() -> EmoteRegistry {
+        let registry = EmoteRegistry::default();
+
+        *registry.global.write().unwrap() = emotes(vec![
+            emote(EmoteProvider::Twitch, "25", "Kappa", false),
+            emote(EmoteProvider::SevenTv, "7tv-kappa", "Kappa", false),
+            emote(EmoteProvider::FrankerFaceZ, "ffz-lul", "LUL", false),
+            emote(EmoteProvider::BetterTtv, "bttv-lul", "LUL", false),
+            emote(EmoteProvider::SevenTv, "7tv-rain", "RainTime", true),
+        ]);
+
+        registry.channels.write().unwrap().insert(
+            "1".to_string(),
+            emotes(vec![emote(
+                EmoteProvider::FrankerFaceZ,
+                "ffz-kappa",
+                "Kappa",
+                false,
+            )]),
+        );
+
+        registry
+    }
+
+    /// Summarizes fragments as their text or the id of their emote and layers.
+    fn summary(fragments: &[Fragment]) -> Vec<String> {
+        fragments
+            .iter()
+            .map(|fragment| match fragment {
+                Fragment::Text { value } => value.clone(),
+                Fragment::Emote { emote, layers, .. } => std::iter::once(&emote.id)
+                    .chain(layers.iter().map(|layer| &layer.id))
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join("+"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keeps_plain_text() {
+        let registry = registry();
+
+        assert_eq!(
+            summary(&registry.resolve("2", "hello  world ")),
+            ["hello  world "]
+        );
+        assert!(registry.resolve("2", "").is_empty());
+    }
+
+    #[test]
+    fn applies_precedence() {
+        let registry = registry();
+
+        // Twitch emotes win over 7TV, and BetterTTV over FrankerFaceZ
+        assert_eq!(
+            summary(&registry.resolve("2", "Kappa LUL")),
+            ["25", " ", "bttv-lul"]
+        );
+
+        // Channel emotes win over global emotes of any provider
+        assert_eq!(
+            summary(&registry.resolve("1", "hi Kappa")),
+            ["hi ", "ffz-kappa"]
+        );
+    }
+
+    #[test]
+    fn layers_zero_width_emotes() {
+        let registry = registry();
+
+        assert_eq!(
+            summary(&registry.resolve("2", "Kappa RainTime RainTime")),
+            ["25+7tv-rain+7tv-rain", "  "]
+        );
+
+        // Shown on their own without a preceding emote
+        assert_eq!(
+            summary(&registry.resolve("2", "RainTime Kappa")),
+            ["7tv-rain", " ", "25"]
+        );
+        assert_eq!(
+            summary(&registry.resolve("2", "hi RainTime")),
+            ["hi ", "7tv-rain"]
+        );
+    }
+}
@@ -10,12 +10,24 @@ pub enum Error {
     #[error(transparent)]
     Http(#[from] reqwest::Error),
 
+    #[error(transparent)]
+    EventSub(#[from] crate::eventsub::error::Error),
+
     #[error(transparent)]
     Helix(#[from] ClientRequestError<reqwest::Error>),
 
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    MessagePack(#[from] rmp_serde::encode::Error),
+
+    #[error(transparent)]
+    SevenTv(#[from] crate::seventv::error::Error),
+
     #[error(transparent)]
     WebSocket(#[from] tungstenite::Error),
 }
@@ -0,0 +1,375 @@
+use serde_json::json;
+use twitch_api::eventsub::EventType;
+use twitch_api::twitch_oauth2::{Scope, TwitchToken, UserToken};
+
+use super::chat::CHAT_EVENTS;
+
+/// The relationship of the user to the channel being joined.
+///
+/// Roles are ordered so that a higher role can subscribe to anything a lower
+/// one can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Viewer,
+    Moderator,
+    Broadcaster,
+}
+
+/// The shape of the condition an event type is subscribed with.
+#[derive(Debug, Clone, Copy)]
+enum Condition {
+    Broadcaster,
+    User,
+    Moderator,
+    RaidTo,
+    RaidFrom,
+}
+
+struct Entry {
+    event: EventType,
+    role: Role,
+    condition: Condition,
+    /// Groups of scopes that must all be satisfied, where a group is
+    /// satisfied by any one of its scopes.
+    scopes: &'static [&'static [Scope]],
+}
+
+const fn entry(
+    event: EventType,
+    role: Role,
+    condition: Condition,
+    scopes: &'static [&'static [Scope]],
+) -> Entry {
+    Entry {
+        event,
+        role,
+        condition,
+        scopes,
+    }
+}
+
+const USER_CHAT: &[&[Scope]] = &[&[Scope::UserReadChat]];
+
+const REDEMPTIONS: &[&[Scope]] = &[&[
+    Scope::ChannelReadRedemptions,
+    Scope::ChannelManageRedemptions,
+]];
+
+const POLLS: &[&[Scope]] = &[&[Scope::ChannelReadPolls, Scope::ChannelManagePolls]];
+
+const PREDICTIONS: &[&[Scope]] = &[&[
+    Scope::ChannelReadPredictions,
+    Scope::ChannelManagePredictions,
+]];
+
+const HYPE_TRAIN: &[&[Scope]] = &[&[Scope::ChannelReadHypeTrain]];
+
+const SHOUTOUTS: &[&[Scope]] = &[&[
+    Scope::ModeratorReadShoutouts,
+    Scope::ModeratorManageShoutouts,
+]];
+
+const CHARITY: &[&[Scope]] = &[&[Scope::ChannelReadCharity]];
+
+const CHANNEL_MODERATE: &[&[Scope]] = &[
+    &[
+        Scope::ModeratorReadBlockedTerms,
+        Scope::ModeratorManageBlockedTerms,
+    ],
+    &[
+        Scope::ModeratorReadChatSettings,
+        Scope::ModeratorManageChatSettings,
+    ],
+    &[
+        Scope::ModeratorReadUnbanRequests,
+        Scope::ModeratorManageUnbanRequests,
+    ],
+    &[
+        Scope::ModeratorReadBannedUsers,
+        Scope::ModeratorManageBannedUsers,
+    ],
+    &[
+        Scope::ModeratorReadChatMessages,
+        Scope::ModeratorManageChatMessages,
+    ],
+    &[Scope::ModeratorReadWarnings, Scope::ModeratorManageWarnings],
+    &[Scope::ModeratorReadModerators],
+    &[Scope::ModeratorReadVips],
+];
+
+/// Every event type subscribed to when joining a channel, along with who can
+/// subscribe to it and the scopes it requires.
+const CATALOG: &[Entry] = {
+    use {Condition as C, EventType as Ev, Role as R};
+
+    &[
+        // Viewer
+        entry(
+            Ev::ChannelChatUserMessageHold,
+            R::Viewer,
+            C::User,
+            USER_CHAT,
+        ),
+        entry(
+            Ev::ChannelChatUserMessageUpdate,
+            R::Viewer,
+            C::User,
+            USER_CHAT,
+        ),
+        entry(Ev::ChannelUpdate, R::Viewer, C::Broadcaster, &[]),
+        entry(Ev::StreamOffline, R::Viewer, C::Broadcaster, &[]),
+        entry(Ev::StreamOnline, R::Viewer, C::Broadcaster, &[]),
+        entry(Ev::ChannelRaid, R::Viewer, C::RaidTo, &[]),
+        entry(Ev::ChannelRaid, R::Viewer, C::RaidFrom, &[]),
+        // Moderator
+        entry(
+            Ev::AutomodMessageHold,
+            R::Moderator,
+            C::Moderator,
+            &[&[Scope::ModeratorManageAutoMod]],
+        ),
+        entry(
+            Ev::AutomodMessageUpdate,
+            R::Moderator,
+            C::Moderator,
+            &[&[Scope::ModeratorManageAutoMod]],
+        ),
+        entry(
+            Ev::ChannelModerate,
+            R::Moderator,
+            C::Moderator,
+            CHANNEL_MODERATE,
+        ),
+        entry(
+            Ev::ChannelSuspiciousUserMessage,
+            R::Moderator,
+            C::Moderator,
+            &[&[Scope::ModeratorReadSuspiciousUsers]],
+        ),
+        entry(
+            Ev::ChannelSuspiciousUserUpdate,
+            R::Moderator,
+            C::Moderator,
+            &[&[Scope::ModeratorReadSuspiciousUsers]],
+        ),
+        entry(
+            Ev::ChannelUnbanRequestCreate,
+            R::Moderator,
+            C::Moderator,
+            &[&[
+                Scope::ModeratorReadUnbanRequests,
+                Scope::ModeratorManageUnbanRequests,
+            ]],
+        ),
+        entry(
+            Ev::ChannelUnbanRequestResolve,
+            R::Moderator,
+            C::Moderator,
+            &[&[
+                Scope::ModeratorReadUnbanRequests,
+                Scope::ModeratorManageUnbanRequests,
+            ]],
+        ),
+        entry(
+            Ev::ChannelWarningAcknowledge,
+            R::Moderator,
+            C::Moderator,
+            &[&[Scope::ModeratorReadWarnings, Scope::ModeratorManageWarnings]],
+        ),
+        entry(
+            Ev::ChannelFollow,
+            R::Moderator,
+            C::Moderator,
+            &[&[Scope::ModeratorReadFollowers]],
+        ),
+        entry(
+            Ev::ChannelShoutoutCreate,
+            R::Moderator,
+            C::Moderator,
+            SHOUTOUTS,
+        ),
+        entry(
+            Ev::ChannelShoutoutReceive,
+            R::Moderator,
+            C::Moderator,
+            SHOUTOUTS,
+        ),
+        // Broadcaster
+        entry(
+            Ev::ChannelSubscriptionEnd,
+            R::Broadcaster,
+            C::Broadcaster,
+            &[&[Scope::ChannelReadSubscriptions]],
+        ),
+        entry(
+            Ev::ChannelPointsCustomRewardRedemptionAdd,
+            R::Broadcaster,
+            C::Broadcaster,
+            REDEMPTIONS,
+        ),
+        entry(
+            Ev::ChannelPointsAutomaticRewardRedemptionAdd,
+            R::Broadcaster,
+            C::Broadcaster,
+            REDEMPTIONS,
+        ),
+        entry(Ev::ChannelPollBegin, R::Broadcaster, C::Broadcaster, POLLS),
+        entry(
+            Ev::ChannelPollProgress,
+            R::Broadcaster,
+            C::Broadcaster,
+            POLLS,
+        ),
+        entry(Ev::ChannelPollEnd, R::Broadcaster, C::Broadcaster, POLLS),
+        entry(
+            Ev::ChannelPredictionBegin,
+            R::Broadcaster,
+            C::Broadcaster,
+            PREDICTIONS,
+        ),
+        entry(
+            Ev::ChannelPredictionProgress,
+            R::Broadcaster,
+            C::Broadcaster,
+            PREDICTIONS,
+        ),
+        entry(
+            Ev::ChannelPredictionLock,
+            R::Broadcaster,
+            C::Broadcaster,
+            PREDICTIONS,
+        ),
+        entry(
+            Ev::ChannelPredictionEnd,
+            R::Broadcaster,
+            C::Broadcaster,
+            PREDICTIONS,
+        ),
+        entry(
+            Ev::ChannelHypeTrainBegin,
+            R::Broadcaster,
+            C::Broadcaster,
+            HYPE_TRAIN,
+        ),
+        entry(
+            Ev::ChannelHypeTrainProgress,
+            R::Broadcaster,
+            C::Broadcaster,
+            HYPE_TRAIN,
+        ),
+        entry(
+            Ev::ChannelHypeTrainEnd,
+            R::Broadcaster,
+            C::Broadcaster,
+            HYPE_TRAIN,
+        ),
+        entry(
+            Ev::ChannelAdBreakBegin,
+            R::Broadcaster,
+            C::Broadcaster,
+            &[&[Scope::ChannelReadAds]],
+        ),
+        entry(
+            Ev::ChannelCharityCampaignStart,
+            R::Broadcaster,
+            C::Broadcaster,
+            CHARITY,
+        ),
+        entry(
+            Ev::ChannelCharityCampaignProgress,
+            R::Broadcaster,
+            C::Broadcaster,
+            CHARITY,
+        ),
+        entry(
+            Ev::ChannelCharityCampaignStop,
+            R::Broadcaster,
+            C::Broadcaster,
+            CHARITY,
+        ),
+        entry(
+            Ev::ChannelCharityCampaignDonate,
+            R::Broadcaster,
+            C::Broadcaster,
+            CHARITY,
+        ),
+    ]
+};
+
+impl Entry {
+    fn allowed(&self, role: Role, scopes: &[Scope]) -> bool {
+        role >= self.role
+            && self
+                .scopes
+                .iter()
+                .all(|group| group.iter().any(|scope| scopes.contains(scope)))
+    }
+
+    fn condition(&self, broadcaster_id: &str, user_id: &str) -> serde_json::Value {
+        match self.condition {
+            Condition::Broadcaster => json!({ "broadcaster_user_id": broadcaster_id }),
+            Condition::User => json!({
+                "broadcaster_user_id": broadcaster_id,
+                "user_id": user_id
+            }),
+            Condition::Moderator => json!({
+                "broadcaster_user_id": broadcaster_id,
+                "moderator_user_id": user_id
+            }),
+            Condition::RaidTo => json!({ "to_broadcaster_user_id": broadcaster_id }),
+            Condition::RaidFrom => json!({ "from_broadcaster_user_id": broadcaster_id }),
+        }
+    }
+}
+
+fn role_of(token: &UserToken, broadcaster_id: &str, is_mod: bool) -> Role {
+    if broadcaster_id == token.user_id.as_str() {
+        Role::Broadcaster
+    } else if is_mod {
+        Role::Moderator
+    } else {
+        Role::Viewer
+    }
+}
+
+/// Returns the events to subscribe to for a channel along with their
+/// conditions, skipping any the token isn't allowed to subscribe to.
+pub fn events_for(
+    token: &UserToken,
+    broadcaster_id: &str,
+    is_mod: bool,
+) -> Vec<(EventType, serde_json::Value)> {
+    let user_id = token.user_id.as_str();
+    let role = role_of(token, broadcaster_id, is_mod);
+    let scopes = token.scopes();
+
+    CATALOG
+        .iter()
+        .filter(|entry| {
+            let allowed = entry.allowed(role, scopes);
+
+            if !allowed {
+                tracing::debug!("Skipping {} as {role:?}", entry.event);
+            }
+
+            allowed
+        })
+        .map(|entry| (entry.event, entry.condition(broadcaster_id, user_id)))
+        .collect()
+}
+
+/// Returns the chat events to subscribe to for a channel receiving chat
+/// through EventSub, or nothing if the token can't read chat.
+pub fn chat_events_for(
+    token: &UserToken,
+    broadcaster_id: &str,
+) -> Vec<(EventType, serde_json::Value)> {
+    let user_id = token.user_id.as_str();
+
+    CHAT_EVENTS
+        .into_iter()
+        .map(|event| entry(event, Role::Viewer, Condition::User, USER_CHAT))
+        .filter(|entry| entry.allowed(Role::Viewer, token.scopes()))
+        .map(|entry| (entry.event, entry.condition(broadcaster_id, user_id)))
+        .collect()
+}
@@ -0,0 +1,960 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+use serde::Deserialize;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use twitch_api::eventsub::EventType;
+
+use super::client::NotificationPayload;
+use super::event::Event;
+use crate::irc::message::prefix::IrcPrefix;
+use crate::irc::message::{ClearChatAction, IrcMessage, IrcTags, ServerMessage};
+
+/// Chat events subscribed to for channels ingesting chat through EventSub.
+pub const CHAT_EVENTS: [EventType; 4] = [
+    EventType::ChannelChatClear,
+    EventType::ChannelChatMessage,
+    EventType::ChannelChatMessageDelete,
+    EventType::ChannelChatNotification,
+];
+
+/// Id and login IRC uses for the sender of anonymous gifts.
+const ANONYMOUS_GIFTER: (&str, &str, &str) =
+    ("274598607", "ananonymousgifter", "AnAnonymousGifter");
+
+/// Where the chat messages of a channel are received from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatSource {
+    #[default]
+    Irc,
+    EventSub,
+}
+
+/// The logins of the channels receiving chat through EventSub.
+#[derive(Default)]
+pub struct ChatSources(RwLock<HashSet<String>>);
+
+impl ChatSources {
+    pub fn set(&self, channel: &str, source: ChatSource) {
+        let mut channels = self.0.write().unwrap();
+
+        match source {
+            ChatSource::Irc => channels.remove(channel),
+            ChatSource::EventSub => channels.insert(channel.to_string()),
+        };
+    }
+
+    pub fn remove(&self, channel: &str) {
+        self.0.write().unwrap().remove(channel);
+    }
+
+    /// Whether the channel receives chat through EventSub.
+    pub fn contains(&self, channel: &str) -> bool {
+        self.0.read().unwrap().contains(channel)
+    }
+
+    /// Whether a message received over IRC should be used. Chat messages,
+    /// notices, deletions and chat clears are dropped for channels using
+    /// EventSub since they are delivered there instead.
+    ///
+    /// Bans and timeouts are still taken from IRC since
+    /// `channel.chat.clear_user_messages` doesn't say which one occurred.
+    pub fn accepts_irc(&self, message: &ServerMessage) -> bool {
+        let handled = match message {
+            ServerMessage::Privmsg(_)
+            | ServerMessage::UserNotice(_)
+            | ServerMessage::ClearMsg(_) => true,
+            ServerMessage::ClearChat(msg) => msg.action == ClearChatAction::ChatClear,
+            _ => false,
+        };
+
+        if !handled {
+            return true;
+        }
+
+        let channels = self.0.read().unwrap();
+
+        message
+            .channel_login()
+            .is_none_or(|login| !channels.contains(login))
+    }
+}
+
+#[derive(Deserialize)]
+struct ChatBadge {
+    set_id: String,
+    id: String,
+    info: String,
+}
+
+#[derive(Deserialize)]
+struct FragmentEmote {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct Fragment {
+    text: String,
+    emote: Option<FragmentEmote>,
+}
+
+#[derive(Deserialize)]
+struct ChatText {
+    text: String,
+    #[serde(default)]
+    fragments: Vec<Fragment>,
+}
+
+#[derive(Deserialize)]
+struct Cheer {
+    bits: u64,
+}
+
+#[derive(Deserialize)]
+struct ChatReply {
+    parent_message_id: String,
+    parent_message_body: String,
+    parent_user_id: String,
+    parent_user_login: String,
+    parent_user_name: String,
+    thread_message_id: String,
+    thread_user_id: String,
+    thread_user_login: String,
+    thread_user_name: String,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    broadcaster_user_id: String,
+    broadcaster_user_login: String,
+    chatter_user_id: String,
+    chatter_user_login: String,
+    chatter_user_name: String,
+    message_id: String,
+    message: ChatText,
+    message_type: String,
+    badges: Vec<ChatBadge>,
+    cheer: Option<Cheer>,
+    color: String,
+    reply: Option<ChatReply>,
+    source_broadcaster_user_id: Option<String>,
+    source_message_id: Option<String>,
+    source_badges: Option<Vec<ChatBadge>>,
+}
+
+#[derive(Deserialize)]
+struct ChatNotification {
+    broadcaster_user_id: String,
+    broadcaster_user_login: String,
+    broadcaster_user_name: String,
+    chatter_user_id: Option<String>,
+    chatter_user_login: Option<String>,
+    chatter_user_name: Option<String>,
+    chatter_is_anonymous: bool,
+    color: String,
+    badges: Vec<ChatBadge>,
+    system_message: String,
+    message_id: String,
+    message: ChatText,
+    notice_type: String,
+    source_broadcaster_user_id: Option<String>,
+    source_message_id: Option<String>,
+    source_badges: Option<Vec<ChatBadge>>,
+    /// The object describing the notice, keyed by its type.
+    #[serde(flatten)]
+    details: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ChatClear {
+    broadcaster_user_id: String,
+    broadcaster_user_login: String,
+}
+
+#[derive(Deserialize)]
+struct ChatMessageDelete {
+    broadcaster_user_id: String,
+    broadcaster_user_login: String,
+    target_user_login: String,
+    message_id: String,
+}
+
+/// Accumulates the tags of the IRC message an event is converted to.
+#[derive(Default)]
+struct Tags(HashMap<String, String>);
+
+impl Tags {
+    fn set(&mut self, key: &str, value: impl ToString) -> &mut Self {
+        self.0.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    fn set_badges(&mut self, badges: &[ChatBadge]) -> &mut Self {
+        let list = badges
+            .iter()
+            .map(|badge| format!("{}/{}", badge.set_id, badge.id))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        // Only subscriber badges carry info, which is the number of months
+        let info = badges
+            .iter()
+            .filter(|badge| !badge.info.is_empty())
+            .map(|badge| format!("{}/{}", badge.set_id, badge.info))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        self.set("badges", list).set("badge-info", info)
+    }
+
+    fn set_emotes(&mut self, fragments: &[Fragment]) -> &mut Self {
+        let mut emotes = Vec::new();
+        let mut position = 0;
+
+        for fragment in fragments {
+            let length = fragment.text.chars().count();
+
+            if let Some(emote) = &fragment.emote
+                && length > 0
+            {
+                emotes.push(format!(
+                    "{}:{}-{}",
+                    emote.id,
+                    position,
+                    position + length - 1
+                ));
+            }
+
+            position += length;
+        }
+
+        self.set("emotes", emotes.join("/"))
+    }
+
+    fn set_source(
+        &mut self,
+        channel_id: Option<&String>,
+        message_id: Option<&String>,
+        badges: Option<&Vec<ChatBadge>>,
+    ) -> &mut Self {
+        if let (Some(channel_id), Some(message_id)) = (channel_id, message_id) {
+            self.set("source-room-id", channel_id)
+                .set("source-id", message_id);
+
+            if let Some(badges) = badges {
+                let list = badges
+                    .iter()
+                    .map(|badge| format!("{}/{}", badge.set_id, badge.id))
+                    .collect::<Vec<_>>()
+                    .join(",");
+
+                self.set("source-badges", list);
+            }
+        }
+
+        self
+    }
+
+    fn set_user(&mut self, prefix: &str, user: &serde_json::Value) -> &mut Self {
+        let field = |key: &str| user[key].as_str().unwrap_or_default().to_string();
+
+        self.set(&format!("{prefix}-id"), field("user_id"))
+            .set(&format!("{prefix}-user-name"), field("user_login"))
+            .set(&format!("{prefix}-display-name"), field("user_name"))
+    }
+
+    fn into_message(self, command: &str, params: Vec<String>, nick: Option<&str>) -> IrcMessage {
+        IrcMessage {
+            tags: IrcTags(self.0),
+            prefix: nick.map(|nick| IrcPrefix::Full {
+                nick: nick.to_string(),
+                user: Some(nick.to_string()),
+                host: Some(format!("{nick}.tmi.twitch.tv")),
+            }),
+            command: command.to_string(),
+            params,
+        }
+    }
+}
+
+/// Converts the RFC 3339 timestamp of a notification to milliseconds since
+/// the epoch, the format of `tmi-sent-ts`.
+fn timestamp_millis(timestamp: &str) -> i128 {
+    let timestamp =
+        OffsetDateTime::parse(timestamp, &Rfc3339).unwrap_or_else(|_| OffsetDateTime::now_utc());
+
+    timestamp.unix_timestamp_nanos() / 1_000_000
+}
+
+fn sub_plan(details: &serde_json::Value) -> String {
+    if details["is_prime"].as_bool().unwrap_or_default() {
+        "Prime".to_string()
+    } else {
+        details["sub_tier"].as_str().unwrap_or("1000").to_string()
+    }
+}
+
+fn privmsg(event: ChatMessage, timestamp: i128) -> IrcMessage {
+    let mut tags = Tags::default();
+
+    let is_mod = event.badges.iter().any(|badge| badge.set_id == "moderator");
+    let is_subscriber = event
+        .badges
+        .iter()
+        .any(|badge| matches!(badge.set_id.as_str(), "subscriber" | "founder"));
+
+    tags.set("id", &event.message_id)
+        .set("room-id", &event.broadcaster_user_id)
+        .set("user-id", &event.chatter_user_id)
+        .set("display-name", &event.chatter_user_name)
+        .set("color", &event.color)
+        .set("mod", u8::from(is_mod))
+        .set("subscriber", u8::from(is_subscriber))
+        .set("first-msg", u8::from(event.message_type == "user_intro"))
+        .set("tmi-sent-ts", timestamp)
+        .set_badges(&event.badges)
+        .set_emotes(&event.message.fragments)
+        .set_source(
+            event.source_broadcaster_user_id.as_ref(),
+            event.source_message_id.as_ref(),
+            event.source_badges.as_ref(),
+        );
+
+    if event.message_type == "channel_points_highlighted" {
+        tags.set("msg-id", "highlighted-message");
+    }
+
+    if let Some(cheer) = &event.cheer {
+        tags.set("bits", cheer.bits);
+    }
+
+    if let Some(reply) = &event.reply {
+        tags.set("reply-parent-msg-id", &reply.parent_message_id)
+            .set("reply-parent-msg-body", &reply.parent_message_body)
+            .set("reply-parent-user-id", &reply.parent_user_id)
+            .set("reply-parent-user-login", &reply.parent_user_login)
+            .set("reply-parent-display-name", &reply.parent_user_name)
+            .set("reply-thread-parent-msg-id", &reply.thread_message_id)
+            .set("reply-thread-parent-user-id", &reply.thread_user_id)
+            .set("reply-thread-parent-user-login", &reply.thread_user_login)
+            .set("reply-thread-parent-display-name", &reply.thread_user_name);
+    }
+
+    tags.into_message(
+        "PRIVMSG",
+        vec![
+            format!("#{}", event.broadcaster_user_login),
+            event.message.text,
+        ],
+        Some(&event.chatter_user_login),
+    )
+}
+
+/// Maps a notice to the `msg-id` IRC uses for it, setting the `msg-param-*`
+/// tags IRC would include.
+fn notice_params(
+    tags: &mut Tags,
+    event: &ChatNotification,
+    kind: &str,
+    details: &serde_json::Value,
+) -> String {
+    let anonymous =
+        event.chatter_is_anonymous || details["gifter_is_anonymous"].as_bool().unwrap_or_default();
+
+    match kind {
+        "sub" | "resub" => {
+            let cumulative = details["cumulative_months"].as_u64().unwrap_or(1);
+            let streak = details["streak_months"].as_u64();
+
+            tags.set("msg-param-cumulative-months", cumulative)
+                .set("msg-param-should-share-streak", u8::from(streak.is_some()))
+                .set("msg-param-streak-months", streak.unwrap_or_default())
+                .set("msg-param-sub-plan", sub_plan(details))
+                .set(
+                    "msg-param-sub-plan-name",
+                    format!("Channel Subscription ({})", event.broadcaster_user_name),
+                );
+
+            if let Some(duration) = details["duration_months"].as_u64() {
+                tags.set("msg-param-multimonth-duration", duration);
+            }
+
+            kind.to_string()
+        }
+        "sub_gift" => {
+            tags.set_user("msg-param-recipient", &recipient(details))
+                .set("msg-param-sub-plan", sub_plan(details))
+                .set(
+                    "msg-param-sub-plan-name",
+                    format!("Channel Subscription ({})", event.broadcaster_user_name),
+                )
+                // EventSub doesn't include the recipient's tenure
+                .set("msg-param-months", 1)
+                .set(
+                    "msg-param-gift-months",
+                    details["duration_months"].as_u64().unwrap_or(1),
+                )
+                .set(
+                    "msg-param-sender-count",
+                    details["cumulative_total"].as_u64().unwrap_or_default(),
+                );
+
+            if anonymous { "anonsubgift" } else { "subgift" }.to_string()
+        }
+        "community_sub_gift" => {
+            tags.set(
+                "msg-param-mass-gift-count",
+                details["total"].as_u64().unwrap_or_default(),
+            )
+            .set(
+                "msg-param-sender-count",
+                details["cumulative_total"].as_u64().unwrap_or_default(),
+            )
+            .set("msg-param-sub-plan", sub_plan(details));
+
+            if anonymous {
+                "anonsubmysterygift"
+            } else {
+                "submysterygift"
+            }
+            .to_string()
+        }
+        "gift_paid_upgrade" => {
+            if anonymous {
+                return "anongiftpaidupgrade".to_string();
+            }
+
+            tags.set(
+                "msg-param-sender-login",
+                details["gifter_user_login"].as_str().unwrap_or_default(),
+            )
+            .set(
+                "msg-param-sender-name",
+                details["gifter_user_name"].as_str().unwrap_or_default(),
+            );
+
+            "giftpaidupgrade".to_string()
+        }
+        "prime_paid_upgrade" => {
+            tags.set("msg-param-sub-plan", sub_plan(details));
+
+            "primepaidupgrade".to_string()
+        }
+        "raid" => {
+            tags.set(
+                "msg-param-viewerCount",
+                details["viewer_count"].as_u64().unwrap_or_default(),
+            )
+            .set(
+                "msg-param-profileImageURL",
+                details["profile_image_url"].as_str().unwrap_or_default(),
+            );
+
+            "raid".to_string()
+        }
+        "unraid" => "unraid".to_string(),
+        "pay_it_forward" => {
+            tags.set("msg-param-prior-gifter-anonymous", u8::from(anonymous))
+                .set(
+                    "msg-param-prior-gifter-id",
+                    details["gifter_user_id"].as_str().unwrap_or_default(),
+                )
+                .set(
+                    "msg-param-prior-gifter-user-name",
+                    details["gifter_user_login"].as_str().unwrap_or_default(),
+                )
+                .set(
+                    "msg-param-prior-gifter-display-name",
+                    details["gifter_user_name"].as_str().unwrap_or_default(),
+                );
+
+            "communitypayforward".to_string()
+        }
+        "announcement" => {
+            tags.set(
+                "msg-param-color",
+                details["color"]
+                    .as_str()
+                    .unwrap_or("PRIMARY")
+                    .to_uppercase(),
+            );
+
+            "announcement".to_string()
+        }
+        "bits_badge_tier" => {
+            tags.set(
+                "msg-param-threshold",
+                details["tier"].as_u64().unwrap_or_default(),
+            );
+
+            "bitsbadgetier".to_string()
+        }
+        "charity_donation" => {
+            let amount = &details["amount"];
+
+            tags.set(
+                "msg-param-charity-name",
+                details["charity_name"].as_str().unwrap_or_default(),
+            )
+            .set(
+                "msg-param-donation-amount",
+                amount["value"].as_u64().unwrap_or_default(),
+            )
+            .set(
+                "msg-param-donation-currency",
+                amount["currency"].as_str().unwrap_or_default(),
+            )
+            .set(
+                "msg-param-exponent",
+                amount["decimal_place"].as_u64().unwrap_or_default(),
+            );
+
+            "charitydonation".to_string()
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Returns the recipient of a gifted sub in the shape of the other users in
+/// notice details.
+fn recipient(details: &serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "user_id": details["recipient_user_id"],
+        "user_login": details["recipient_user_login"],
+        "user_name": details["recipient_user_name"],
+    })
+}
+
+fn usernotice(event: ChatNotification, timestamp: i128) -> IrcMessage {
+    let mut tags = Tags::default();
+
+    // Notices shared from another channel in a Shared Chat session have their
+    // details under a prefixed key, e.g. `shared_chat_sub`
+    let (shared, kind) = match event.notice_type.strip_prefix("shared_chat_") {
+        Some(kind) => (true, kind),
+        None => (false, event.notice_type.as_str()),
+    };
+
+    let details = event
+        .details
+        .get(&event.notice_type)
+        .cloned()
+        .unwrap_or_default();
+
+    let msg_id = notice_params(&mut tags, &event, kind, &details);
+
+    let (id, login, name) = if kind == "raid" {
+        (
+            details["user_id"].as_str().unwrap_or_default().to_string(),
+            details["user_login"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            details["user_name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        )
+    } else {
+        match (
+            &event.chatter_user_id,
+            &event.chatter_user_login,
+            &event.chatter_user_name,
+        ) {
+            (Some(id), Some(login), Some(name)) if !event.chatter_is_anonymous => {
+                (id.clone(), login.clone(), name.clone())
+            }
+            _ => {
+                let (id, login, name) = ANONYMOUS_GIFTER;
+                (id.to_string(), login.to_string(), name.to_string())
+            }
+        }
+    };
+
+    if shared {
+        tags.set("msg-id", "sharedchatnotice")
+            .set("source-msg-id", msg_id);
+    } else {
+        tags.set("msg-id", msg_id);
+    }
+
+    tags.set("id", &event.message_id)
+        .set("room-id", &event.broadcaster_user_id)
+        .set("user-id", id)
+        .set("login", &login)
+        .set("display-name", name)
+        .set("color", &event.color)
+        .set("system-msg", &event.system_message)
+        .set("tmi-sent-ts", timestamp)
+        .set_badges(&event.badges)
+        .set_emotes(&event.message.fragments)
+        .set_source(
+            event.source_broadcaster_user_id.as_ref(),
+            event.source_message_id.as_ref(),
+            event.source_badges.as_ref(),
+        );
+
+    let mut params = vec![format!("#{}", event.broadcaster_user_login)];
+
+    if !event.message.text.is_empty() {
+        params.push(event.message.text);
+    }
+
+    tags.into_message("USERNOTICE", params, Some(&login))
+}
+
+fn clearchat(event: ChatClear, timestamp: i128) -> IrcMessage {
+    let mut tags = Tags::default();
+
+    tags.set("room-id", &event.broadcaster_user_id)
+        .set("tmi-sent-ts", timestamp);
+
+    tags.into_message(
+        "CLEARCHAT",
+        vec![format!("#{}", event.broadcaster_user_login)],
+        None,
+    )
+}
+
+fn clearmsg(event: ChatMessageDelete, timestamp: i128) -> IrcMessage {
+    let mut tags = Tags::default();
+
+    tags.set("room-id", &event.broadcaster_user_id)
+        .set("login", &event.target_user_login)
+        .set("target-msg-id", &event.message_id)
+        .set("tmi-sent-ts", timestamp);
+
+    // EventSub doesn't include the text of deleted messages
+    tags.into_message(
+        "CLEARMSG",
+        vec![format!("#{}", event.broadcaster_user_login), String::new()],
+        None,
+    )
+}
+
+fn convert<T: for<'de> Deserialize<'de>>(
+    event: &serde_json::Value,
+    timestamp: i128,
+    f: fn(T, i128) -> IrcMessage,
+) -> Result<IrcMessage, serde_json::Error> {
+    T::deserialize(event).map(|event| f(event, timestamp))
+}
+
+/// Normalizes a chat notification into the [`ServerMessage`] IRC would have
+/// delivered for it, returning `None` if the notification isn't a chat event.
+pub fn normalize(payload: &NotificationPayload) -> Option<ServerMessage> {
+    let kind = payload.kind();
+
+    if !CHAT_EVENTS.contains(kind) {
+        return None;
+    }
+
+    let Event::Opaque(event) = payload.event() else {
+        return None;
+    };
+
+    let timestamp = timestamp_millis(payload.timestamp());
+
+    let result = match kind {
+        EventType::ChannelChatMessage => convert(event, timestamp, privmsg),
+        EventType::ChannelChatNotification => convert(event, timestamp, usernotice),
+        EventType::ChannelChatClear => convert(event, timestamp, clearchat),
+        EventType::ChannelChatMessageDelete => convert(event, timestamp, clearmsg),
+        _ => return None,
+    };
+
+    let message = match result {
+        Ok(message) => message,
+        Err(err) => {
+            tracing::warn!(%err, "Failed to read {kind} event");
+            return None;
+        }
+    };
+
+    match ServerMessage::try_from(message) {
+        Ok(message) => Some(message),
+        Err(err) => {
+            tracing::warn!(%err, "Failed to normalize {kind} event");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::irc::message::UserNoticeEvent;
+
+    fn payload(kind: &str, event: Value) -> NotificationPayload {
+        serde_json::from_value(json!({
+            "subscription": {
+                "id": "sub",
+                "type": kind,
+                "version": "1",
+                "condition": {},
+            },
+            "event": event,
+        }))
+        .unwrap()
+    }
+
+    fn badge(set_id: &str, id: &str, info: &str) -> Value {
+        json!({ "set_id": set_id, "id": id, "info": info })
+    }
+
+    fn notification(notice_type: &str, anonymous: bool, details: Value) -> Value {
+        let chatter = |value: &str| if anonymous { Value::Null } else { json!(value) };
+
+        let mut event = json!({
+            "broadcaster_user_id": "1",
+            "broadcaster_user_login": "channel",
+            "broadcaster_user_name": "Channel",
+            "chatter_user_id": chatter("2"),
+            "chatter_user_login": chatter("chatter"),
+            "chatter_user_name": chatter("Chatter"),
+            "chatter_is_anonymous": anonymous,
+            "color": "#FF0000",
+            "badges": [badge("subscriber", "12", "14")],
+            "system_message": "system message",
+            "message_id": "notice-id",
+            "message": { "text": "", "fragments": [] },
+            "notice_type": notice_type,
+            "source_broadcaster_user_id": null,
+            "source_message_id": null,
+            "source_badges": null,
+        });
+
+        event[notice_type] = details;
+        event
+    }
+
+    fn tag<'a>(message: &'a ServerMessage, key: &str) -> Option<&'a str> {
+        message.raw().tags.0.get(key).map(String::as_str)
+    }
+
+    #[test]
+    fn converts_messages_with_emote_ranges() {
+        let message = normalize(&payload(
+            "channel.chat.message",
+            json!({
+                "broadcaster_user_id": "1",
+                "broadcaster_user_login": "channel",
+                "chatter_user_id": "2",
+                "chatter_user_login": "chatter",
+                "chatter_user_name": "Chatter",
+                "message_id": "message-id",
+                "message": {
+                    "text": "hi Kappa 🎉 Kappa",
+                    "fragments": [
+                        { "type": "text", "text": "hi ", "emote": null },
+                        { "type": "emote", "text": "Kappa", "emote": { "id": "25" } },
+                        { "type": "text", "text": " 🎉 ", "emote": null },
+                        { "type": "emote", "text": "Kappa", "emote": { "id": "25" } },
+                    ],
+                },
+                "message_type": "text",
+                "badges": [badge("moderator", "1", ""), badge("subscriber", "12", "14")],
+                "cheer": null,
+                "color": "#00FF00",
+                "reply": null,
+                "source_broadcaster_user_id": null,
+                "source_message_id": null,
+                "source_badges": null,
+            }),
+        ))
+        .unwrap();
+
+        assert_eq!(tag(&message, "emotes"), Some("25:3-7/25:11-15"));
+        assert_eq!(tag(&message, "badges"), Some("moderator/1,subscriber/12"));
+        assert_eq!(tag(&message, "badge-info"), Some("subscriber/14"));
+        assert_eq!(tag(&message, "mod"), Some("1"));
+
+        let ServerMessage::Privmsg(msg) = message else {
+            panic!("expected a PRIVMSG");
+        };
+
+        assert_eq!(msg.channel_login, "channel");
+        assert_eq!(msg.sender.login, "chatter");
+        assert_eq!(msg.message_text, "hi Kappa 🎉 Kappa");
+
+        // Ranges count characters rather than bytes, like IRC
+        let ranges: Vec<_> = msg.emotes.iter().map(|e| e.range.clone()).collect();
+        assert_eq!(ranges, [3..8, 11..16]);
+        assert!(msg.emotes.iter().all(|e| e.id == "25" && e.code == "Kappa"));
+    }
+
+    #[test]
+    fn maps_resubs() {
+        let message = normalize(&payload(
+            "channel.chat.notification",
+            notification(
+                "resub",
+                false,
+                json!({
+                    "cumulative_months": 14,
+                    "streak_months": null,
+                    "duration_months": 1,
+                    "sub_tier": "2000",
+                    "is_prime": false,
+                    "is_gift": false,
+                }),
+            ),
+        ))
+        .unwrap();
+
+        assert_eq!(tag(&message, "msg-id"), Some("resub"));
+        assert_eq!(tag(&message, "msg-param-should-share-streak"), Some("0"));
+
+        let ServerMessage::UserNotice(msg) = message else {
+            panic!("expected a USERNOTICE");
+        };
+
+        assert_eq!(msg.sender.login, "chatter");
+        assert_eq!(msg.message_text, None);
+        assert!(matches!(
+            msg.event,
+            UserNoticeEvent::SubOrResub {
+                is_resub: true,
+                cumulative_months: 14,
+                ref sub_plan,
+                ..
+            } if sub_plan == "2000"
+        ));
+    }
+
+    #[test]
+    fn maps_anonymous_gifts() {
+        let message = normalize(&payload(
+            "channel.chat.notification",
+            notification(
+                "sub_gift",
+                true,
+                json!({
+                    "duration_months": 3,
+                    "cumulative_total": null,
+                    "recipient_user_id": "3",
+                    "recipient_user_login": "recipient",
+                    "recipient_user_name": "Recipient",
+                    "sub_tier": "1000",
+                    "community_gift_id": null,
+                }),
+            ),
+        ))
+        .unwrap();
+
+        assert_eq!(tag(&message, "msg-id"), Some("anonsubgift"));
+
+        let ServerMessage::UserNotice(msg) = message else {
+            panic!("expected a USERNOTICE");
+        };
+
+        assert_eq!(msg.sender.login, ANONYMOUS_GIFTER.1);
+        assert!(matches!(
+            msg.event,
+            UserNoticeEvent::SubGift {
+                num_gifted_months: 3,
+                ref recipient,
+                ..
+            } if recipient.login == "recipient"
+        ));
+    }
+
+    #[test]
+    fn maps_raids_to_the_raider() {
+        let message = normalize(&payload(
+            "channel.chat.notification",
+            notification(
+                "raid",
+                false,
+                json!({
+                    "user_id": "4",
+                    "user_login": "raider",
+                    "user_name": "Raider",
+                    "viewer_count": 42,
+                    "profile_image_url": "https://example.com/raider.png",
+                }),
+            ),
+        ))
+        .unwrap();
+
+        let ServerMessage::UserNotice(msg) = message else {
+            panic!("expected a USERNOTICE");
+        };
+
+        assert_eq!(msg.sender.login, "raider");
+        assert!(matches!(
+            msg.event,
+            UserNoticeEvent::Raid {
+                viewer_count: 42,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn maps_shared_chat_notices() {
+        let message = normalize(&payload(
+            "channel.chat.notification",
+            notification(
+                "shared_chat_announcement",
+                false,
+                json!({ "color": "blue" }),
+            ),
+        ))
+        .unwrap();
+
+        assert_eq!(tag(&message, "msg-id"), Some("sharedchatnotice"));
+        assert_eq!(tag(&message, "source-msg-id"), Some("announcement"));
+        assert_eq!(tag(&message, "msg-param-color"), Some("BLUE"));
+    }
+
+    #[test]
+    fn converts_deletions_and_clears() {
+        let deleted = normalize(&payload(
+            "channel.chat.message_delete",
+            json!({
+                "broadcaster_user_id": "1",
+                "broadcaster_user_login": "channel",
+                "broadcaster_user_name": "Channel",
+                "target_user_id": "2",
+                "target_user_login": "chatter",
+                "target_user_name": "Chatter",
+                "message_id": "message-id",
+            }),
+        ))
+        .unwrap();
+
+        let ServerMessage::ClearMsg(msg) = deleted else {
+            panic!("expected a CLEARMSG");
+        };
+
+        assert_eq!(msg.message_id, "message-id");
+        assert_eq!(msg.sender_login, "chatter");
+
+        let cleared = normalize(&payload(
+            "channel.chat.clear",
+            json!({
+                "broadcaster_user_id": "1",
+                "broadcaster_user_login": "channel",
+                "broadcaster_user_name": "Channel",
+            }),
+        ))
+        .unwrap();
+
+        assert!(matches!(
+            cleared,
+            ServerMessage::ClearChat(ref msg) if msg.action == ClearChatAction::ChatClear
+        ));
+    }
+
+    #[test]
+    fn ignores_other_events() {
+        let follow = payload("channel.follow", json!({}));
+
+        assert!(normalize(&follow).is_none());
+    }
+}
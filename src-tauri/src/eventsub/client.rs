@@ -1,45 +1,91 @@
-use std::collections::HashMap;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::anyhow;
 use futures::future::join_all;
 use futures::{SinkExt, StreamExt};
+use reqwest::StatusCode;
 use serde::de::{DeserializeOwned, Error as DeError};
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::json;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 use tokio::net::TcpStream;
-use tokio::sync::{Mutex, mpsc};
+use tokio::sync::{Mutex, broadcast, mpsc, watch};
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::tungstenite::protocol::CloseFrame;
 use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
 use tracing::Instrument;
-use twitch_api::HelixClient;
 use twitch_api::eventsub::{EventSubSubscription, EventType};
 use twitch_api::twitch_oauth2::{TwitchToken, UserToken};
 
+use super::dedup::SeenMessages;
+use super::error::Error as EventSubError;
+use super::event::Event;
 use crate::HTTP;
-use crate::api::Response;
 use crate::error::Error;
 
-#[cfg(local)]
-const TWITCH_EVENTSUB_WS_URI: &str = "ws://127.0.0.1:8080/ws";
-#[cfg(local)]
-const TWITCH_EVENTSUB_ENDPOINT: &str = "http://127.0.0.1:8080/eventsub/subscriptions";
-
-#[cfg(not(local))]
 const TWITCH_EVENTSUB_WS_URI: &str = "wss://eventsub.wss.twitch.tv/ws";
-#[cfg(not(local))]
 const TWITCH_EVENTSUB_ENDPOINT: &str = "https://api.twitch.tv/helix/eventsub/subscriptions";
 
-const V2_EVENTS: [EventType; 4] = [
+/// Endpoints used by the Twitch CLI's mock EventSub server.
+const LOCAL_EVENTSUB_WS_URI: &str = "ws://127.0.0.1:8080/ws";
+const LOCAL_EVENTSUB_ENDPOINT: &str = "http://127.0.0.1:8080/eventsub/subscriptions";
+
+/// Maximum number of enabled subscriptions on a single WebSocket session.
+const MAX_SUBSCRIPTIONS_PER_SESSION: usize = 300;
+
+/// Maximum number of WebSocket sessions open at once for the same user.
+const MAX_SESSIONS: usize = 3;
+
+/// How long to wait for a session to be welcomed before subscribing to it.
+const WELCOME_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long message ids are remembered for and the age after which messages
+/// are dropped as stale.
+const MESSAGE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Maximum number of message ids remembered at once.
+const MAX_SEEN_MESSAGES: usize = 10_000;
+
+/// Number of times a subscription is attempted if it fails transiently.
+const MAX_SUBSCRIBE_ATTEMPTS: u32 = 3;
+
+/// Longest time to wait for a rate limit to reset before retrying, in seconds.
+const MAX_RATELIMIT_WAIT: u64 = 60;
+
+/// Extra time allowed on top of the negotiated keepalive timeout before a
+/// session is considered dead.
+const KEEPALIVE_GRACE: Duration = Duration::from_secs(2);
+
+/// Number of times to try reconnecting a dead session before giving up.
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+const V2_EVENTS: [EventType; 8] = [
     EventType::AutomodMessageHold,
     EventType::AutomodMessageUpdate,
+    EventType::ChannelFollow,
+    EventType::ChannelHypeTrainBegin,
+    EventType::ChannelHypeTrainEnd,
+    EventType::ChannelHypeTrainProgress,
     EventType::ChannelModerate,
     EventType::ChannelPointsAutomaticRewardRedemptionAdd,
 ];
 
+fn request_error(err: reqwest::Error) -> EventSubError {
+    EventSubError::Request {
+        message: err.to_string(),
+    }
+}
+
+/// The version used when subscribing to the event type.
+fn version_of(event: EventType) -> &'static str {
+    if V2_EVENTS.contains(&event) { "2" } else { "1" }
+}
+
 #[derive(Debug, Deserialize)]
 enum MessageType {
     #[serde(rename = "session_welcome")]
@@ -56,7 +102,9 @@ enum MessageType {
 
 #[derive(Debug, Deserialize)]
 pub struct MessageMetadata {
+    message_id: String,
     message_type: MessageType,
+    message_timestamp: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -64,12 +112,40 @@ pub struct Subscription {
     pub id: String,
     #[serde(rename = "type")]
     kind: EventType,
+    version: String,
     condition: serde_json::Value,
+    #[serde(default)]
+    cost: u64,
+    /// Index of the session the subscription was created on.
+    #[serde(skip)]
+    session: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedSubscription {
+    data: (EventSubSubscription,),
+    total: usize,
+    total_cost: u64,
+    max_total_cost: u64,
+}
+
+/// The outcome of subscribing to a single event.
+#[derive(Debug, Serialize)]
+pub struct SubscriptionResult {
+    pub event: EventType,
+    pub error: Option<EventSubError>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct HelixError {
+    #[serde(default)]
+    message: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct WebSocketSession {
     id: String,
+    keepalive_timeout_seconds: Option<u64>,
     reconnect_url: Option<String>,
 }
 
@@ -78,15 +154,92 @@ pub struct SessionPayload {
     session: WebSocketSession,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize)]
 pub struct NotificationPayload {
+    /// Id of the message the notification was delivered in, which is the same
+    /// across redeliveries.
+    id: String,
+    /// When the message was sent by Twitch, in RFC 3339 format.
+    timestamp: String,
     subscription: Subscription,
-    event: serde_json::Value,
+    event: Event,
+}
+
+impl<'de> Deserialize<'de> for NotificationPayload {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawNotification {
+            subscription: Subscription,
+            event: serde_json::Value,
+        }
+
+        let RawNotification {
+            subscription,
+            event,
+        } = RawNotification::deserialize(deserializer)?;
+
+        // Filled in from the metadata of the message containing the payload
+        Ok(Self {
+            id: String::new(),
+            timestamp: String::new(),
+            event: Event::parse(&subscription.kind, &subscription.version, event),
+            subscription,
+        })
+    }
+}
+
+impl NotificationPayload {
+    pub fn kind(&self) -> &EventType {
+        &self.subscription.kind
+    }
+
+    pub fn timestamp(&self) -> &str {
+        &self.timestamp
+    }
+
+    pub fn event(&self) -> &Event {
+        &self.event
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct RevocationPayload {
-    pub subscription: Subscription,
+    pub subscription: RevokedSubscription,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevokedSubscription {
+    pub id: String,
+    #[serde(rename = "type")]
+    kind: EventType,
+    status: RevocationReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationReason {
+    /// The user revoked the authorization token the subscription relied on
+    AuthorizationRevoked,
+    /// The user in the condition no longer exists
+    UserRemoved,
+    /// The user is no longer a moderator of the broadcaster in the condition
+    ModeratorRemoved,
+    /// The subscription type and version are no longer supported
+    VersionRemoved,
+    #[serde(other)]
+    Unknown,
+}
+
+impl RevocationReason {
+    /// Whether subscribing again could succeed. Subscriptions revoked because
+    /// of a removed version are retried with the version currently used for
+    /// the type, if it differs.
+    fn is_retryable(&self) -> bool {
+        matches!(self, Self::VersionRemoved | Self::Unknown)
+    }
 }
 
 #[derive(Debug)]
@@ -123,53 +276,289 @@ impl<'de> Deserialize<'de> for WebSocketMessage {
         match message.metadata.message_type {
             MessageType::Welcome => Ok(Self::Welcome(parse_payload(payload)?)),
             MessageType::Keepalive => Ok(Self::Keepalive),
-            MessageType::Notification => Ok(Self::Notification(parse_payload(payload)?)),
+            MessageType::Notification => {
+                let mut notification: NotificationPayload = parse_payload(payload)?;
+
+                notification.id = message.metadata.message_id;
+                notification.timestamp = message.metadata.message_timestamp;
+
+                Ok(Self::Notification(notification))
+            }
             MessageType::Reconnect => Ok(Self::Reconnect(parse_payload(payload)?)),
             MessageType::Revocation => Ok(Self::Revocation(parse_payload(payload)?)),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconnectReason {
+    /// Twitch asked for the session to move to a new connection
+    Requested,
+    /// No message was received within the keepalive timeout
+    KeepaliveTimeout,
+    /// The connection errored
+    Error,
+}
+
+/// Where to connect to EventSub and manage subscriptions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Endpoints {
+    pub websocket: String,
+    pub subscriptions: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            websocket: TWITCH_EVENTSUB_WS_URI.into(),
+            subscriptions: TWITCH_EVENTSUB_ENDPOINT.into(),
+        }
+    }
+}
+
+impl Endpoints {
+    /// The Twitch endpoints, or those of the Twitch CLI if `USE_LOCAL_EVENTSUB`
+    /// is set to `1`.
+    pub fn from_env() -> Self {
+        if std::env::var("USE_LOCAL_EVENTSUB").is_ok_and(|value| value == "1") {
+            Self {
+                websocket: LOCAL_EVENTSUB_WS_URI.into(),
+                subscriptions: LOCAL_EVENTSUB_ENDPOINT.into(),
+            }
+        } else {
+            Self::default()
+        }
+    }
+}
+
+/// Changes in the state of a session, forwarded to the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Status {
+    Connected {
+        session: usize,
+    },
+    Reconnecting {
+        session: usize,
+        reason: ReconnectReason,
+        attempt: u32,
+    },
+    Restored {
+        session: usize,
+        subscriptions: usize,
+    },
+    Disconnected {
+        session: usize,
+    },
+    Revoked {
+        session: usize,
+        channel: String,
+        event: EventType,
+        condition: serde_json::Value,
+        reason: RevocationReason,
+        resubscribed: bool,
+    },
+}
+
+/// A single WebSocket connection to EventSub. Each session can only hold a
+/// limited number of subscriptions, so more are opened as they fill up.
+struct Session {
+    index: usize,
+    id: watch::Sender<Option<String>>,
+    connected: AtomicBool,
+    reconnecting: AtomicBool,
+    /// Keepalive timeout negotiated in the welcome message, in seconds.
+    keepalive: AtomicU64,
+    /// Number of subscriptions created or being created on the session.
+    subscriptions: AtomicUsize,
+    cost: AtomicU64,
+    /// Unix timestamp in milliseconds of the last message received, or 0.
+    last_message: AtomicU64,
+}
+
+impl Session {
+    fn new(index: usize) -> Self {
+        Self {
+            index,
+            id: watch::Sender::new(None),
+            connected: AtomicBool::default(),
+            reconnecting: AtomicBool::default(),
+            keepalive: AtomicU64::default(),
+            subscriptions: AtomicUsize::default(),
+            cost: AtomicU64::default(),
+            last_message: AtomicU64::default(),
+        }
+    }
+
+    /// Reserves a slot for a subscription, returning `false` if the session is
+    /// full.
+    fn try_reserve(&self) -> bool {
+        self.subscriptions
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                (count < MAX_SUBSCRIPTIONS_PER_SESSION).then_some(count + 1)
+            })
+            .is_ok()
+    }
+
+    /// The longest time allowed between two messages before the session is
+    /// considered dead, if known.
+    fn keepalive_timeout(&self) -> Option<Duration> {
+        match self.keepalive.load(Ordering::Relaxed) {
+            0 => None,
+            secs => Some(Duration::from_secs(secs) + KEEPALIVE_GRACE),
+        }
+    }
+
+    fn release(&self, cost: u64) {
+        let _ = self
+            .subscriptions
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                Some(count.saturating_sub(1))
+            });
+
+        let _ = self
+            .cost
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |total| {
+                Some(total.saturating_sub(cost))
+            });
+    }
+
+    async fn wait_for_id(&self) -> Result<String, EventSubError> {
+        let mut receiver = self.id.subscribe();
+
+        let id = tokio::time::timeout(WELCOME_TIMEOUT, receiver.wait_for(Option::is_some))
+            .await
+            .map_err(|_| EventSubError::NotConnected)?
+            .map_err(|_| EventSubError::NotConnected)?;
+
+        Ok(id.clone().unwrap())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+struct Cost {
+    total: usize,
+    total_cost: u64,
+    max_total_cost: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionUsage {
+    id: Option<String>,
+    connected: bool,
+    subscriptions: usize,
+    cost: u64,
+    last_message_at: Option<u64>,
+}
+
+/// Subscription usage reported by Helix along with the usage of each session.
+#[derive(Debug, Serialize)]
+pub struct Usage {
+    #[serde(flatten)]
+    cost: Cost,
+    sessions: Vec<SessionUsage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubscriptionStatus {
+    id: String,
+    #[serde(rename = "type")]
+    kind: EventType,
+    version: String,
+    cost: u64,
+    session: usize,
+}
+
+/// Usage of the client along with the live subscriptions of each channel.
+#[derive(Debug, Serialize)]
+pub struct ConnectionStatus {
+    #[serde(flatten)]
+    usage: Usage,
+    channels: BTreeMap<String, Vec<SubscriptionStatus>>,
+}
+
 pub struct EventSubClient {
-    helix: Arc<HelixClient<'static, reqwest::Client>>,
-    pub token: Arc<UserToken>,
-    session_id: Mutex<Option<String>>,
+    this: Weak<Self>,
+    endpoints: Endpoints,
+    token: std::sync::RwLock<Arc<UserToken>>,
+    sessions: std::sync::Mutex<Vec<Arc<Session>>>,
     pub subscriptions: Mutex<HashMap<String, Subscription>>,
+    cost: std::sync::Mutex<Cost>,
+    seen: std::sync::Mutex<SeenMessages>,
     sender: mpsc::UnboundedSender<NotificationPayload>,
-    connected: AtomicBool,
-    reconnecting: AtomicBool,
+    status: broadcast::Sender<Status>,
 }
 
 type Stream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
 impl EventSubClient {
     pub fn new(
-        helix: Arc<HelixClient<'static, reqwest::Client>>,
+        endpoints: Endpoints,
         token: Arc<UserToken>,
-    ) -> (mpsc::UnboundedReceiver<NotificationPayload>, Self) {
+    ) -> (mpsc::UnboundedReceiver<NotificationPayload>, Arc<Self>) {
         let (sender, receiver) = mpsc::unbounded_channel::<NotificationPayload>();
 
-        let client = Self {
-            helix,
-            token,
-            session_id: Mutex::new(None),
+        let client = Arc::new_cyclic(|this| Self {
+            this: this.clone(),
+            endpoints,
+            token: std::sync::RwLock::new(token),
+            sessions: std::sync::Mutex::new(Vec::new()),
             subscriptions: Mutex::new(HashMap::new()),
+            cost: std::sync::Mutex::new(Cost::default()),
+            seen: std::sync::Mutex::new(SeenMessages::new(MESSAGE_TTL, MAX_SEEN_MESSAGES)),
             sender,
-            connected: AtomicBool::default(),
-            reconnecting: AtomicBool::default(),
-        };
+            status: broadcast::Sender::new(16),
+        });
 
         (receiver, client)
     }
 
-    #[tracing::instrument(name = "eventsub_connect", skip_all)]
+    /// The token subscriptions are created with.
+    pub fn token(&self) -> Arc<UserToken> {
+        self.token.read().unwrap().clone()
+    }
+
+    /// Replaces the token used for creating and deleting subscriptions.
+    /// Existing subscriptions stay active as long as the user's authorization
+    /// isn't revoked.
+    pub fn set_token(&self, token: Arc<UserToken>) {
+        *self.token.write().unwrap() = token;
+    }
+
+    /// Subscribes to changes in the state of every session.
+    pub fn status(&self) -> broadcast::Receiver<Status> {
+        self.status.subscribe()
+    }
+
+    fn emit_status(&self, status: Status) {
+        // Only fails if nothing is listening
+        let _ = self.status.send(status);
+    }
+
+    /// Opens the first session. Additional sessions are opened once it has
+    /// reached the subscription limit.
     pub async fn connect(self: Arc<Self>) -> Result<(), Error> {
+        let session = Arc::new(Session::new(0));
+
+        self.sessions.lock().unwrap().push(session.clone());
+        self.spawn_session(session);
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "eventsub_connect", skip_all, fields(session = session.index))]
+    fn spawn_session(&self, session: Arc<Session>) {
+        let Some(this) = self.this.upgrade() else {
+            return;
+        };
+
         tokio::spawn(
             async move {
-                let ws_uri = TWITCH_EVENTSUB_WS_URI.to_string();
-                tracing::info!("Connecting to EventSub");
+                tracing::info!("Connecting to EventSub at {}", this.endpoints.websocket);
 
-                let stream = match connect_async(&ws_uri).await {
+                let stream = match connect_async(&this.endpoints.websocket).await {
                     Ok((stream, _)) => stream,
                     Err(err) => {
                         tracing::error!(%err, "Failed to connect to EventSub");
@@ -178,41 +567,98 @@ impl EventSubClient {
                 };
 
                 tracing::info!("Connected to EventSub");
-                self.set_connected(true);
+                session.connected.store(true, Ordering::Relaxed);
+                this.emit_status(Status::Connected {
+                    session: session.index,
+                });
 
-                let _ = self.clone().process_stream(stream).await;
+                if let Err(err) = this.process_stream(&session, stream).await {
+                    tracing::error!(%err, "EventSub session failed");
+                }
 
-                self.set_connected(false);
-                *self.session_id.lock().await = None;
+                session.connected.store(false, Ordering::Relaxed);
+                session.id.send_replace(None);
+                this.emit_status(Status::Disconnected {
+                    session: session.index,
+                });
 
                 Ok(())
             }
             .in_current_span(),
         );
-
-        Ok(())
     }
 
-    async fn process_stream(self: Arc<Self>, mut stream: Stream) -> Result<(), Error> {
+    async fn process_stream(
+        &self,
+        session: &Arc<Session>,
+        mut stream: Stream,
+    ) -> Result<(), Error> {
         loop {
-            match stream.next().await {
+            let next = match session.keepalive_timeout() {
+                Some(timeout) => match tokio::time::timeout(timeout, stream.next()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        tracing::warn!("No EventSub message received in {timeout:?}");
+
+                        match self
+                            .recover(session, ReconnectReason::KeepaliveTimeout)
+                            .await
+                        {
+                            Some(new_stream) => {
+                                stream = new_stream;
+                                continue;
+                            }
+                            None => break,
+                        }
+                    }
+                },
+                None => stream.next().await,
+            };
+
+            if let Some(Ok(_)) = next {
+                session
+                    .last_message
+                    .store(crate::unix_millis(), Ordering::Relaxed);
+            }
+
+            match next {
                 Some(Ok(message)) => match message {
                     Message::Ping(data) => {
                         stream.send(Message::Pong(data)).await?;
                     }
                     Message::Text(data) => {
-                        if let Some(new_stream) = self.clone().handle_text(&data).await? {
-                            let frame = CloseFrame {
-                                code: CloseCode::Normal,
-                                reason: "Reconnecting".into(),
-                            };
-
-                            if let Err(err) = stream.close(Some(frame)).await {
-                                tracing::error!(%err, "Error closing old EventSub connection");
+                        let Some(url) = self.handle_text(session, &data).await? else {
+                            continue;
+                        };
+
+                        tracing::info!("Reconnecting to EventSub at {url}");
+
+                        let new_stream = match self.reconnect(session, &url).await {
+                            Ok(new_stream) => new_stream,
+                            Err(err) => {
+                                tracing::error!(%err, "Failed to reconnect to {url}");
+
+                                // Subscriptions don't carry over to a new
+                                // connection made to the default url
+                                session.reconnecting.store(false, Ordering::Relaxed);
+
+                                match self.recover(session, ReconnectReason::Requested).await {
+                                    Some(new_stream) => new_stream,
+                                    None => break,
+                                }
                             }
+                        };
 
-                            stream = new_stream;
+                        let frame = CloseFrame {
+                            code: CloseCode::Normal,
+                            reason: "Reconnecting".into(),
+                        };
+
+                        if let Err(err) = stream.close(Some(frame)).await {
+                            tracing::error!(%err, "Error closing old EventSub connection");
                         }
+
+                        stream = new_stream;
                     }
                     Message::Close(frame) => {
                         if let Some(frame) = frame {
@@ -228,14 +674,11 @@ impl EventSubClient {
                 Some(Err(err)) => {
                     tracing::error!(%err, "EventSub connection error");
 
-                    match self.clone().reconnect(TWITCH_EVENTSUB_WS_URI).await {
-                        Ok(new_stream) => {
+                    match self.recover(session, ReconnectReason::Error).await {
+                        Some(new_stream) => {
                             stream = new_stream;
                         }
-                        Err(reconnect_err) => {
-                            tracing::error!(%reconnect_err, "Failed to reconnect to EventSub");
-                            break;
-                        }
+                        None => break,
                     }
                 }
                 None => {
@@ -245,25 +688,61 @@ impl EventSubClient {
             }
         }
 
-        self.reconnecting.store(false, Ordering::SeqCst);
+        session.reconnecting.store(false, Ordering::SeqCst);
 
         Ok(())
     }
 
-    async fn handle_text(self: Arc<Self>, data: &str) -> Result<Option<Stream>, Error> {
-        if let Ok(msg) = serde_json::from_str(data)
-            && let Some(url) = self.clone().handle_message(msg).await?
-        {
-            tracing::info!("Reconnecting to EventSub at {url}");
-            return Ok(Some(self.reconnect(&url).await?));
+    /// Replaces a dead connection with a new one, retrying with exponential
+    /// backoff. Subscriptions are restored on the new session once it has
+    /// been welcomed.
+    async fn recover(&self, session: &Arc<Session>, reason: ReconnectReason) -> Option<Stream> {
+        session.id.send_replace(None);
+
+        for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+            self.emit_status(Status::Reconnecting {
+                session: session.index,
+                reason,
+                attempt,
+            });
+
+            match self.reconnect(session, &self.endpoints.websocket).await {
+                Ok(stream) => return Some(stream),
+                Err(err) => {
+                    let delay = Duration::from_secs(1 << (attempt - 1));
+
+                    tracing::error!(
+                        %err,
+                        "Failed to reconnect to EventSub (attempt {attempt}), retrying in {delay:?}"
+                    );
+
+                    tokio::time::sleep(delay).await;
+                }
+            }
         }
 
-        Ok(None)
+        tracing::error!("Giving up on reconnecting to EventSub");
+
+        None
+    }
+
+    /// Handles a text message, returning the url to reconnect to if Twitch
+    /// requested a reconnect.
+    async fn handle_text(
+        &self,
+        session: &Arc<Session>,
+        data: &str,
+    ) -> Result<Option<String>, Error> {
+        match serde_json::from_str(data) {
+            Ok(msg) => self.handle_message(session, msg).await,
+            Err(_) => Ok(None),
+        }
     }
 
-    #[tracing::instrument(skip_all)]
+    #[tracing::instrument(skip_all, fields(session = session.index))]
     async fn handle_message(
-        self: Arc<Self>,
+        &self,
+        session: &Arc<Session>,
         msg: WebSocketMessage,
     ) -> Result<Option<String>, Error> {
         use WebSocketMessage as Ws;
@@ -271,58 +750,35 @@ impl EventSubClient {
         match msg {
             Ws::Welcome(payload) => {
                 tracing::debug!("Set EventSub session id to {}", payload.session.id);
-                *self.session_id.lock().await = Some(payload.session.id);
 
-                if self
+                session.keepalive.store(
+                    payload
+                        .session
+                        .keepalive_timeout_seconds
+                        .unwrap_or_default(),
+                    Ordering::Relaxed,
+                );
+                session.id.send_replace(Some(payload.session.id));
+
+                if session
                     .reconnecting
                     .compare_exchange(true, false, Ordering::Relaxed, Ordering::Relaxed)
                     .is_err()
                 {
                     tracing::info!("Initial connection to EventSub established");
-
-                    let mut to_restore = Vec::new();
-
-                    {
-                        let mut map = self.subscriptions.lock().await;
-
-                        if !map.is_empty() {
-                            tracing::info!("Restoring {} subscriptions", map.len());
-
-                            for (key, sub) in map.drain() {
-                                if let Some((username, _)) = key.split_once(':') {
-                                    to_restore.push((
-                                        username.to_string(),
-                                        sub.kind,
-                                        sub.condition,
-                                    ));
-                                }
-                            }
-                        }
-                    }
-
-                    self.subscribe(
-                        self.token.login.as_str(),
-                        EventType::UserUpdate,
-                        json!({ "user_id": self.token.user_id }),
-                    )
-                    .await?;
-
-                    for (username, kind, condition) in to_restore {
-                        if kind == EventType::UserUpdate {
-                            continue;
-                        }
-
-                        if let Err(err) = self.subscribe(&username, kind, condition).await {
-                            tracing::error!(%err, "Failed to restore {kind} subscription");
-                        }
-                    }
+                    self.spawn_restore(session.clone());
                 } else {
                     tracing::info!("Reconnected to EventSub");
                 }
             }
             Ws::Notification(payload) => {
+                if !self.is_new(&payload) {
+                    return Ok(None);
+                }
+
                 tracing::trace!(
-                    "Received {} event: {}",
+                    opaque = payload.event.is_opaque(),
+                    "Received {} event: {:?}",
                     payload.subscription.kind,
                     payload.event
                 );
@@ -337,21 +793,30 @@ impl EventSubClient {
                     .reconnect_url
                     .expect("missing reconnect_url in reconnect payload");
 
-                self.reconnecting.store(true, Ordering::Relaxed);
+                session.reconnecting.store(true, Ordering::Relaxed);
+                self.emit_status(Status::Reconnecting {
+                    session: session.index,
+                    reason: ReconnectReason::Requested,
+                    attempt: 1,
+                });
 
                 return Ok(Some(url));
             }
             Ws::Revocation(payload) => {
+                let revoked = payload.subscription;
+
                 tracing::warn!(
+                    reason = ?revoked.status,
                     "Revocation requested for {} ({})",
-                    payload.subscription.kind,
-                    payload.subscription.id
+                    revoked.kind,
+                    revoked.id
                 );
 
-                self.subscriptions
-                    .lock()
-                    .await
-                    .remove(&payload.subscription.kind.to_string());
+                if let Some(this) = self.this.upgrade() {
+                    tokio::spawn(
+                        async move { this.handle_revocation(revoked).await }.in_current_span(),
+                    );
+                }
             }
             _ => (),
         }
@@ -359,7 +824,163 @@ impl EventSubClient {
         Ok(None)
     }
 
-    async fn reconnect(self: Arc<Self>, url: &str) -> Result<Stream, Error> {
+    async fn handle_revocation(&self, revoked: RevokedSubscription) {
+        let removed = {
+            let mut map = self.subscriptions.lock().await;
+
+            let key = map
+                .iter()
+                .find(|(_, sub)| sub.id == revoked.id)
+                .map(|(key, _)| key.clone());
+
+            key.and_then(|key| map.remove_entry(&key))
+        };
+
+        let Some((key, sub)) = removed else {
+            tracing::debug!("Revoked subscription {} is not tracked", revoked.id);
+            return;
+        };
+
+        self.release(&sub);
+
+        let channel = key
+            .split_once(':')
+            .map_or(key.as_str(), |(channel, _)| channel)
+            .to_string();
+
+        let mut resubscribed = false;
+
+        if revoked.status.is_retryable()
+            && (revoked.status != RevocationReason::VersionRemoved
+                || sub.version != version_of(sub.kind))
+        {
+            match self
+                .subscribe(&channel, sub.kind, sub.condition.clone())
+                .await
+            {
+                Ok(_) => {
+                    tracing::info!("Resubscribed to revoked {} subscription", sub.kind);
+                    resubscribed = true;
+                }
+                Err(err) => {
+                    tracing::error!(%err, "Failed to resubscribe to {}", sub.kind);
+                }
+            }
+        }
+
+        self.emit_status(Status::Revoked {
+            session: sub.session,
+            channel,
+            event: sub.kind,
+            condition: sub.condition,
+            reason: revoked.status,
+            resubscribed,
+        });
+    }
+
+    /// Whether a notification hasn't been received before and isn't too old
+    /// to be relevant.
+    fn is_new(&self, payload: &NotificationPayload) -> bool {
+        if let Ok(timestamp) = OffsetDateTime::parse(&payload.timestamp, &Rfc3339) {
+            let age = OffsetDateTime::now_utc() - timestamp;
+
+            if age > MESSAGE_TTL {
+                tracing::debug!("Dropping {} message from {age} ago", payload.id);
+                return false;
+            }
+        }
+
+        if !self.seen.lock().unwrap().insert(&payload.id) {
+            tracing::debug!("Dropping duplicate message {}", payload.id);
+            return false;
+        }
+
+        true
+    }
+
+    /// Restores the subscriptions of a session in the background so retries
+    /// don't hold up reading from its stream.
+    fn spawn_restore(&self, session: Arc<Session>) {
+        let Some(this) = self.this.upgrade() else {
+            return;
+        };
+
+        tokio::spawn(
+            async move {
+                this.restore(&session).await;
+            }
+            .in_current_span(),
+        );
+    }
+
+    /// Recreates the subscriptions of a session after it was (re)opened. Its
+    /// subscriptions don't carry over when a new connection is established
+    /// unless Twitch asked for the reconnect.
+    async fn restore(&self, session: &Arc<Session>) {
+        let mut to_restore = Vec::new();
+
+        {
+            let mut map = self.subscriptions.lock().await;
+
+            let keys: Vec<_> = map
+                .iter()
+                .filter(|(_, sub)| sub.session == session.index)
+                .map(|(key, _)| key.clone())
+                .collect();
+
+            if !keys.is_empty() {
+                tracing::info!("Restoring {} subscriptions", keys.len());
+            }
+
+            for key in keys {
+                let sub = map.remove(&key).unwrap();
+
+                // Only the restored entries are released since subscriptions
+                // still being created hold reservations on the same counters
+                self.release(&sub);
+
+                if let Some((username, _)) = key.split_once(':') {
+                    to_restore.push((username.to_string(), sub.kind, sub.condition));
+                }
+            }
+        }
+
+        if session.index == 0 {
+            let token = self.token();
+
+            if let Err(err) = self
+                .subscribe_on(
+                    session,
+                    token.login.as_str(),
+                    EventType::UserUpdate,
+                    json!({ "user_id": token.user_id }),
+                )
+                .await
+            {
+                tracing::error!(%err, "Failed to subscribe to user updates");
+            }
+        }
+
+        let mut restored = 0;
+
+        for (username, kind, condition) in to_restore {
+            if kind == EventType::UserUpdate {
+                continue;
+            }
+
+            match self.subscribe_on(session, &username, kind, condition).await {
+                Ok(_) => restored += 1,
+                Err(err) => tracing::error!(%err, "Failed to restore {kind} subscription"),
+            }
+        }
+
+        self.emit_status(Status::Restored {
+            session: session.index,
+            subscriptions: restored,
+        });
+    }
+
+    async fn reconnect(&self, session: &Arc<Session>, url: &str) -> Result<Stream, Error> {
         let (mut stream, _) = connect_async(url).await.map_err(Error::WebSocket)?;
 
         loop {
@@ -369,7 +990,7 @@ impl EventSubClient {
                         .map_err(|e| Error::Generic(anyhow::anyhow!(e)))?;
 
                     if let WebSocketMessage::Welcome(_) = msg {
-                        self.handle_message(msg).await?;
+                        self.handle_message(session, msg).await?;
 
                         tracing::info!("Switched to new EventSub connection");
                         return Ok(stream);
@@ -385,11 +1006,89 @@ impl EventSubClient {
     }
 
     pub fn connected(&self) -> bool {
-        self.connected.load(Ordering::Relaxed)
+        self.sessions
+            .lock()
+            .unwrap()
+            .first()
+            .is_some_and(|session| session.connected.load(Ordering::Relaxed))
     }
 
-    pub fn set_connected(&self, value: bool) {
-        self.connected.store(value, Ordering::Relaxed);
+    pub fn usage(&self) -> Usage {
+        let sessions = self
+            .sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|session| SessionUsage {
+                id: session.id.borrow().clone(),
+                connected: session.connected.load(Ordering::Relaxed),
+                subscriptions: session.subscriptions.load(Ordering::Relaxed),
+                cost: session.cost.load(Ordering::Relaxed),
+                last_message_at: match session.last_message.load(Ordering::Relaxed) {
+                    0 => None,
+                    millis => Some(millis),
+                },
+            })
+            .collect();
+
+        Usage {
+            cost: *self.cost.lock().unwrap(),
+            sessions,
+        }
+    }
+
+    pub async fn connection_status(&self) -> ConnectionStatus {
+        let mut channels = BTreeMap::<_, Vec<_>>::new();
+
+        for (key, subscription) in self.subscriptions.lock().await.iter() {
+            let Some((channel, _)) = key.split_once(':') else {
+                continue;
+            };
+
+            channels
+                .entry(channel.to_string())
+                .or_default()
+                .push(SubscriptionStatus {
+                    id: subscription.id.clone(),
+                    kind: subscription.kind,
+                    version: subscription.version.clone(),
+                    cost: subscription.cost,
+                    session: subscription.session,
+                });
+        }
+
+        ConnectionStatus {
+            usage: self.usage(),
+            channels,
+        }
+    }
+
+    /// Returns a session with room for another subscription, opening a new
+    /// one if all existing sessions are full. A slot is reserved on the
+    /// returned session.
+    fn acquire_session(&self) -> Result<Arc<Session>, EventSubError> {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        if let Some(session) = sessions.iter().find(|session| session.try_reserve()) {
+            return Ok(session.clone());
+        }
+
+        if sessions.len() >= MAX_SESSIONS {
+            return Err(EventSubError::SessionsFull {
+                sessions: MAX_SESSIONS,
+                limit: MAX_SUBSCRIPTIONS_PER_SESSION,
+            });
+        }
+
+        tracing::info!("All EventSub sessions are full, opening another");
+
+        let session = Arc::new(Session::new(sessions.len()));
+        session.try_reserve();
+
+        sessions.push(session.clone());
+        self.spawn_session(session.clone());
+
+        Ok(session)
     }
 
     #[tracing::instrument(name = "eventsub_subscribe", skip(self, condition), fields(%condition))]
@@ -399,13 +1098,58 @@ impl EventSubClient {
         event: EventType,
         condition: serde_json::Value,
     ) -> Result<(), Error> {
-        let session_id = self.session_id.lock().await.clone();
+        let session = self.acquire_session()?;
 
-        let Some(session_id) = session_id else {
-            return Err(Error::Generic(anyhow!("No EventSub connection")));
-        };
+        self.subscribe_reserved(&session, username, event, condition)
+            .await
+    }
+
+    async fn subscribe_on(
+        &self,
+        session: &Session,
+        username: &str,
+        event: EventType,
+        condition: serde_json::Value,
+    ) -> Result<(), Error> {
+        if !session.try_reserve() {
+            return Err(EventSubError::SessionsFull {
+                sessions: 1,
+                limit: MAX_SUBSCRIPTIONS_PER_SESSION,
+            }
+            .into());
+        }
 
-        let version = if V2_EVENTS.contains(&event) { "2" } else { "1" };
+        self.subscribe_reserved(session, username, event, condition)
+            .await
+    }
+
+    async fn subscribe_reserved(
+        &self,
+        session: &Session,
+        username: &str,
+        event: EventType,
+        condition: serde_json::Value,
+    ) -> Result<(), Error> {
+        let result = self
+            .create_subscription(session, username, event, condition)
+            .await;
+
+        if result.is_err() {
+            session.release(0);
+        }
+
+        result
+    }
+
+    async fn create_subscription(
+        &self,
+        session: &Session,
+        username: &str,
+        event: EventType,
+        condition: serde_json::Value,
+    ) -> Result<(), Error> {
+        let session_id = session.wait_for_id().await?;
+        let version = version_of(event);
 
         let body = json!({
             "type": event,
@@ -417,62 +1161,208 @@ impl EventSubClient {
             }
         });
 
-        let response: Response<(EventSubSubscription,)> = HTTP
-            .post(TWITCH_EVENTSUB_ENDPOINT)
-            .bearer_auth(self.token.access_token.as_str())
-            .header("Client-Id", self.token.client_id().as_str())
-            .json(&body)
-            .send()
-            .await?
-            .json()
-            .await?;
+        let mut attempt = 1;
+
+        let response = loop {
+            let err = match self.post_subscription(&body).await {
+                Ok(response) => break response,
+                Err(err) => err,
+            };
+
+            if !err.is_transient() || attempt >= MAX_SUBSCRIBE_ATTEMPTS {
+                return Err(err.into());
+            }
+
+            let delay = match err {
+                EventSubError::RateLimited { reset: Some(reset) } => {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+
+                    Duration::from_secs(reset.saturating_sub(now).clamp(1, MAX_RATELIMIT_WAIT))
+                }
+                _ => Duration::from_secs(1 << (attempt - 1)),
+            };
+
+            tracing::warn!(%err, "Retrying subscription in {delay:?} (attempt {attempt})");
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        };
+
+        let created: CreatedSubscription = response.json().await.map_err(request_error)?;
+        let (mut subscription,) = created.data;
+        let cost = subscription.cost as u64;
+
+        *self.cost.lock().unwrap() = Cost {
+            total: created.total,
+            total_cost: created.total_cost,
+            max_total_cost: created.max_total_cost,
+        };
+
+        session.cost.fetch_add(cost, Ordering::SeqCst);
 
+        let id = subscription.id.take();
+
+        // Keyed by id too since the same type can be subscribed to with
+        // different conditions, e.g. incoming and outgoing raids
         self.subscriptions.lock().await.insert(
-            format!("{username}:{event}"),
+            format!("{username}:{event}:{id}"),
             Subscription {
-                id: response.data.0.id.take(),
+                id,
                 kind: event,
+                version: version.to_string(),
                 condition,
+                cost,
+                session: session.index,
             },
         );
 
-        tracing::trace!("Subscription created");
+        tracing::trace!(
+            cost,
+            total_cost = created.total_cost,
+            max_total_cost = created.max_total_cost,
+            "Subscription created"
+        );
 
         Ok(())
     }
 
+    /// Sends a request to create a subscription, converting any failure into
+    /// the reason Helix gave for it.
+    async fn post_subscription(
+        &self,
+        body: &serde_json::Value,
+    ) -> Result<reqwest::Response, EventSubError> {
+        let token = self.token();
+
+        let response = HTTP
+            .post(&self.endpoints.subscriptions)
+            .bearer_auth(token.access_token.as_str())
+            .header("Client-Id", token.client_id().as_str())
+            .json(body)
+            .send()
+            .await
+            .map_err(request_error)?;
+
+        let status = response.status();
+
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let reset = response
+            .headers()
+            .get("Ratelimit-Reset")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+
+        let error: HelixError = response.json().await.unwrap_or_default();
+        let message = error.message.to_lowercase();
+
+        let err = match status {
+            StatusCode::TOO_MANY_REQUESTS if message.contains("cost") => {
+                let cost = *self.cost.lock().unwrap();
+
+                EventSubError::CostLimit {
+                    total_cost: cost.total_cost,
+                    max_total_cost: cost.max_total_cost,
+                }
+            }
+            StatusCode::TOO_MANY_REQUESTS => EventSubError::RateLimited { reset },
+            StatusCode::CONFLICT => EventSubError::Conflict,
+            StatusCode::FORBIDDEN if message.contains("moderator") => EventSubError::NotModerator,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => EventSubError::MissingScope {
+                message: error.message,
+            },
+            _ => EventSubError::Rejected {
+                status: status.as_u16(),
+                message: error.message,
+            },
+        };
+
+        Err(err)
+    }
+
+    fn release(&self, subscription: &Subscription) {
+        if let Some(session) = self.sessions.lock().unwrap().get(subscription.session) {
+            session.release(subscription.cost);
+        }
+
+        let mut cost = self.cost.lock().unwrap();
+
+        cost.total = cost.total.saturating_sub(1);
+        cost.total_cost = cost.total_cost.saturating_sub(subscription.cost);
+    }
+
+    /// Subscribes to every event in `subscriptions`, returning the result of
+    /// each in the same order.
     #[tracing::instrument(name = "eventsub_subscribe_all", skip(self, subscriptions))]
     pub async fn subscribe_all(
         &self,
         channel: &str,
         subscriptions: Vec<(EventType, &serde_json::Value)>,
-    ) -> Result<(), Error> {
+    ) -> Vec<SubscriptionResult> {
         let futures = subscriptions
             .iter()
             .map(|&(event, condition)| self.subscribe(channel, event, condition.clone()));
 
-        let success = join_all(futures).await.iter().filter(|r| r.is_ok()).count();
+        let results: Vec<_> = join_all(futures)
+            .await
+            .into_iter()
+            .zip(&subscriptions)
+            .map(|(result, &(event, _))| SubscriptionResult {
+                event,
+                error: result.err().map(|err| match err {
+                    Error::EventSub(err) => err,
+                    err => EventSubError::Request {
+                        message: err.to_string(),
+                    },
+                }),
+            })
+            .collect();
+
+        for result in &results {
+            if let Some(ref err) = result.error {
+                tracing::warn!(%err, "Failed to subscribe to {}", result.event);
+            }
+        }
 
-        tracing::info!("{} subscriptions created", success);
+        tracing::info!(
+            "{} of {} subscriptions created",
+            results.iter().filter(|r| r.error.is_none()).count(),
+            results.len()
+        );
 
-        Ok(())
+        results
     }
 
+    /// Removes a subscription of the channel by the rest of its key, which is
+    /// its type and id.
     pub async fn unsubscribe(
         &self,
         channel: &str,
-        event: String,
+        key: String,
     ) -> Result<Option<Subscription>, Error> {
         let subscription = self
             .subscriptions
             .lock()
             .await
-            .remove(&format!("{channel}:{event}"));
+            .remove(&format!("{channel}:{key}"));
 
         if let Some(ref sub) = subscription {
-            self.helix
-                .delete_eventsub_subscription(&sub.id, &*self.token)
-                .await?;
+            self.release(sub);
+
+            let token = self.token();
+
+            HTTP.delete(&self.endpoints.subscriptions)
+                .query(&[("id", &sub.id)])
+                .bearer_auth(token.access_token.as_str())
+                .header("Client-Id", token.client_id().as_str())
+                .send()
+                .await?
+                .error_for_status()?;
         }
 
         Ok(subscription)
@@ -481,22 +1371,40 @@ impl EventSubClient {
     pub async fn unsubscribe_all(
         &self,
         channel: &str,
+    ) -> Result<Vec<(EventType, serde_json::Value)>, Error> {
+        self.unsubscribe_where(channel, |_| true).await
+    }
+
+    /// Removes the subscriptions of the channel to any of the given events.
+    pub async fn unsubscribe_events(
+        &self,
+        channel: &str,
+        events: &[EventType],
+    ) -> Result<Vec<(EventType, serde_json::Value)>, Error> {
+        self.unsubscribe_where(channel, |sub| events.contains(&sub.kind))
+            .await
+    }
+
+    async fn unsubscribe_where(
+        &self,
+        channel: &str,
+        filter: impl Fn(&Subscription) -> bool,
     ) -> Result<Vec<(EventType, serde_json::Value)>, Error> {
         let prefix = format!("{channel}:");
 
-        let events = {
+        let keys = {
             let subscriptions = self.subscriptions.lock().await;
 
             subscriptions
-                .keys()
-                .filter(|k| k.starts_with(&prefix))
-                .map(|k| k.strip_prefix(&prefix).unwrap().to_string())
+                .iter()
+                .filter(|(k, sub)| k.starts_with(&prefix) && filter(sub))
+                .map(|(k, _)| k.strip_prefix(&prefix).unwrap().to_string())
                 .collect::<Vec<_>>()
         };
 
-        let futures = events
+        let futures = keys
             .iter()
-            .map(|event| self.unsubscribe(channel, event.clone()));
+            .map(|key| self.unsubscribe(channel, key.clone()));
 
         let results = join_all(futures).await;
         let mut unsubscribed = Vec::new();
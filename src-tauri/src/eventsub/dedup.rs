@@ -0,0 +1,92 @@
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Ids of recently received messages, forgotten once they are older than the
+/// configured lifetime or the capacity is exceeded.
+pub struct SeenMessages {
+    ttl: Duration,
+    capacity: usize,
+    order: VecDeque<(Instant, String)>,
+    ids: HashSet<String>,
+}
+
+impl SeenMessages {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            ids: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// Records a message id, returning `false` if it was already seen.
+    pub fn insert(&mut self, id: &str) -> bool {
+        self.evict_expired();
+
+        if self.ids.contains(id) {
+            return false;
+        }
+
+        if self.order.len() == self.capacity
+            && let Some((_, oldest)) = self.order.pop_front()
+        {
+            self.ids.remove(&oldest);
+        }
+
+        self.order.push_back((Instant::now(), id.to_string()));
+        self.ids.insert(id.to_string());
+
+        true
+    }
+
+    fn evict_expired(&mut self) {
+        while let Some((seen_at, _)) = self.order.front()
+            && seen_at.elapsed() > self.ttl
+        {
+            let (_, id) = self.order.pop_front().unwrap();
+            self.ids.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_duplicates() {
+        let mut seen = SeenMessages::new(Duration::from_secs(60), 10);
+
+        assert!(seen.insert("a"));
+        assert!(seen.insert("b"));
+        assert!(!seen.insert("a"));
+        assert!(!seen.insert("b"));
+    }
+
+    #[test]
+    fn forgets_oldest_past_capacity() {
+        let mut seen = SeenMessages::new(Duration::from_secs(60), 2);
+
+        assert!(seen.insert("a"));
+        assert!(seen.insert("b"));
+        assert!(seen.insert("c"));
+
+        // "a" was evicted to make room for "c"
+        assert!(!seen.insert("c"));
+        assert!(seen.insert("a"));
+        assert_eq!(seen.order.len(), 2);
+        assert_eq!(seen.ids.len(), 2);
+    }
+
+    #[test]
+    fn forgets_expired_ids() {
+        let mut seen = SeenMessages::new(Duration::from_millis(20), 10);
+
+        assert!(seen.insert("a"));
+        std::thread::sleep(Duration::from_millis(40));
+
+        assert!(seen.insert("a"));
+        assert_eq!(seen.order.len(), 1);
+    }
+}
@@ -0,0 +1,51 @@
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Error {
+    /// No session received a welcome message in time
+    #[error("No EventSub connection")]
+    NotConnected,
+    /// Every session is at the subscription limit and no more can be opened
+    #[error("All {sessions} EventSub sessions have reached the limit of {limit} subscriptions")]
+    SessionsFull { sessions: usize, limit: usize },
+    /// The combined cost of the user's subscriptions would exceed the maximum
+    #[error("EventSub subscription cost limit reached ({total_cost} of {max_total_cost})")]
+    CostLimit {
+        total_cost: u64,
+        max_total_cost: u64,
+    },
+    /// The token is missing a scope required by the subscription type
+    #[error("Missing authorization for the subscription: {message}")]
+    MissingScope { message: String },
+    /// The user isn't a moderator of the broadcaster in the condition
+    #[error("Not a moderator of the channel")]
+    NotModerator,
+    /// Too many requests were made, even after retrying
+    #[error("Rate limited by Twitch")]
+    RateLimited {
+        /// Unix timestamp at which the rate limit resets, if known.
+        reset: Option<u64>,
+    },
+    /// A subscription with the same type and condition already exists
+    #[error("Subscription already exists")]
+    Conflict,
+    /// Helix rejected the subscription for any other reason
+    #[error("EventSub subscription rejected with status {status}: {message}")]
+    Rejected { status: u16, message: String },
+    /// The request failed to be sent or its response couldn't be read
+    #[error("EventSub subscription request failed: {message}")]
+    Request { message: String },
+}
+
+impl Error {
+    /// Whether the request may succeed if retried later.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::RateLimited { .. } | Self::Request { .. } => true,
+            Self::Rejected { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+}
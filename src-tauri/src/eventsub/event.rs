@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use twitch_api::eventsub::{EventType, automod, channel, stream, user};
+
+use super::chat::CHAT_EVENTS;
+use super::hype_train::{
+    ChannelHypeTrainBeginV2Payload, ChannelHypeTrainEndV2Payload, ChannelHypeTrainProgressV2Payload,
+};
+
+/// The event of a notification, deserialized into the payload of its
+/// subscription type and version.
+///
+/// Serializes as `{ kind, data }` where `data` is the payload in the shape
+/// Twitch sends it, minus anything the payload types don't know about. Events
+/// without a typed payload have the `opaque` kind.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+pub enum Event {
+    AutomodMessageHold(automod::AutomodMessageHoldV2Payload),
+    AutomodMessageUpdate(automod::AutomodMessageUpdateV2Payload),
+    ChannelChatUserMessageHold(channel::ChannelChatUserMessageHoldV1Payload),
+    ChannelChatUserMessageUpdate(channel::ChannelChatUserMessageUpdateV1Payload),
+    ChannelAdBreakBegin(channel::ChannelAdBreakBeginV1Payload),
+    ChannelCharityCampaignDonate(channel::ChannelCharityCampaignDonateV1Payload),
+    ChannelCharityCampaignProgress(channel::ChannelCharityCampaignProgressV1Payload),
+    ChannelCharityCampaignStart(channel::ChannelCharityCampaignStartV1Payload),
+    ChannelCharityCampaignStop(channel::ChannelCharityCampaignStopV1Payload),
+    ChannelFollow(channel::ChannelFollowV2Payload),
+    ChannelHypeTrainBegin(ChannelHypeTrainBeginV2Payload),
+    ChannelHypeTrainEnd(ChannelHypeTrainEndV2Payload),
+    ChannelHypeTrainProgress(ChannelHypeTrainProgressV2Payload),
+    ChannelModerate(channel::ChannelModerateV2Payload),
+    ChannelPointsCustomRewardRedemptionAdd(
+        channel::ChannelPointsCustomRewardRedemptionAddV1Payload,
+    ),
+    ChannelPollBegin(channel::ChannelPollBeginV1Payload),
+    ChannelPollEnd(channel::ChannelPollEndV1Payload),
+    ChannelPollProgress(channel::ChannelPollProgressV1Payload),
+    ChannelPredictionBegin(channel::ChannelPredictionBeginV1Payload),
+    ChannelPredictionEnd(channel::ChannelPredictionEndV1Payload),
+    ChannelPredictionLock(channel::ChannelPredictionLockV1Payload),
+    ChannelPredictionProgress(channel::ChannelPredictionProgressV1Payload),
+    ChannelRaid(channel::ChannelRaidV1Payload),
+    ChannelShoutoutCreate(channel::ChannelShoutoutCreateV1Payload),
+    ChannelShoutoutReceive(channel::ChannelShoutoutReceiveV1Payload),
+    ChannelSubscriptionEnd(channel::ChannelSubscriptionEndV1Payload),
+    ChannelSuspiciousUserMessage(channel::ChannelSuspiciousUserMessageV1Payload),
+    ChannelSuspiciousUserUpdate(channel::ChannelSuspiciousUserUpdateV1Payload),
+    ChannelUnbanRequestCreate(channel::ChannelUnbanRequestCreateV1Payload),
+    ChannelUnbanRequestResolve(channel::ChannelUnbanRequestResolveV1Payload),
+    ChannelUpdate(channel::ChannelUpdateV1Payload),
+    ChannelWarningAcknowledge(channel::ChannelWarningAcknowledgeV1Payload),
+    StreamOffline(stream::StreamOfflineV1Payload),
+    StreamOnline(stream::StreamOnlineV1Payload),
+    UserUpdate(user::UserUpdateV1Payload),
+    /// An event of an unsupported type or one that failed to deserialize,
+    /// passed through untouched.
+    Opaque(serde_json::Value),
+}
+
+fn typed<'a, T: Deserialize<'a>>(
+    event: &'a serde_json::Value,
+    variant: fn(T) -> Event,
+) -> Result<Event, serde_json::Error> {
+    T::deserialize(event).map(variant)
+}
+
+impl Event {
+    pub fn parse(kind: &EventType, version: &str, event: serde_json::Value) -> Self {
+        use EventType as Ev;
+
+        let result = match (kind, version) {
+            (Ev::AutomodMessageHold, "2") => typed(&event, Self::AutomodMessageHold),
+            (Ev::AutomodMessageUpdate, "2") => typed(&event, Self::AutomodMessageUpdate),
+            (Ev::ChannelChatUserMessageHold, "1") => {
+                typed(&event, Self::ChannelChatUserMessageHold)
+            }
+            (Ev::ChannelChatUserMessageUpdate, "1") => {
+                typed(&event, Self::ChannelChatUserMessageUpdate)
+            }
+            (Ev::ChannelAdBreakBegin, "1") => typed(&event, Self::ChannelAdBreakBegin),
+            (Ev::ChannelCharityCampaignDonate, "1") => {
+                typed(&event, Self::ChannelCharityCampaignDonate)
+            }
+            (Ev::ChannelCharityCampaignProgress, "1") => {
+                typed(&event, Self::ChannelCharityCampaignProgress)
+            }
+            (Ev::ChannelCharityCampaignStart, "1") => {
+                typed(&event, Self::ChannelCharityCampaignStart)
+            }
+            (Ev::ChannelCharityCampaignStop, "1") => {
+                typed(&event, Self::ChannelCharityCampaignStop)
+            }
+            (Ev::ChannelFollow, "2") => typed(&event, Self::ChannelFollow),
+            (Ev::ChannelHypeTrainBegin, "2") => typed(&event, Self::ChannelHypeTrainBegin),
+            (Ev::ChannelHypeTrainEnd, "2") => typed(&event, Self::ChannelHypeTrainEnd),
+            (Ev::ChannelHypeTrainProgress, "2") => typed(&event, Self::ChannelHypeTrainProgress),
+            (Ev::ChannelModerate, "2") => typed(&event, Self::ChannelModerate),
+            (Ev::ChannelPointsCustomRewardRedemptionAdd, "1") => {
+                typed(&event, Self::ChannelPointsCustomRewardRedemptionAdd)
+            }
+            (Ev::ChannelPollBegin, "1") => typed(&event, Self::ChannelPollBegin),
+            (Ev::ChannelPollEnd, "1") => typed(&event, Self::ChannelPollEnd),
+            (Ev::ChannelPollProgress, "1") => typed(&event, Self::ChannelPollProgress),
+            (Ev::ChannelPredictionBegin, "1") => typed(&event, Self::ChannelPredictionBegin),
+            (Ev::ChannelPredictionEnd, "1") => typed(&event, Self::ChannelPredictionEnd),
+            (Ev::ChannelPredictionLock, "1") => typed(&event, Self::ChannelPredictionLock),
+            (Ev::ChannelPredictionProgress, "1") => typed(&event, Self::ChannelPredictionProgress),
+            (Ev::ChannelRaid, "1") => typed(&event, Self::ChannelRaid),
+            (Ev::ChannelShoutoutCreate, "1") => typed(&event, Self::ChannelShoutoutCreate),
+            (Ev::ChannelShoutoutReceive, "1") => typed(&event, Self::ChannelShoutoutReceive),
+            (Ev::ChannelSubscriptionEnd, "1") => typed(&event, Self::ChannelSubscriptionEnd),
+            (Ev::ChannelSuspiciousUserMessage, "1") => {
+                typed(&event, Self::ChannelSuspiciousUserMessage)
+            }
+            (Ev::ChannelSuspiciousUserUpdate, "1") => {
+                typed(&event, Self::ChannelSuspiciousUserUpdate)
+            }
+            (Ev::ChannelUnbanRequestCreate, "1") => typed(&event, Self::ChannelUnbanRequestCreate),
+            (Ev::ChannelUnbanRequestResolve, "1") => {
+                typed(&event, Self::ChannelUnbanRequestResolve)
+            }
+            (Ev::ChannelUpdate, "1") => typed(&event, Self::ChannelUpdate),
+            (Ev::ChannelWarningAcknowledge, "1") => typed(&event, Self::ChannelWarningAcknowledge),
+            (Ev::StreamOffline, "1") => typed(&event, Self::StreamOffline),
+            (Ev::StreamOnline, "1") => typed(&event, Self::StreamOnline),
+            (Ev::UserUpdate, "1") => typed(&event, Self::UserUpdate),
+            // Normalized into IRC messages by the chat module
+            _ if CHAT_EVENTS.contains(kind) => return Self::Opaque(event),
+            _ => {
+                tracing::warn!("Passing through unsupported {kind} v{version} event as opaque");
+                return Self::Opaque(event);
+            }
+        };
+
+        result.unwrap_or_else(|err| {
+            tracing::warn!(%err, "Failed to deserialize {kind} v{version} event, passing through as opaque");
+            Self::Opaque(event)
+        })
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        matches!(self, Self::Opaque(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn hype_train_progress() -> serde_json::Value {
+        json!({
+            "id": "1b0AsbInCHZW2SQFQkCzqN07Ib2",
+            "broadcaster_user_id": "1337",
+            "broadcaster_user_login": "cool_user",
+            "broadcaster_user_name": "Cool_User",
+            "total": 700,
+            "progress": 200,
+            "goal": 1000,
+            "top_contributions": [
+                { "user_id": "123", "user_login": "pogchamp", "user_name": "PogChamp", "type": "bits", "total": 50 }
+            ],
+            "level": 2,
+            "type": "regular",
+            "is_shared_train": false,
+            "shared_train_participants": null,
+            "started_at": "2020-07-15T17:16:03.17106713Z",
+            "expires_at": "2020-07-15T17:16:11.17106713Z"
+        })
+    }
+
+    #[test]
+    fn parses_hype_train_v2() {
+        let event = Event::parse(
+            &EventType::ChannelHypeTrainProgress,
+            "2",
+            hype_train_progress(),
+        );
+
+        let Event::ChannelHypeTrainProgress(payload) = &event else {
+            panic!("expected a typed hype train event, got {event:?}");
+        };
+
+        assert_eq!(payload.level, 2);
+        assert_eq!(payload.progress, 200);
+
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["kind"], "channel_hype_train_progress");
+        assert_eq!(value["data"]["type"], "regular");
+    }
+
+    #[test]
+    fn unknown_version_is_opaque() {
+        let event = Event::parse(
+            &EventType::ChannelHypeTrainProgress,
+            "1",
+            hype_train_progress(),
+        );
+
+        assert!(event.is_opaque());
+    }
+}
@@ -0,0 +1,87 @@
+//! Payloads for v2 of the hype train events, which twitch_api only provides
+//! v1 types for.
+
+use serde::{Deserialize, Serialize};
+use twitch_api::eventsub::channel::hypetrain::Contribution;
+use twitch_api::types::{DisplayName, HypeTrainId, Timestamp, UserId, UserName};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HypeTrainType {
+    Regular,
+    GoldenKappa,
+    Shared,
+}
+
+/// A broadcaster taking part in a shared hype train.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedTrainParticipant {
+    pub broadcaster_user_id: UserId,
+    pub broadcaster_user_login: UserName,
+    pub broadcaster_user_name: DisplayName,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelHypeTrainBeginV2Payload {
+    pub id: HypeTrainId,
+    pub broadcaster_user_id: UserId,
+    pub broadcaster_user_login: UserName,
+    pub broadcaster_user_name: DisplayName,
+    pub total: i64,
+    /// Points contributed towards the current level.
+    pub progress: i64,
+    /// Points required to reach the next level.
+    pub goal: i64,
+    pub top_contributions: Vec<Contribution>,
+    pub level: i64,
+    pub all_time_high_level: i64,
+    pub all_time_high_total: i64,
+    #[serde(rename = "type")]
+    pub kind: HypeTrainType,
+    #[serde(default)]
+    pub is_shared_train: bool,
+    pub shared_train_participants: Option<Vec<SharedTrainParticipant>>,
+    pub started_at: Timestamp,
+    pub expires_at: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelHypeTrainProgressV2Payload {
+    pub id: HypeTrainId,
+    pub broadcaster_user_id: UserId,
+    pub broadcaster_user_login: UserName,
+    pub broadcaster_user_name: DisplayName,
+    pub total: i64,
+    /// Points contributed towards the current level.
+    pub progress: i64,
+    /// Points required to reach the next level.
+    pub goal: i64,
+    pub top_contributions: Vec<Contribution>,
+    pub level: i64,
+    #[serde(rename = "type")]
+    pub kind: HypeTrainType,
+    #[serde(default)]
+    pub is_shared_train: bool,
+    pub shared_train_participants: Option<Vec<SharedTrainParticipant>>,
+    pub started_at: Timestamp,
+    pub expires_at: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelHypeTrainEndV2Payload {
+    pub id: HypeTrainId,
+    pub broadcaster_user_id: UserId,
+    pub broadcaster_user_login: UserName,
+    pub broadcaster_user_name: DisplayName,
+    pub total: i64,
+    pub top_contributions: Vec<Contribution>,
+    pub level: i64,
+    #[serde(rename = "type")]
+    pub kind: HypeTrainType,
+    #[serde(default)]
+    pub is_shared_train: bool,
+    pub shared_train_participants: Option<Vec<SharedTrainParticipant>>,
+    pub started_at: Timestamp,
+    pub ended_at: Timestamp,
+    pub cooldown_ends_at: Timestamp,
+}
@@ -0,0 +1,834 @@
+//! A minimal EventSub server for developing and testing without Twitch.
+//!
+//! Implements the WebSocket transport (welcome, keepalive, notification,
+//! reconnect and revocation messages) and the subset of the subscriptions API
+//! used by [`EventSubClient`](super::EventSubClient). Events are only sent when
+//! triggered with [`MockServer::trigger`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, mpsc};
+use tokio_tungstenite::accept_hdr_async;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tracing::Instrument;
+
+use super::client::Endpoints;
+use crate::error::Error;
+
+const KEEPALIVE: Duration = Duration::from_secs(10);
+const MAX_SUBSCRIPTIONS_PER_SESSION: usize = 300;
+const MAX_TOTAL_COST: u64 = 10;
+
+/// An action to perform on the connected clients.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MockAction {
+    /// Sends an event to every subscription of the given type.
+    Notification {
+        event_type: String,
+        event: serde_json::Value,
+    },
+    /// Asks every session to move to a new connection.
+    Reconnect,
+    /// Revokes a subscription by id, or every subscription of a type.
+    Revoke {
+        subscription_id: Option<String>,
+        event_type: Option<String>,
+        reason: String,
+    },
+    /// Stops sending any messages on the current connections, simulating
+    /// half-open connections. Connections made afterwards aren't affected.
+    Silence { enabled: bool },
+}
+
+struct MockSession {
+    /// Incremented every time the session moves to a new connection.
+    generation: u64,
+    sender: mpsc::UnboundedSender<Message>,
+    silent: Arc<AtomicBool>,
+}
+
+struct MockSubscription {
+    id: String,
+    kind: String,
+    version: String,
+    condition: serde_json::Value,
+    session_id: String,
+    created_at: String,
+}
+
+impl MockSubscription {
+    fn to_json(&self, status: &str) -> serde_json::Value {
+        json!({
+            "id": self.id,
+            "status": status,
+            "type": self.kind,
+            "version": self.version,
+            "condition": self.condition,
+            "created_at": self.created_at,
+            "transport": {
+                "method": "websocket",
+                "session_id": self.session_id,
+                "connected_at": self.created_at,
+            },
+            "cost": 0,
+        })
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    sessions: HashMap<String, MockSession>,
+    subscriptions: HashMap<String, MockSubscription>,
+}
+
+pub struct MockServer {
+    endpoints: Endpoints,
+    ws_addr: String,
+    keepalive: Duration,
+    state: Mutex<MockState>,
+    next_id: AtomicU64,
+}
+
+fn now() -> String {
+    OffsetDateTime::now_utc().format(&Rfc3339).unwrap()
+}
+
+impl MockServer {
+    /// Starts the server on random local ports.
+    pub async fn start() -> Result<Arc<Self>, Error> {
+        Self::start_with_keepalive(KEEPALIVE).await
+    }
+
+    /// Starts the server with a custom keepalive interval, which is rounded
+    /// down to whole seconds when sent to clients.
+    async fn start_with_keepalive(keepalive: Duration) -> Result<Arc<Self>, Error> {
+        let ws_listener = TcpListener::bind("127.0.0.1:0").await?;
+        let http_listener = TcpListener::bind("127.0.0.1:0").await?;
+
+        let ws_addr = ws_listener.local_addr()?.to_string();
+        let http_addr = http_listener.local_addr()?.to_string();
+
+        let server = Arc::new(Self {
+            endpoints: Endpoints {
+                websocket: format!("ws://{ws_addr}/ws"),
+                subscriptions: format!("http://{http_addr}/eventsub/subscriptions"),
+            },
+            ws_addr,
+            keepalive,
+            state: Mutex::new(MockState::default()),
+            next_id: AtomicU64::default(),
+        });
+
+        tracing::info!(?server.endpoints, "Mock EventSub server started");
+
+        let ws_server = server.clone();
+
+        tokio::spawn(
+            async move {
+                while let Ok((stream, _)) = ws_listener.accept().await {
+                    tokio::spawn(ws_server.clone().handle_websocket(stream).in_current_span());
+                }
+            }
+            .in_current_span(),
+        );
+
+        let http_server = server.clone();
+
+        tokio::spawn(
+            async move {
+                while let Ok((stream, _)) = http_listener.accept().await {
+                    tokio::spawn(http_server.clone().handle_http(stream).in_current_span());
+                }
+            }
+            .in_current_span(),
+        );
+
+        Ok(server)
+    }
+
+    pub fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    fn next_id(&self) -> String {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        format!("mock-{:08x}-{id:08x}", std::process::id())
+    }
+
+    fn message(&self, message_type: &str, payload: serde_json::Value) -> Message {
+        let message = json!({
+            "metadata": {
+                "message_id": self.next_id(),
+                "message_type": message_type,
+                "message_timestamp": now(),
+            },
+            "payload": payload,
+        });
+
+        Message::text(message.to_string())
+    }
+
+    /// Performs an action, returning the number of messages sent.
+    pub async fn trigger(&self, action: MockAction) -> usize {
+        match action {
+            MockAction::Notification { event_type, event } => self.notify(&event_type, event).await,
+            MockAction::Reconnect => self.reconnect().await,
+            MockAction::Revoke {
+                subscription_id,
+                event_type,
+                reason,
+            } => {
+                self.revoke(
+                    |sub| {
+                        subscription_id.as_ref().is_some_and(|id| *id == sub.id)
+                            || event_type.as_ref().is_some_and(|kind| *kind == sub.kind)
+                    },
+                    &reason,
+                )
+                .await
+            }
+            MockAction::Silence { enabled } => {
+                let state = self.state.lock().await;
+
+                for session in state.sessions.values() {
+                    session.silent.store(enabled, Ordering::Relaxed);
+                }
+
+                state.sessions.len()
+            }
+        }
+    }
+
+    pub async fn notify(&self, event_type: &str, event: serde_json::Value) -> usize {
+        let state = self.state.lock().await;
+        let mut sent = 0;
+
+        for sub in state
+            .subscriptions
+            .values()
+            .filter(|sub| sub.kind == event_type)
+        {
+            let Some(session) = state.sessions.get(&sub.session_id) else {
+                continue;
+            };
+
+            let message = self.message(
+                "notification",
+                json!({
+                    "subscription": sub.to_json("enabled"),
+                    "event": event,
+                }),
+            );
+
+            if session.sender.send(message).is_ok() {
+                sent += 1;
+            }
+        }
+
+        sent
+    }
+
+    pub async fn reconnect(&self) -> usize {
+        let state = self.state.lock().await;
+
+        for (id, session) in &state.sessions {
+            let message = self.message(
+                "session_reconnect",
+                json!({
+                    "session": {
+                        "id": id,
+                        "status": "reconnecting",
+                        "keepalive_timeout_seconds": null,
+                        "reconnect_url": format!("ws://{}/ws?reconnect={id}", self.ws_addr),
+                        "connected_at": now(),
+                    }
+                }),
+            );
+
+            let _ = session.sender.send(message);
+        }
+
+        state.sessions.len()
+    }
+
+    async fn revoke(&self, filter: impl Fn(&MockSubscription) -> bool, reason: &str) -> usize {
+        let mut state = self.state.lock().await;
+
+        let ids: Vec<_> = state
+            .subscriptions
+            .values()
+            .filter(|sub| filter(sub))
+            .map(|sub| sub.id.clone())
+            .collect();
+
+        for id in &ids {
+            let sub = state.subscriptions.remove(id).unwrap();
+
+            if let Some(session) = state.sessions.get(&sub.session_id) {
+                let message =
+                    self.message("revocation", json!({ "subscription": sub.to_json(reason) }));
+
+                let _ = session.sender.send(message);
+            }
+        }
+
+        ids.len()
+    }
+
+    async fn handle_websocket(self: Arc<Self>, stream: TcpStream) {
+        let mut path = String::new();
+
+        let ws = match accept_hdr_async(stream, |request: &Request, response: Response| {
+            path = request.uri().to_string();
+            Ok::<_, ErrorResponse>(response)
+        })
+        .await
+        {
+            Ok(ws) => ws,
+            Err(err) => {
+                tracing::error!(%err, "Mock EventSub handshake failed");
+                return;
+            }
+        };
+
+        let reconnect_id = path.split_once("reconnect=").map(|(_, id)| id.to_string());
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let silent = Arc::new(AtomicBool::default());
+
+        let (session_id, generation) = {
+            let mut state = self.state.lock().await;
+
+            let session_id = match reconnect_id {
+                Some(id) if state.sessions.contains_key(&id) => id,
+                _ => self.next_id(),
+            };
+
+            let generation = state
+                .sessions
+                .get(&session_id)
+                .map_or(0, |session| session.generation + 1);
+
+            state.sessions.insert(
+                session_id.clone(),
+                MockSession {
+                    generation,
+                    sender: sender.clone(),
+                    silent: silent.clone(),
+                },
+            );
+
+            (session_id, generation)
+        };
+
+        let welcome = self.message(
+            "session_welcome",
+            json!({
+                "session": {
+                    "id": session_id,
+                    "status": "connected",
+                    "keepalive_timeout_seconds": self.keepalive.as_secs(),
+                    "reconnect_url": null,
+                    "connected_at": now(),
+                }
+            }),
+        );
+
+        let _ = sender.send(welcome);
+        drop(sender);
+
+        let (mut sink, mut incoming) = ws.split();
+        let server = self.clone();
+
+        let writer = tokio::spawn(async move {
+            loop {
+                let message = match tokio::time::timeout(server.keepalive, receiver.recv()).await {
+                    Ok(Some(message)) => message,
+                    Ok(None) => break,
+                    Err(_) => server.message("session_keepalive", json!({})),
+                };
+
+                if silent.load(Ordering::Relaxed) {
+                    continue;
+                }
+
+                if sink.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        while let Some(Ok(message)) = incoming.next().await {
+            if message.is_close() {
+                break;
+            }
+        }
+
+        writer.abort();
+
+        let mut state = self.state.lock().await;
+
+        // Subscriptions are kept if the session moved to a new connection
+        if state
+            .sessions
+            .get(&session_id)
+            .is_some_and(|session| session.generation == generation)
+        {
+            state.sessions.remove(&session_id);
+            state
+                .subscriptions
+                .retain(|_, sub| sub.session_id != session_id);
+        }
+    }
+
+    async fn handle_http(self: Arc<Self>, mut stream: TcpStream) {
+        let Some((method, path, body)) = read_request(&mut stream).await else {
+            return;
+        };
+
+        let (status, body) = match (method.as_str(), path.split_once('?')) {
+            ("POST", None) if path == "/eventsub/subscriptions" => {
+                self.create_subscription(&body).await
+            }
+            ("DELETE", Some(("/eventsub/subscriptions", query))) => {
+                let id = query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix("id="))
+                    .unwrap_or_default();
+
+                let removed = self.state.lock().await.subscriptions.remove(id);
+
+                match removed {
+                    Some(_) => (204, None),
+                    None => error(404, "Not Found", "subscription not found"),
+                }
+            }
+            _ => error(404, "Not Found", "not found"),
+        };
+
+        let body = body.map(|body| body.to_string()).unwrap_or_default();
+
+        let response = format!(
+            // Only one request is handled per connection
+            "HTTP/1.1 {status} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            reason_phrase(status),
+            body.len()
+        );
+
+        if let Err(err) = stream.write_all(response.as_bytes()).await {
+            tracing::error!(%err, "Failed to write mock EventSub response");
+        }
+    }
+
+    async fn create_subscription(&self, body: &[u8]) -> (u16, Option<serde_json::Value>) {
+        #[derive(Deserialize)]
+        struct Transport {
+            session_id: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Request {
+            #[serde(rename = "type")]
+            kind: String,
+            version: String,
+            condition: serde_json::Value,
+            transport: Transport,
+        }
+
+        let request: Request = match serde_json::from_slice(body) {
+            Ok(request) => request,
+            Err(err) => return error(400, "Bad Request", &err.to_string()),
+        };
+
+        let mut state = self.state.lock().await;
+
+        if !state.sessions.contains_key(&request.transport.session_id) {
+            return error(
+                400,
+                "Bad Request",
+                "websocket transport session does not exist or has already disconnected",
+            );
+        }
+
+        let on_session = state
+            .subscriptions
+            .values()
+            .filter(|sub| sub.session_id == request.transport.session_id);
+
+        if on_session.clone().count() >= MAX_SUBSCRIPTIONS_PER_SESSION {
+            return error(
+                429,
+                "Too Many Requests",
+                "number of websocket transport subscriptions exceeded",
+            );
+        }
+
+        if on_session
+            .clone()
+            .any(|sub| sub.kind == request.kind && sub.condition == request.condition)
+        {
+            return error(409, "Conflict", "subscription already exists");
+        }
+
+        let subscription = MockSubscription {
+            id: self.next_id(),
+            kind: request.kind,
+            version: request.version,
+            condition: request.condition,
+            session_id: request.transport.session_id,
+            created_at: now(),
+        };
+
+        let data = subscription.to_json("enabled");
+
+        state
+            .subscriptions
+            .insert(subscription.id.clone(), subscription);
+
+        let body = json!({
+            "data": [data],
+            "total": state.subscriptions.len(),
+            "total_cost": 0,
+            "max_total_cost": MAX_TOTAL_COST,
+        });
+
+        (202, Some(body))
+    }
+}
+
+fn error(status: u16, error: &str, message: &str) -> (u16, Option<serde_json::Value>) {
+    let body = json!({
+        "error": error,
+        "status": status,
+        "message": message,
+    });
+
+    (status, Some(body))
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        202 => "Accepted",
+        204 => "No Content",
+        400 => "Bad Request",
+        409 => "Conflict",
+        429 => "Too Many Requests",
+        _ => "Not Found",
+    }
+}
+
+/// Reads a request, returning its method, path and body.
+async fn read_request(stream: &mut TcpStream) -> Option<(String, String, Vec<u8>)> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 4096];
+
+    loop {
+        let read = stream.read(&mut chunk).await.ok()?;
+
+        if read == 0 {
+            return None;
+        }
+
+        buffer.extend_from_slice(&chunk[..read]);
+
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut request = httparse::Request::new(&mut headers);
+
+        let httparse::Status::Complete(header_len) = request.parse(&buffer).ok()? else {
+            continue;
+        };
+
+        let content_length = request
+            .headers
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case("Content-Length"))
+            .and_then(|header| std::str::from_utf8(header.value).ok()?.parse().ok())
+            .unwrap_or(0);
+
+        if buffer.len() - header_len < content_length {
+            continue;
+        }
+
+        return Some((
+            request.method?.to_string(),
+            request.path?.to_string(),
+            buffer[header_len..header_len + content_length].to_vec(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::broadcast;
+    use twitch_api::eventsub::EventType;
+    use twitch_api::twitch_oauth2::{AccessToken, ClientId, ClientSecret, RefreshToken, UserToken};
+
+    use super::*;
+    use crate::eventsub::EventSubClient;
+    use crate::eventsub::client::{NotificationPayload, ReconnectReason, RevocationReason, Status};
+
+    const TIMEOUT: Duration = Duration::from_secs(15);
+
+    struct Harness {
+        server: Arc<MockServer>,
+        client: Arc<EventSubClient>,
+        notifications: mpsc::UnboundedReceiver<NotificationPayload>,
+        status: broadcast::Receiver<Status>,
+    }
+
+    impl Harness {
+        /// Connects a client to a new mock server and subscribes to follows.
+        async fn start(keepalive: Duration) -> Self {
+            let server = MockServer::start_with_keepalive(keepalive).await.unwrap();
+
+            let token = UserToken::from_existing_unchecked(
+                AccessToken::from("token".to_string()),
+                None::<RefreshToken>,
+                ClientId::from("client".to_string()),
+                None::<ClientSecret>,
+                "tester".to_string().into(),
+                "1".to_string().into(),
+                None,
+                None,
+            );
+
+            let (notifications, client) =
+                EventSubClient::new(server.endpoints().clone(), Arc::new(token));
+            let status = client.status();
+
+            client.clone().connect().await.unwrap();
+            client
+                .subscribe(
+                    "tester",
+                    EventType::ChannelFollow,
+                    json!({ "broadcaster_user_id": "1", "moderator_user_id": "1" }),
+                )
+                .await
+                .unwrap();
+
+            Self {
+                server,
+                client,
+                notifications,
+                status,
+            }
+        }
+
+        async fn follow(&self) -> usize {
+            self.server
+                .notify(
+                    "channel.follow",
+                    json!({
+                        "user_id": "2",
+                        "user_login": "follower",
+                        "user_name": "Follower",
+                        "broadcaster_user_id": "1",
+                        "broadcaster_user_login": "tester",
+                        "broadcaster_user_name": "Tester",
+                        "followed_at": OffsetDateTime::now_utc().format(&Rfc3339).unwrap(),
+                    }),
+                )
+                .await
+        }
+
+        async fn recv(&mut self) -> NotificationPayload {
+            tokio::time::timeout(TIMEOUT, self.notifications.recv())
+                .await
+                .expect("timed out waiting for a notification")
+                .expect("client dropped")
+        }
+
+        async fn wait_for(&mut self, matches: impl Fn(&Status) -> bool) -> Status {
+            tokio::time::timeout(TIMEOUT, async {
+                loop {
+                    let status = self.status.recv().await.unwrap();
+
+                    if matches(&status) {
+                        return status;
+                    }
+                }
+            })
+            .await
+            .expect("timed out waiting for a status")
+        }
+
+        async fn follow_id(&self) -> String {
+            let state = self.server.state.lock().await;
+
+            state
+                .subscriptions
+                .values()
+                .find(|sub| sub.kind == "channel.follow")
+                .map(|sub| sub.id.clone())
+                .expect("no follow subscription")
+        }
+    }
+
+    #[tokio::test]
+    async fn delivers_notifications() {
+        let mut harness = Harness::start(KEEPALIVE).await;
+
+        assert_eq!(harness.follow().await, 1);
+
+        let payload = harness.recv().await;
+
+        assert_eq!(*payload.kind(), EventType::ChannelFollow);
+        assert!(!payload.event().is_opaque());
+    }
+
+    #[tokio::test]
+    async fn follows_requested_reconnects() {
+        let mut harness = Harness::start(KEEPALIVE).await;
+
+        assert_eq!(harness.server.trigger(MockAction::Reconnect).await, 1);
+
+        harness
+            .wait_for(|status| {
+                matches!(
+                    status,
+                    Status::Reconnecting {
+                        reason: ReconnectReason::Requested,
+                        ..
+                    }
+                )
+            })
+            .await;
+
+        tokio::time::timeout(TIMEOUT, async {
+            loop {
+                let state = harness.server.state.lock().await;
+
+                if state
+                    .sessions
+                    .values()
+                    .all(|session| session.generation > 0)
+                {
+                    break;
+                }
+
+                drop(state);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("timed out waiting for the new connection");
+
+        // Subscriptions carry over to the new connection without restoring
+        assert_eq!(harness.follow().await, 1);
+        assert_eq!(*harness.recv().await.kind(), EventType::ChannelFollow);
+    }
+
+    #[tokio::test]
+    async fn recovers_from_keepalive_timeouts() {
+        let mut harness = Harness::start(Duration::from_secs(1)).await;
+
+        harness
+            .server
+            .trigger(MockAction::Silence { enabled: true })
+            .await;
+
+        harness
+            .wait_for(|status| {
+                matches!(
+                    status,
+                    Status::Reconnecting {
+                        reason: ReconnectReason::KeepaliveTimeout,
+                        ..
+                    }
+                )
+            })
+            .await;
+
+        harness
+            .wait_for(|status| {
+                matches!(
+                    status,
+                    Status::Restored {
+                        subscriptions: 1,
+                        ..
+                    }
+                )
+            })
+            .await;
+
+        assert_eq!(harness.follow().await, 1);
+        assert_eq!(*harness.recv().await.kind(), EventType::ChannelFollow);
+    }
+
+    #[tokio::test]
+    async fn drops_revoked_subscriptions() {
+        let mut harness = Harness::start(KEEPALIVE).await;
+        let id = harness.follow_id().await;
+
+        let revoked = harness
+            .server
+            .trigger(MockAction::Revoke {
+                subscription_id: None,
+                event_type: Some("channel.follow".to_string()),
+                reason: "moderator_removed".to_string(),
+            })
+            .await;
+
+        assert_eq!(revoked, 1);
+
+        let status = harness
+            .wait_for(|status| matches!(status, Status::Revoked { .. }))
+            .await;
+
+        assert!(matches!(
+            status,
+            Status::Revoked {
+                event: EventType::ChannelFollow,
+                reason: RevocationReason::ModeratorRemoved,
+                resubscribed: false,
+                ..
+            }
+        ));
+
+        let subscriptions = harness.client.subscriptions.lock().await;
+        assert!(!subscriptions.values().any(|sub| sub.id == id));
+    }
+
+    #[tokio::test]
+    async fn resubscribes_after_unknown_revocations() {
+        let mut harness = Harness::start(KEEPALIVE).await;
+        let id = harness.follow_id().await;
+
+        harness
+            .server
+            .trigger(MockAction::Revoke {
+                subscription_id: Some(id.clone()),
+                event_type: None,
+                reason: "notification_failures_exceeded".to_string(),
+            })
+            .await;
+
+        let status = harness
+            .wait_for(|status| matches!(status, Status::Revoked { .. }))
+            .await;
+
+        assert!(matches!(
+            status,
+            Status::Revoked {
+                resubscribed: true,
+                ..
+            }
+        ));
+
+        assert_ne!(harness.follow_id().await, id);
+        assert_eq!(harness.follow().await, 1);
+        assert_eq!(*harness.recv().await.kind(), EventType::ChannelFollow);
+    }
+}
@@ -1,39 +1,80 @@
+pub mod catalog;
+pub mod chat;
 pub mod client;
+mod dedup;
+pub mod error;
+mod event;
+mod hype_train;
+#[cfg(debug_assertions)]
+pub mod mock;
 
 use std::sync::Arc;
 
+#[cfg(debug_assertions)]
+use anyhow::anyhow;
+use chat::ChatSources;
 pub use client::EventSubClient;
-use client::NotificationPayload;
+use client::{Endpoints, Usage};
+#[cfg(debug_assertions)]
+use mock::{MockAction, MockServer};
 use tauri::async_runtime::{self, Mutex};
 use tauri::ipc::Channel;
-use tauri::{AppHandle, Manager, State};
+use tauri::{AppHandle, Emitter, Manager, State, Webview};
+use tokio::sync::broadcast::error::RecvError;
 
 use crate::AppState;
 use crate::api::get_access_token;
+use crate::badges::BadgeCatalog;
 use crate::error::Error;
+use crate::history::MessageBuffer;
 
 #[tauri::command]
 pub async fn connect_eventsub(
     app_handle: AppHandle,
+    webview: Webview,
     state: State<'_, Mutex<AppState>>,
-    channel: Channel<NotificationPayload>,
+    channel: Channel,
+    endpoints: Option<Endpoints>,
 ) -> Result<(), Error> {
     let mut guard = state.lock().await;
-    let token = get_access_token(&guard)?.clone();
-    let helix = Arc::new(guard.helix.clone());
+    let subscribers = guard.subscribers.eventsub.clone();
+    let irc_subscribers = guard.subscribers.irc.clone();
+
+    subscribers.attach(webview.label(), channel);
 
     if let Some(client) = &guard.eventsub
         && client.connected()
     {
+        tracing::info!("Reusing existing EventSub connection");
         return Ok(());
     }
 
-    let (mut incoming, client) = EventSubClient::new(helix, Arc::new(token));
-    let client = Arc::new(client);
+    let token = get_access_token(&guard)?.clone();
+    let endpoints = endpoints.unwrap_or_else(Endpoints::from_env);
+
+    let (mut incoming, client) = EventSubClient::new(endpoints, Arc::new(token));
 
     guard.eventsub = Some(client.clone());
     drop(guard);
 
+    let mut status = client.status();
+    let status_handle = app_handle.clone();
+    let chat_handle = app_handle.clone();
+
+    async_runtime::spawn(async move {
+        loop {
+            match status.recv().await {
+                Ok(status) => {
+                    if let Err(err) = status_handle.emit("eventsubstatus", status) {
+                        tracing::error!(%err, "Failed to emit EventSub status");
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
     async_runtime::spawn(async move {
         if client.clone().connect().await.is_err() {
             let state = app_handle.state::<Mutex<AppState>>();
@@ -47,9 +88,73 @@ pub async fn connect_eventsub(
 
     async_runtime::spawn(async move {
         while let Some(message) = incoming.recv().await {
-            channel.send(message).unwrap();
+            // Chat events are delivered alongside IRC messages so the
+            // frontend handles them the same regardless of their source
+            if let Some(mut chat) = chat::normalize(&message) {
+                let sources = chat_handle.state::<ChatSources>();
+
+                if chat
+                    .channel_login()
+                    .is_some_and(|login| sources.contains(login))
+                {
+                    chat_handle
+                        .state::<BadgeCatalog>()
+                        .enrich(&chat_handle, &mut chat);
+
+                    chat_handle.state::<MessageBuffer>().push(&chat).await;
+
+                    irc_subscribers.send_irc(&[chat]);
+                }
+
+                continue;
+            }
+
+            subscribers.send(message);
         }
     });
 
     Ok(())
 }
+
+#[tauri::command]
+pub async fn get_eventsub_usage(state: State<'_, Mutex<AppState>>) -> Result<Option<Usage>, Error> {
+    let state = state.lock().await;
+
+    Ok(state.eventsub.as_ref().map(|client| client.usage()))
+}
+
+/// Starts the mock EventSub server if it isn't running, returning the
+/// endpoints to connect to it with.
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub async fn start_mock_eventsub(state: State<'_, Mutex<AppState>>) -> Result<Endpoints, Error> {
+    let mut state = state.lock().await;
+
+    if let Some(server) = &state.mock_eventsub {
+        return Ok(server.endpoints().clone());
+    }
+
+    let server = MockServer::start().await?;
+    let endpoints = server.endpoints().clone();
+
+    state.mock_eventsub = Some(server);
+
+    Ok(endpoints)
+}
+
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub async fn trigger_mock_eventsub(
+    state: State<'_, Mutex<AppState>>,
+    action: MockAction,
+) -> Result<usize, Error> {
+    let server = state.lock().await.mock_eventsub.clone();
+
+    let Some(server) = server else {
+        return Err(Error::Generic(anyhow!(
+            "Mock EventSub server is not running"
+        )));
+    };
+
+    Ok(server.trigger(action).await)
+}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::{Mutex, mpsc};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::Instrument;
+
+use crate::HTTP;
+use crate::error::Error;
+use crate::socket::{self, Socket, SocketHandler};
+
+const FFZ_WS_URI: &str = "wss://socket.frankerfacez.com/";
+pub const FFZ_API: &str = "https://api.frankerfacez.com/v1";
+
+/// How often the rooms of joined channels are fetched again. The socket only
+/// tells clients which rooms changed, and isn't guaranteed to, so changes it
+/// misses are still picked up.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Emote {
+    pub id: u64,
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub urls: HashMap<String, String>,
+    /// Whether the emote is overlaid on the previous emote.
+    #[serde(default)]
+    pub modifier: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmoteSet {
+    pub emoticons: Vec<Emote>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoomInfo {
+    pub set: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Room {
+    pub room: RoomInfo,
+    pub sets: HashMap<String, EmoteSet>,
+}
+
+impl Room {
+    /// Takes the emotes of the emote set of the room.
+    pub fn into_emotes(mut self) -> Vec<Emote> {
+        self.sets
+            .remove(&self.room.set.to_string())
+            .map(|set| set.emoticons)
+            .unwrap_or_default()
+    }
+}
+
+/// The emotes of a channel after they changed, keyed by the Twitch id of the
+/// channel.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum Event {
+    #[serde(rename = "ffz.room_update")]
+    RoomUpdate {
+        channel_id: String,
+        emotes: Vec<Emote>,
+    },
+}
+
+/// A joined channel and the id of its emote set once known.
+struct Channel {
+    id: String,
+    set: Option<u64>,
+    /// Ids of the emotes last sent for the channel.
+    emotes: Option<Vec<u64>>,
+}
+
+/// A command pushed by the server, in the form `-1 <command> <json>`.
+fn parse_command(text: &str) -> Option<(&str, serde_json::Value)> {
+    let (id, rest) = text.split_once(' ')?;
+
+    if id != "-1" {
+        return None;
+    }
+
+    let (command, data) = rest.split_once(' ').unwrap_or((rest, "null"));
+
+    Some((command, serde_json::from_str(data).ok()?))
+}
+
+pub struct FfzClient {
+    channels: Mutex<HashMap<String, Channel>>,
+    sender: mpsc::UnboundedSender<Event>,
+    /// Id of the next command sent to the server.
+    next_command: AtomicU64,
+    socket: Socket,
+}
+
+impl SocketHandler for FfzClient {
+    const NAME: &'static str = "FrankerFaceZ";
+    const URL: &'static str = FFZ_WS_URI;
+
+    fn socket(&self) -> &Socket {
+        &self.socket
+    }
+
+    async fn on_connect(&self) -> Vec<Message> {
+        let mut commands = vec![self.command("hello", &json!(["hyperion", false]))];
+
+        for login in self.channels.lock().await.keys() {
+            commands.push(self.command("sub", &format!("room.{login}").into()));
+        }
+
+        commands
+    }
+
+    async fn on_text(&self, text: &str) -> bool {
+        self.handle_text(text).await
+    }
+}
+
+impl FfzClient {
+    pub fn new() -> (mpsc::UnboundedReceiver<Event>, Self) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        let client = Self {
+            channels: Mutex::new(HashMap::new()),
+            sender,
+            next_command: AtomicU64::new(1),
+            socket: Socket::new(),
+        };
+
+        (receiver, client)
+    }
+
+    fn command(&self, command: &str, data: &serde_json::Value) -> Message {
+        let id = self.next_command.fetch_add(1, Ordering::Relaxed);
+
+        Message::Text(format!("{id} {command} {data}").into())
+    }
+
+    #[tracing::instrument(name = "ffz_connect", skip_all)]
+    pub async fn connect(self: Arc<Self>) -> Result<(), Error> {
+        socket::connect(self.clone()).await?;
+
+        tokio::spawn(
+            async move {
+                let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+                interval.tick().await;
+
+                loop {
+                    interval.tick().await;
+
+                    let logins: Vec<_> = self.channels.lock().await.keys().cloned().collect();
+
+                    for login in logins {
+                        self.refresh(&login, false).await;
+                    }
+                }
+            }
+            .in_current_span(),
+        );
+
+        Ok(())
+    }
+
+    /// Handles a message from the server, returning `false` if the server
+    /// asked for a reconnect.
+    async fn handle_text(&self, text: &str) -> bool {
+        let Some((command, data)) = parse_command(text) else {
+            return true;
+        };
+
+        tracing::trace!(%data, "Received {command} command");
+
+        let logins: Vec<_> = match command {
+            "reconnect" => {
+                tracing::info!("Reconnect requested");
+                return false;
+            }
+            // The emote sets of a channel were added or removed
+            "follow_sets" => data
+                .as_object()
+                .map(|rooms| rooms.keys().cloned().collect())
+                .unwrap_or_default(),
+            // The emotes of a set changed
+            "load_set" | "update_set" => {
+                let set = data.as_u64();
+
+                self.channels
+                    .lock()
+                    .await
+                    .iter()
+                    .filter(|(_, channel)| channel.set.is_none() || channel.set == set)
+                    .map(|(login, _)| login.clone())
+                    .collect()
+            }
+            _ => return true,
+        };
+
+        for login in logins {
+            self.refresh(&login, true).await;
+        }
+
+        true
+    }
+
+    /// Fetches the emotes of a joined channel and sends them to the frontend,
+    /// unless `force` is unset and they haven't changed since last sent.
+    async fn refresh(&self, login: &str, force: bool) {
+        let Some(id) = self
+            .channels
+            .lock()
+            .await
+            .get(login)
+            .map(|channel| channel.id.clone())
+        else {
+            return;
+        };
+
+        let room: Room = match self.fetch_room(&id).await {
+            Ok(room) => room,
+            Err(err) => {
+                tracing::error!(%err, "Failed to fetch FrankerFaceZ room for {login}");
+                return;
+            }
+        };
+
+        let set = room.room.set;
+        let emotes = room.into_emotes();
+
+        let mut ids: Vec<_> = emotes.iter().map(|emote| emote.id).collect();
+        ids.sort_unstable();
+
+        {
+            let mut channels = self.channels.lock().await;
+
+            let Some(channel) = channels.get_mut(login) else {
+                return;
+            };
+
+            // The first fetch only records the emotes, since they were
+            // already loaded when joining
+            let changed = channel.emotes.as_ref().is_some_and(|sent| *sent != ids);
+
+            channel.set = Some(set);
+            channel.emotes = Some(ids);
+
+            if !force && !changed {
+                return;
+            }
+        }
+
+        tracing::info!("Updated {} FrankerFaceZ emotes in {login}", emotes.len());
+
+        let event = Event::RoomUpdate {
+            channel_id: id,
+            emotes,
+        };
+
+        if let Err(err) = self.sender.send(event) {
+            tracing::error!(%err, "Error sending payload");
+        }
+    }
+
+    async fn fetch_room(&self, id: &str) -> Result<Room, Error> {
+        let room = HTTP
+            .get(format!("{FFZ_API}/room/id/{id}"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(room)
+    }
+
+    pub fn connected(&self) -> bool {
+        self.socket.connected()
+    }
+
+    #[tracing::instrument(name = "ffz_subscribe", skip(self))]
+    pub async fn subscribe(&self, channel: &str, id: &str) {
+        self.channels.lock().await.insert(
+            channel.to_string(),
+            Channel {
+                id: id.to_string(),
+                set: None,
+                emotes: None,
+            },
+        );
+
+        let topic = serde_json::Value::from(format!("room.{channel}"));
+        self.socket.send(self.command("sub", &topic));
+    }
+
+    pub async fn unsubscribe(&self, channel: &str) {
+        let removed = self.channels.lock().await.remove(channel).is_some();
+
+        if removed {
+            let topic = serde_json::Value::from(format!("room.{channel}"));
+            self.socket.send(self.command("unsub", &topic));
+        }
+    }
+}
@@ -0,0 +1,60 @@
+pub mod client;
+
+use std::sync::Arc;
+
+pub use client::FfzClient;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager, State, Webview, async_runtime};
+use tokio::sync::Mutex;
+
+use crate::AppState;
+use crate::emotes::EmoteRegistry;
+use crate::error::Error;
+
+#[tauri::command]
+pub async fn connect_ffz(
+    app_handle: AppHandle,
+    webview: Webview,
+    state: State<'_, Mutex<AppState>>,
+    channel: Channel,
+) -> Result<(), Error> {
+    let mut state = state.lock().await;
+    let subscribers = state.subscribers.ffz.clone();
+
+    subscribers.attach(webview.label(), channel);
+
+    // The client reconnects on its own, so it's reused even if it's currently
+    // disconnected.
+    if state.ffz.is_some() {
+        tracing::info!("Reusing existing FrankerFaceZ connection");
+        return Ok(());
+    }
+
+    let registry_handle = app_handle.clone();
+    let (mut incoming, client) = FfzClient::new();
+    let client = Arc::new(client);
+
+    state.ffz = Some(client.clone());
+    drop(state);
+
+    async_runtime::spawn(async move {
+        if client.clone().connect().await.is_err() {
+            let state = app_handle.state::<Mutex<AppState>>();
+            let mut state = state.lock().await;
+
+            state.ffz = None;
+        };
+    });
+
+    async_runtime::spawn(async move {
+        while let Some(event) = incoming.recv().await {
+            registry_handle
+                .state::<EmoteRegistry>()
+                .apply_ffz(&registry_handle, &event);
+
+            subscribers.send(event);
+        }
+    });
+
+    Ok(())
+}
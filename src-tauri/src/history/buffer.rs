@@ -0,0 +1,261 @@
+use std::collections::{HashMap, VecDeque};
+
+use tokio::sync::Mutex;
+
+use crate::irc::message::{ClearChatAction, ServerMessage};
+
+/// Bounded backlog of the messages received in a single channel.
+struct ChannelBuffer {
+    messages: VecDeque<ServerMessage>,
+    /// Sequence number of the message at the front of `messages`.
+    head: u64,
+    /// Maps message ids to their sequence number.
+    index: HashMap<String, u64>,
+}
+
+impl ChannelBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            messages: VecDeque::with_capacity(capacity),
+            head: 0,
+            index: HashMap::new(),
+        }
+    }
+
+    fn position(&self, message_id: &str) -> Option<usize> {
+        self.index
+            .get(message_id)
+            .map(|seq| (seq - self.head) as usize)
+    }
+
+    fn push(&mut self, message: ServerMessage, capacity: usize) {
+        if self.messages.len() == capacity
+            && let Some(evicted) = self.messages.pop_front()
+        {
+            if let Some(id) = evicted.message_id() {
+                self.index.remove(id);
+            }
+
+            self.head += 1;
+        }
+
+        if let Some(id) = message.message_id() {
+            let seq = self.head + self.messages.len() as u64;
+            self.index.insert(id.to_string(), seq);
+        }
+
+        self.messages.push_back(message);
+    }
+
+    /// Returns up to `limit` messages in chronological order, ending right
+    /// before the message with `before_id` or at the latest message if `None`.
+    fn page(&self, before_id: Option<&str>, limit: usize) -> Vec<ServerMessage> {
+        let end = match before_id {
+            Some(id) => match self.position(id) {
+                Some(pos) => pos,
+                None => return Vec::new(),
+            },
+            None => self.messages.len(),
+        };
+
+        let start = end.saturating_sub(limit);
+
+        self.messages.range(start..end).cloned().collect()
+    }
+
+    fn delete_message(&mut self, message_id: &str) {
+        let Some(pos) = self.position(message_id) else {
+            return;
+        };
+
+        match &mut self.messages[pos] {
+            ServerMessage::Privmsg(msg) => msg.deleted = true,
+            ServerMessage::UserNotice(msg) => msg.deleted = true,
+            _ => (),
+        }
+    }
+
+    fn delete_messages(&mut self, user_id: Option<&str>) {
+        for message in &mut self.messages {
+            match message {
+                ServerMessage::Privmsg(msg) if user_id.is_none_or(|id| msg.sender.id == id) => {
+                    msg.deleted = true;
+                }
+                ServerMessage::UserNotice(msg) if user_id.is_none_or(|id| msg.sender.id == id) => {
+                    msg.deleted = true;
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// In-memory backlog of every joined channel, allowing the frontend to restore
+/// its view after being reloaded.
+pub struct MessageBuffer {
+    capacity: usize,
+    channels: Mutex<HashMap<String, ChannelBuffer>>,
+}
+
+impl MessageBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            channels: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a message received from IRC. Moderation messages are applied
+    /// to the buffered messages they target in addition to being recorded.
+    pub async fn push(&self, message: &ServerMessage) {
+        if !matches!(
+            message,
+            ServerMessage::Privmsg(_)
+                | ServerMessage::UserNotice(_)
+                | ServerMessage::ClearChat(_)
+                | ServerMessage::ClearMsg(_)
+                | ServerMessage::Notice(_)
+        ) {
+            return;
+        }
+
+        let Some(channel) = message.channel_login() else {
+            return;
+        };
+
+        let mut channels = self.channels.lock().await;
+
+        let buffer = channels
+            .entry(channel.to_string())
+            .or_insert_with(|| ChannelBuffer::new(self.capacity));
+
+        match message {
+            ServerMessage::ClearMsg(msg) => buffer.delete_message(&msg.message_id),
+            ServerMessage::ClearChat(msg) => match &msg.action {
+                ClearChatAction::ChatClear => buffer.delete_messages(None),
+                ClearChatAction::UserBan { user_id, .. }
+                | ClearChatAction::UserTimeout { user_id, .. } => {
+                    buffer.delete_messages(Some(user_id.as_str()))
+                }
+            },
+            _ => (),
+        }
+
+        buffer.push(message.clone(), self.capacity);
+    }
+
+    /// Returns up to `limit` messages in chronological order, ending right
+    /// before the message with `before_id` or at the latest message if `None`.
+    /// Returns the whole backlog if `limit` is `None`.
+    pub async fn page(
+        &self,
+        channel: &str,
+        before_id: Option<&str>,
+        limit: Option<usize>,
+    ) -> Vec<ServerMessage> {
+        self.channels
+            .lock()
+            .await
+            .get(channel)
+            .map(|buffer| buffer.page(before_id, limit.unwrap_or(self.capacity)))
+            .unwrap_or_default()
+    }
+
+    pub async fn remove(&self, channel: &str) {
+        self.channels.lock().await.remove(channel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irc::message::IrcMessage;
+
+    fn privmsg(id: &str, user_id: &str) -> ServerMessage {
+        let line = format!(
+            "@badge-info=;badges=;color=;display-name={user_id};emotes=;id={id};mod=0;room-id=1;subscriber=0;tmi-sent-ts=1700000000000;user-id={user_id} :{user_id}!{user_id}@{user_id}.tmi.twitch.tv PRIVMSG #chan :hi"
+        );
+
+        ServerMessage::try_from(IrcMessage::parse(&line).unwrap()).unwrap()
+    }
+
+    fn ids(messages: &[ServerMessage]) -> Vec<&str> {
+        messages.iter().filter_map(|msg| msg.message_id()).collect()
+    }
+
+    fn is_deleted(message: &ServerMessage) -> bool {
+        matches!(message, ServerMessage::Privmsg(msg) if msg.deleted)
+    }
+
+    fn filled(capacity: usize, count: usize) -> ChannelBuffer {
+        let mut buffer = ChannelBuffer::new(capacity);
+
+        for i in 0..count {
+            buffer.push(privmsg(&i.to_string(), "u"), capacity);
+        }
+
+        buffer
+    }
+
+    #[test]
+    fn maps_ids_to_positions() {
+        let buffer = filled(5, 3);
+
+        assert_eq!(buffer.position("0"), Some(0));
+        assert_eq!(buffer.position("2"), Some(2));
+        assert_eq!(buffer.position("3"), None);
+    }
+
+    #[test]
+    fn evicts_oldest_messages() {
+        let buffer = filled(3, 5);
+
+        assert_eq!(buffer.head, 2);
+        assert_eq!(ids(&buffer.page(None, 10)), ["2", "3", "4"]);
+
+        // Positions shift with the head and evicted ids are forgotten
+        assert_eq!(buffer.position("0"), None);
+        assert_eq!(buffer.position("1"), None);
+        assert_eq!(buffer.position("2"), Some(0));
+        assert_eq!(buffer.position("4"), Some(2));
+        assert_eq!(buffer.index.len(), 3);
+    }
+
+    #[test]
+    fn pages_backwards() {
+        let buffer = filled(10, 6);
+
+        assert_eq!(ids(&buffer.page(None, 2)), ["4", "5"]);
+        assert_eq!(ids(&buffer.page(Some("4"), 2)), ["2", "3"]);
+        assert_eq!(ids(&buffer.page(Some("1"), 5)), ["0"]);
+        assert!(buffer.page(Some("0"), 5).is_empty());
+        assert!(buffer.page(Some("missing"), 5).is_empty());
+    }
+
+    #[test]
+    fn pages_after_eviction() {
+        let buffer = filled(4, 10);
+
+        assert_eq!(ids(&buffer.page(Some("8"), 2)), ["6", "7"]);
+        assert_eq!(ids(&buffer.page(Some("7"), 5)), ["6"]);
+        assert!(buffer.page(Some("5"), 5).is_empty());
+    }
+
+    #[test]
+    fn deletes_messages() {
+        let mut buffer = ChannelBuffer::new(10);
+        buffer.push(privmsg("a", "1"), 10);
+        buffer.push(privmsg("b", "2"), 10);
+        buffer.push(privmsg("c", "1"), 10);
+
+        buffer.delete_message("b");
+        assert!(is_deleted(&buffer.messages[1]));
+        assert!(!is_deleted(&buffer.messages[0]));
+
+        buffer.delete_messages(Some("1"));
+        assert!(buffer.messages.iter().all(is_deleted));
+
+        // Unknown and evicted ids are ignored
+        buffer.delete_message("missing");
+    }
+}
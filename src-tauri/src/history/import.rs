@@ -0,0 +1,580 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use time::{Date, Month, PrimitiveDateTime, Time, UtcOffset};
+use tokio::fs;
+
+use crate::HTTP;
+use crate::error::Error;
+use crate::irc::message::prefix::IrcPrefix;
+use crate::irc::message::{IrcMessage, IrcTags, ServerMessage};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Plain text logs written by Chatterino, e.g. `[12:34:56] user: message`.
+    Chatterino,
+    /// Raw IRC lines, as returned by justlog and rustlog with `?raw` or
+    /// dumped from a client.
+    Irc,
+    /// The JSON format served by justlog and rustlog.
+    Json,
+}
+
+impl LogFormat {
+    /// Guesses the format of a log from its first non-empty line.
+    pub fn detect(contents: &str) -> Option<Self> {
+        let line = contents.lines().map(str::trim).find(|l| !l.is_empty())?;
+
+        match line.chars().next()? {
+            '{' => Some(Self::Json),
+            '@' | ':' => Some(Self::Irc),
+            '[' | '#' => Some(Self::Chatterino),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportSummary {
+    /// Number of messages that were parsed from the logs.
+    pub parsed: usize,
+    /// Number of lines that could not be parsed.
+    pub failed: usize,
+    /// Number of messages that weren't already in the chat store.
+    pub imported: usize,
+    /// Files that could not be imported at all.
+    pub skipped_files: Vec<PathBuf>,
+    /// Parsed messages grouped by the channel they belong to.
+    #[serde(skip)]
+    pub messages: HashMap<String, Vec<ServerMessage>>,
+}
+
+impl ImportSummary {
+    fn push(&mut self, message: ServerMessage) {
+        let Some(channel) = message.channel_login() else {
+            self.failed += 1;
+            return;
+        };
+
+        self.parsed += 1;
+        self.messages
+            .entry(channel.to_string())
+            .or_default()
+            .push(message);
+    }
+
+    pub fn skip(&mut self, file: &Path) {
+        self.skipped_files.push(file.to_path_buf());
+    }
+}
+
+/// A single chat line parsed from a text log.
+#[derive(Debug)]
+struct TextLogLine {
+    timestamp: u64,
+    login: String,
+    name: String,
+    text: String,
+}
+
+impl TextLogLine {
+    /// Builds a PRIVMSG equivalent to the line so it can be stored alongside
+    /// messages received over IRC. Text logs don't record ids, so a stable id
+    /// is derived from the line itself to allow repeated imports.
+    fn into_irc(self, channel: &str, channel_id: &str) -> IrcMessage {
+        let id = format!(
+            "import-{:016x}",
+            fnv1a(&format!(
+                "{channel}:{}:{}:{}",
+                self.timestamp, self.login, self.text
+            ))
+        );
+
+        let tags: HashMap<_, _> = [
+            ("id", id),
+            ("room-id", channel_id.to_string()),
+            // Text logs don't contain user ids, which are left empty rather
+            // than guessed since they're used to look up the user
+            ("user-id", String::new()),
+            ("display-name", self.name),
+            ("tmi-sent-ts", self.timestamp.to_string()),
+            ("historical", "1".to_string()),
+            ("badges", String::new()),
+            ("badge-info", String::new()),
+            ("color", String::new()),
+            ("emotes", String::new()),
+            ("mod", "0".to_string()),
+            ("subscriber", "0".to_string()),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+
+        IrcMessage {
+            tags: IrcTags::from(tags),
+            prefix: Some(IrcPrefix::Full {
+                nick: self.login.clone(),
+                user: Some(self.login.clone()),
+                host: Some(format!("{}.tmi.twitch.tv", self.login)),
+            }),
+            command: "PRIVMSG".to_string(),
+            params: vec![format!("#{channel}"), self.text],
+        }
+    }
+}
+
+/// Infers the channel from a Chatterino file name, e.g.
+/// `forsen-2024-01-05.log`.
+pub fn channel_from_file_name(file_name: &str) -> Option<String> {
+    let stem = file_name.strip_suffix(".log")?;
+    let channel = stem.get(..stem.len().checked_sub(11)?)?;
+
+    (!channel.is_empty()).then(|| channel.to_lowercase())
+}
+
+fn fnv1a(input: &str) -> u64 {
+    input.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn parse_date(source: &str) -> Option<Date> {
+    let mut parts = source.splitn(3, '-');
+
+    let year = parts.next()?.parse().ok()?;
+    let month: u8 = parts.next()?.parse().ok()?;
+    let day = parts.next()?.get(..2)?.parse().ok()?;
+
+    Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()
+}
+
+fn parse_time(source: &str) -> Option<Time> {
+    let mut parts = source.splitn(3, ':').map(|p| p.parse::<u8>().ok());
+
+    Time::from_hms(parts.next()??, parts.next()??, parts.next()??).ok()
+}
+
+fn parse_irc_line(line: &str) -> Option<ServerMessage> {
+    let irc_message = match IrcMessage::parse(line) {
+        Ok(msg) => msg,
+        Err(err) => {
+            tracing::debug!(%err, "Failed to parse IRC line");
+            return None;
+        }
+    };
+
+    match ServerMessage::try_from(irc_message) {
+        Ok(msg) => Some(msg),
+        Err(err) => {
+            tracing::debug!(%err, "Failed to convert to ServerMessage");
+            None
+        }
+    }
+}
+
+pub fn parse_irc(contents: &str, summary: &mut ImportSummary) {
+    for line in contents.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        match parse_irc_line(line) {
+            Some(msg) => summary.push(msg),
+            None => summary.failed += 1,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonLog {
+    messages: Vec<JsonLogMessage>,
+}
+
+#[derive(Deserialize)]
+struct JsonLogMessage {
+    raw: String,
+}
+
+pub fn parse_json(contents: &str, summary: &mut ImportSummary) -> Result<(), Error> {
+    let log: JsonLog = serde_json::from_str(contents).map_err(|err| anyhow!(err))?;
+
+    for message in log.messages {
+        match parse_irc_line(&message.raw) {
+            Some(msg) => summary.push(msg),
+            None => summary.failed += 1,
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses a single `[HH:MM:SS] user: message` line.
+fn parse_chatterino_line(line: &str) -> Option<(Time, String, String, String)> {
+    let (time, rest) = line.strip_prefix('[')?.split_once("] ")?;
+    let time = parse_time(time)?;
+
+    // System messages such as timeouts don't have a sender
+    let (sender, text) = rest.split_once(": ")?;
+
+    // Localized names are written as "Name (login)"
+    let (name, login) = match sender.split_once(" (") {
+        Some((name, login)) => (name, login.strip_suffix(')')?),
+        None => (sender, sender),
+    };
+
+    if login.contains(' ') {
+        return None;
+    }
+
+    Some((
+        time,
+        name.to_string(),
+        login.to_lowercase(),
+        text.to_string(),
+    ))
+}
+
+/// Parses a Chatterino log. The date is read from the `# Start logging at`
+/// header, falling back to the `channel-YYYY-MM-DD.log` file name.
+fn parse_chatterino(contents: &str, file_name: &str) -> (Vec<TextLogLine>, usize) {
+    let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+
+    let stem = file_name.trim_end_matches(".log");
+    let mut date = stem
+        .get(stem.len().saturating_sub(10)..)
+        .and_then(parse_date);
+
+    let mut last_time = None;
+    let mut lines = Vec::new();
+    let mut failed = 0;
+
+    for line in contents.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(header) = line.strip_prefix("# Start logging at ") {
+            date = parse_date(header).or(date);
+            last_time = None;
+            continue;
+        }
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let (Some(current_date), Some((time, name, login, text))) =
+            (date, parse_chatterino_line(line))
+        else {
+            failed += 1;
+            continue;
+        };
+
+        // Sessions that run past midnight continue in the same file
+        let current_date = if last_time.is_some_and(|last| time < last) {
+            current_date.next_day().unwrap_or(current_date)
+        } else {
+            current_date
+        };
+
+        date = Some(current_date);
+        last_time = Some(time);
+
+        let timestamp = PrimitiveDateTime::new(current_date, time)
+            .assume_offset(offset)
+            .unix_timestamp()
+            * 1000;
+
+        lines.push(TextLogLine {
+            timestamp: timestamp as u64,
+            login,
+            name,
+            text,
+        });
+    }
+
+    (lines, failed)
+}
+
+/// Parses a Chatterino log file. `channel_id` is used as the `room-id` of the
+/// synthesized messages.
+pub fn parse_text(
+    contents: &str,
+    file_name: &str,
+    channel: &str,
+    channel_id: &str,
+    summary: &mut ImportSummary,
+) {
+    let (lines, failed) = parse_chatterino(contents, file_name);
+    summary.failed += failed;
+
+    for line in lines {
+        match ServerMessage::try_from(line.into_irc(channel, channel_id)) {
+            Ok(msg) => summary.push(msg),
+            Err(err) => {
+                tracing::debug!(%err, "Failed to convert log line to ServerMessage");
+                summary.failed += 1;
+            }
+        }
+    }
+}
+
+/// Recursively collects every file under `path`, or `path` itself if it's a
+/// file.
+pub async fn collect_files(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut pending = vec![path.to_path_buf()];
+
+    while let Some(path) = pending.pop() {
+        if fs::metadata(&path).await?.is_dir() {
+            let mut entries = fs::read_dir(&path).await?;
+
+            while let Some(entry) = entries.next_entry().await? {
+                pending.push(entry.path());
+            }
+        } else {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AvailableLogs {
+    available_logs: Vec<AvailableLog>,
+}
+
+#[derive(Deserialize)]
+struct AvailableLog {
+    year: String,
+    month: String,
+    day: Option<String>,
+}
+
+/// Downloads every log of a channel from a justlog or rustlog instance.
+pub async fn fetch_rustlog(
+    base_url: &str,
+    channel: &str,
+    summary: &mut ImportSummary,
+) -> Result<(), Error> {
+    let base_url = reqwest::Url::parse(base_url)
+        .map_err(|err| Error::Generic(anyhow!("Invalid log url: {err}")))?;
+
+    if base_url.cannot_be_a_base() {
+        return Err(Error::Generic(anyhow!("Invalid log url: {base_url}")));
+    }
+
+    // Segments are appended so the channel and dates are percent-encoded
+    let endpoint = |segments: &[&str]| {
+        let mut url = base_url.clone();
+
+        url.path_segments_mut()
+            .expect("url can be a base")
+            .pop_if_empty()
+            .extend(segments);
+
+        url
+    };
+
+    let list: AvailableLogs = HTTP
+        .get(endpoint(&["list"]))
+        .query(&[("channel", channel)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    tracing::info!("Found {} logs for {channel}", list.available_logs.len());
+
+    for log in list.available_logs {
+        let mut segments = vec!["channel", channel, log.year.as_str(), log.month.as_str()];
+        segments.extend(log.day.as_deref());
+
+        let mut url = endpoint(&segments);
+        url.set_query(Some("raw"));
+
+        let response = HTTP.get(url.clone()).send().await?;
+
+        if !response.status().is_success() {
+            tracing::warn!(status = %response.status(), "Failed to fetch {url}");
+            continue;
+        }
+
+        parse_irc(&response.text().await?, summary);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIVMSG: &str = "@badge-info=;badges=;color=;display-name=Foo;emotes=;id=abc;mod=0;room-id=1;subscriber=0;tmi-sent-ts=1700000000000;user-id=2 :foo!foo@foo.tmi.twitch.tv PRIVMSG #bar :hello";
+
+    #[test]
+    fn detects_formats() {
+        assert_eq!(
+            LogFormat::detect("{\"messages\": []}"),
+            Some(LogFormat::Json)
+        );
+        assert_eq!(LogFormat::detect(PRIVMSG), Some(LogFormat::Irc));
+        assert_eq!(
+            LogFormat::detect(":tmi.twitch.tv PING"),
+            Some(LogFormat::Irc)
+        );
+        assert_eq!(
+            LogFormat::detect("\n\n  [12:34:56] foo: hi"),
+            Some(LogFormat::Chatterino)
+        );
+        assert_eq!(
+            LogFormat::detect("# Start logging at 2024-01-05 12:00:00"),
+            Some(LogFormat::Chatterino)
+        );
+        assert_eq!(LogFormat::detect("hello world"), None);
+        assert_eq!(LogFormat::detect("   \n"), None);
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(
+            parse_date("2024-01-05"),
+            Date::from_calendar_date(2024, Month::January, 5).ok()
+        );
+        assert_eq!(
+            parse_date("2024-01-05 12:34:56 Central European Time"),
+            Date::from_calendar_date(2024, Month::January, 5).ok()
+        );
+        assert_eq!(parse_date("2024-13-05"), None);
+        assert_eq!(parse_date("2024-02-30"), None);
+        assert_eq!(parse_date("2024-01"), None);
+        assert_eq!(parse_date("not a date"), None);
+    }
+
+    #[test]
+    fn infers_channel_from_file_name() {
+        assert_eq!(
+            channel_from_file_name("forsen-2024-01-05.log").as_deref(),
+            Some("forsen")
+        );
+        assert_eq!(
+            channel_from_file_name("Some_User-2024-01-05.log").as_deref(),
+            Some("some_user")
+        );
+        assert_eq!(channel_from_file_name("-2024-01-05.log"), None);
+        assert_eq!(channel_from_file_name("2024-01-05.log"), None);
+        assert_eq!(channel_from_file_name("forsen-2024-01-05.txt"), None);
+    }
+
+    #[test]
+    fn parses_chatterino_lines() {
+        let (time, name, login, text) =
+            parse_chatterino_line("[12:34:56] foo: hello: world").unwrap();
+        assert_eq!(time, Time::from_hms(12, 34, 56).unwrap());
+        assert_eq!((name.as_str(), login.as_str()), ("foo", "foo"));
+        assert_eq!(text, "hello: world");
+
+        let (_, name, login, _) = parse_chatterino_line("[00:00:01] 名前 (Login): hi").unwrap();
+        assert_eq!((name.as_str(), login.as_str()), ("名前", "login"));
+
+        // System messages and malformed lines
+        assert!(parse_chatterino_line("[12:34:56] foo has been timed out for 10s.").is_none());
+        assert!(parse_chatterino_line("[12:34:56] some user: hi").is_none());
+        assert!(parse_chatterino_line("[25:00:00] foo: hi").is_none());
+        assert!(parse_chatterino_line("12:34:56 foo: hi").is_none());
+        assert!(parse_chatterino_line("[12:34] foo: hi").is_none());
+    }
+
+    #[test]
+    fn parses_chatterino_logs() {
+        let contents = "\
+# Start logging at 2024-01-05 23:59:00 UTC
+[23:59:58] foo: before midnight
+[00:00:02] bar: after midnight
+garbage
+[00:00:03] baz timed out
+";
+
+        let (lines, failed) = parse_chatterino(contents, "chan-2024-01-01.log");
+        assert_eq!(failed, 2);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].login, "foo");
+        // The second line rolls over to the next day
+        assert_eq!(lines[1].timestamp - lines[0].timestamp, 4000);
+
+        // Without a header or dated file name nothing can be placed in time
+        let (lines, failed) = parse_chatterino("[12:00:00] foo: hi", "chan.log");
+        assert!(lines.is_empty());
+        assert_eq!(failed, 1);
+    }
+
+    #[test]
+    fn parses_text_logs_into_privmsgs() {
+        let mut summary = ImportSummary::default();
+        parse_text(
+            "[12:00:00] Foo: hi",
+            "chan-2024-01-05.log",
+            "chan",
+            "123",
+            &mut summary,
+        );
+
+        assert_eq!((summary.parsed, summary.failed), (1, 0));
+
+        let ServerMessage::Privmsg(msg) = &summary.messages["chan"][0] else {
+            panic!("expected a PRIVMSG");
+        };
+
+        assert_eq!(msg.channel_id, "123");
+        assert_eq!(msg.sender.login, "foo");
+        assert_eq!(msg.sender.id, "");
+        assert_eq!(msg.message_text, "hi");
+        assert!(msg.is_recent);
+
+        // Importing the same line again yields the same id
+        let mut again = ImportSummary::default();
+        parse_text(
+            "[12:00:00] Foo: hi",
+            "chan-2024-01-05.log",
+            "chan",
+            "123",
+            &mut again,
+        );
+        assert_eq!(
+            again.messages["chan"][0].message_id(),
+            summary.messages["chan"][0].message_id()
+        );
+    }
+
+    #[test]
+    fn parses_irc_logs() {
+        let mut summary = ImportSummary::default();
+        parse_irc(&format!("{PRIVMSG}\n\nnot irc at all\n"), &mut summary);
+
+        assert_eq!((summary.parsed, summary.failed), (1, 1));
+        assert_eq!(summary.messages["bar"].len(), 1);
+    }
+
+    #[test]
+    fn counts_messages_without_a_channel_as_failed() {
+        let mut summary = ImportSummary::default();
+        parse_irc(":tmi.twitch.tv PING :tmi.twitch.tv", &mut summary);
+
+        assert_eq!((summary.parsed, summary.failed), (0, 1));
+        assert!(summary.messages.is_empty());
+    }
+
+    #[test]
+    fn parses_json_logs() {
+        let mut summary = ImportSummary::default();
+        let contents = serde_json::json!({
+            "messages": [{ "raw": PRIVMSG }, { "raw": "garbage" }]
+        })
+        .to_string();
+
+        parse_json(&contents, &mut summary).unwrap();
+        assert_eq!((summary.parsed, summary.failed), (1, 1));
+
+        assert!(parse_json("{ not json", &mut summary).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+pub mod buffer;
+pub mod import;
+pub mod providers;
+pub mod store;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+pub use buffer::MessageBuffer;
+use import::{ImportSummary, LogFormat};
+use serde::Deserialize;
+pub use store::ChatStore;
+use tauri::State;
+use tokio::sync::Mutex;
+
+use crate::AppState;
+use crate::api::get_access_token;
+use crate::error::Error;
+use crate::irc::message::ServerMessage;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ImportSource {
+    /// A log file or a directory of log files on disk.
+    Path {
+        path: PathBuf,
+        format: Option<LogFormat>,
+        /// Channel the logs belong to. Only used for text logs and inferred
+        /// from the file name if omitted.
+        channel: Option<String>,
+    },
+    /// A self-hosted justlog or rustlog instance.
+    Rustlog { url: String, channel: String },
+}
+
+async fn resolve_channel_id(
+    state: &State<'_, Mutex<AppState>>,
+    cache: &mut HashMap<String, String>,
+    channel: &str,
+) -> Result<String, Error> {
+    if let Some(id) = cache.get(channel) {
+        return Ok(id.clone());
+    }
+
+    let (helix, token) = {
+        let state = state.lock().await;
+        (state.helix.clone(), get_access_token(&state)?.clone())
+    };
+
+    let Some(user) = helix.get_user_from_login(channel, &token).await? else {
+        return Err(Error::Generic(anyhow!("User {channel} not found")));
+    };
+
+    let id = user.id.take();
+    cache.insert(channel.to_string(), id.clone());
+
+    Ok(id)
+}
+
+#[tracing::instrument(skip(state, store))]
+#[tauri::command]
+pub async fn import_logs(
+    state: State<'_, Mutex<AppState>>,
+    store: State<'_, ChatStore>,
+    source: ImportSource,
+) -> Result<ImportSummary, Error> {
+    let mut summary = ImportSummary::default();
+
+    match source {
+        ImportSource::Path {
+            path,
+            format,
+            channel,
+        } => {
+            let mut channel_ids = HashMap::new();
+
+            for file in import::collect_files(&path).await? {
+                let contents = match tokio::fs::read_to_string(&file).await {
+                    Ok(contents) => contents,
+                    Err(err) => {
+                        tracing::warn!(%err, "Skipping unreadable file {}", file.display());
+                        summary.skip(&file);
+                        continue;
+                    }
+                };
+
+                let Some(format) = format.or_else(|| LogFormat::detect(&contents)) else {
+                    tracing::warn!("Skipping {} with unknown format", file.display());
+                    summary.skip(&file);
+                    continue;
+                };
+
+                match format {
+                    LogFormat::Irc => import::parse_irc(&contents, &mut summary),
+                    LogFormat::Json => {
+                        if let Err(err) = import::parse_json(&contents, &mut summary) {
+                            tracing::warn!(%err, "Skipping malformed file {}", file.display());
+                            summary.skip(&file);
+                        }
+                    }
+                    LogFormat::Chatterino => {
+                        let file_name = file
+                            .file_name()
+                            .map(|name| name.to_string_lossy().to_string())
+                            .unwrap_or_default();
+
+                        let Some(channel) = channel
+                            .clone()
+                            .or_else(|| import::channel_from_file_name(&file_name))
+                        else {
+                            tracing::warn!("Skipping {file_name}, unable to infer channel");
+                            summary.skip(&file);
+                            continue;
+                        };
+
+                        let channel_id = match resolve_channel_id(
+                            &state,
+                            &mut channel_ids,
+                            &channel,
+                        )
+                        .await
+                        {
+                            Ok(id) => id,
+                            Err(err) => {
+                                tracing::warn!(%err, "Skipping {file_name}, unable to resolve {channel}");
+                                summary.skip(&file);
+                                continue;
+                            }
+                        };
+
+                        import::parse_text(
+                            &contents,
+                            &file_name,
+                            &channel,
+                            &channel_id,
+                            &mut summary,
+                        );
+                    }
+                }
+            }
+        }
+        ImportSource::Rustlog { url, channel } => {
+            import::fetch_rustlog(&url, &channel, &mut summary).await?;
+        }
+    }
+
+    for (channel, messages) in &summary.messages {
+        let inserted = store.insert(channel, messages).await?;
+        tracing::info!("Imported {inserted} new messages into {channel}");
+
+        summary.imported += inserted;
+    }
+
+    tracing::info!(
+        parsed = summary.parsed,
+        failed = summary.failed,
+        skipped = summary.skipped_files.len(),
+        "Finished importing logs"
+    );
+
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn search_history(
+    store: State<'_, ChatStore>,
+    channel: String,
+    query: String,
+    limit: usize,
+) -> Result<Vec<ServerMessage>, Error> {
+    store.search(&channel, &query, limit).await
+}
+
+#[tauri::command]
+pub async fn get_channel_buffer(
+    buffer: State<'_, MessageBuffer>,
+    channel: String,
+    before_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ServerMessage>, Error> {
+    Ok(buffer.page(&channel, before_id.as_deref(), limit).await)
+}
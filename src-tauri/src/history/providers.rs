@@ -0,0 +1,260 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use super::ChatStore;
+use crate::HTTP;
+use crate::error::Error;
+use crate::irc::message::{IrcMessage, ServerMessage};
+
+const ROBOTTY_URL: &str = "https://recent-messages.robotty.de/api/v2/recent-messages";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+type CacheEntry = (Instant, u32, Vec<ServerMessage>);
+
+/// Responses from remote providers keyed by provider name and channel.
+static CACHE: LazyLock<Mutex<HashMap<(String, String), CacheEntry>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// A source of chat history for a channel.
+pub trait HistoryProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Whether the provider fetches history from a remote service. Remote
+    /// providers are tried in order until one succeeds.
+    fn is_remote(&self) -> bool {
+        true
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        channel: &'a str,
+        limit: u32,
+    ) -> BoxFuture<'a, Result<Vec<ServerMessage>, Error>>;
+}
+
+#[derive(Debug, Deserialize)]
+struct RecentMessages {
+    #[serde(default)]
+    messages: Vec<String>,
+}
+
+/// Provider for services implementing the recent-messages API.
+pub struct RecentMessagesProvider {
+    name: String,
+    base_url: String,
+}
+
+impl RecentMessagesProvider {
+    pub fn robotty() -> Self {
+        Self {
+            name: "robotty".into(),
+            base_url: ROBOTTY_URL.into(),
+        }
+    }
+
+    /// A user-provided instance, named after its url so that responses from
+    /// different instances aren't cached under the same name.
+    pub fn custom(base_url: String) -> Self {
+        let base_url = base_url.trim_end_matches('/').to_string();
+
+        Self {
+            name: base_url.clone(),
+            base_url,
+        }
+    }
+}
+
+impl HistoryProvider for RecentMessagesProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        channel: &'a str,
+        limit: u32,
+    ) -> BoxFuture<'a, Result<Vec<ServerMessage>, Error>> {
+        Box::pin(async move {
+            let response: RecentMessages = HTTP
+                .get(format!("{}/{channel}?limit={limit}", self.base_url))
+                .timeout(REQUEST_TIMEOUT)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            let server_messages = response
+                .messages
+                .into_iter()
+                .filter_map(|msg| {
+                    let irc_message = match IrcMessage::parse(&msg) {
+                        Ok(msg) => msg,
+                        Err(err) => {
+                            tracing::warn!(%err, "Failed to parse IRC message");
+                            return None;
+                        }
+                    };
+
+                    match ServerMessage::try_from(irc_message) {
+                        Ok(server_msg) => Some(server_msg),
+                        Err(err) => {
+                            tracing::warn!(%err, "Failed to convert to ServerMessage");
+                            None
+                        }
+                    }
+                })
+                .collect();
+
+            Ok(server_messages)
+        })
+    }
+}
+
+/// Wraps a provider to reuse its results for the same channel for a short
+/// while, e.g. when quickly leaving and rejoining a channel.
+pub struct CachedProvider<P> {
+    inner: P,
+}
+
+impl<P: HistoryProvider> CachedProvider<P> {
+    pub fn new(inner: P) -> Self {
+        Self { inner }
+    }
+}
+
+impl<P: HistoryProvider> HistoryProvider for CachedProvider<P> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_remote(&self) -> bool {
+        self.inner.is_remote()
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        channel: &'a str,
+        limit: u32,
+    ) -> BoxFuture<'a, Result<Vec<ServerMessage>, Error>> {
+        Box::pin(async move {
+            let key = (self.name().to_string(), channel.to_string());
+
+            if let Some((fetched_at, cached_limit, messages)) = CACHE.lock().await.get(&key)
+                && fetched_at.elapsed() < CACHE_TTL
+                && *cached_limit >= limit
+            {
+                tracing::debug!("Using cached history from {}", self.name());
+                return Ok(messages.clone());
+            }
+
+            let messages = self.inner.fetch(channel, limit).await?;
+
+            CACHE
+                .lock()
+                .await
+                .insert(key, (Instant::now(), limit, messages.clone()));
+
+            Ok(messages)
+        })
+    }
+}
+
+/// Provider for messages imported into the
+/// [`ChatStore`].
+pub struct LocalProvider {
+    app_handle: AppHandle,
+}
+
+impl LocalProvider {
+    pub fn new(app_handle: AppHandle) -> Self {
+        Self { app_handle }
+    }
+}
+
+impl HistoryProvider for LocalProvider {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        channel: &'a str,
+        limit: u32,
+    ) -> BoxFuture<'a, Result<Vec<ServerMessage>, Error>> {
+        Box::pin(async move {
+            let store = self.app_handle.state::<ChatStore>();
+            store.recent(channel, limit as usize).await
+        })
+    }
+}
+
+/// Fetches history from every provider and merges the results. Remote
+/// providers are used as fallbacks for each other while local providers are
+/// always included. Messages are deduplicated by id and ordered by their server
+/// timestamp, keeping the most recent `limit` messages.
+pub async fn fetch_merged(
+    providers: &[Box<dyn HistoryProvider>],
+    channel: &str,
+    limit: u32,
+) -> Vec<ServerMessage> {
+    let mut messages = Vec::new();
+    let mut remote_fetched = false;
+
+    for provider in providers {
+        if provider.is_remote() && remote_fetched {
+            continue;
+        }
+
+        match provider.fetch(channel, limit).await {
+            Ok(fetched) => {
+                tracing::info!(
+                    "Fetched {} messages from {}",
+                    fetched.len(),
+                    provider.name()
+                );
+
+                remote_fetched |= provider.is_remote();
+                messages.extend(fetched);
+            }
+            Err(err) => {
+                tracing::warn!(%err, "Failed to fetch history from {}", provider.name());
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+
+    messages.retain(|msg| match msg.message_id() {
+        Some(id) => seen.insert(id.to_string()),
+        None => true,
+    });
+
+    // Messages without a timestamp inherit the one of the message before them
+    // so they stay in place when sorting
+    let mut last_timestamp = 0;
+
+    let mut keyed: Vec<_> = messages
+        .into_iter()
+        .map(|msg| {
+            last_timestamp = msg.server_timestamp().unwrap_or(last_timestamp);
+            (last_timestamp, msg)
+        })
+        .collect();
+
+    keyed.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let skip = keyed.len().saturating_sub(limit as usize);
+    keyed.into_iter().skip(skip).map(|(_, msg)| msg).collect()
+}
@@ -0,0 +1,249 @@
+use std::collections::{HashMap, HashSet};
+use std::io::SeekFrom;
+use std::path::PathBuf;
+
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use crate::error::Error;
+use crate::irc::message::{AsRawIrc, IrcMessage, ServerMessage};
+
+/// Location of a stored message within a channel's log.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    timestamp: u64,
+    offset: u64,
+    len: usize,
+}
+
+/// Index of a channel's log, built once when the channel is first accessed
+/// so recent messages can be read without parsing the whole file again.
+#[derive(Default)]
+struct ChannelIndex {
+    ids: HashSet<String>,
+    /// Entries ordered by their server timestamp.
+    entries: Vec<Entry>,
+    /// Length of the log in bytes.
+    len: u64,
+}
+
+impl ChannelIndex {
+    fn push(&mut self, message: &ServerMessage, offset: u64, len: usize) {
+        if let Some(id) = message.message_id() {
+            self.ids.insert(id.to_string());
+        }
+
+        self.entries.push(Entry {
+            timestamp: message.server_timestamp().unwrap_or_default(),
+            offset,
+            len,
+        });
+    }
+}
+
+fn parse_line(line: &str) -> Option<ServerMessage> {
+    ServerMessage::try_from(IrcMessage::parse(line).ok()?).ok()
+}
+
+/// Persistent chat history stored as raw IRC lines, one file per channel.
+pub struct ChatStore {
+    dir: PathBuf,
+    /// Indexes of the channels loaded so far.
+    channels: Mutex<HashMap<String, ChannelIndex>>,
+}
+
+impl ChatStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            channels: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn channel_path(&self, channel: &str) -> PathBuf {
+        self.dir.join(format!("{}.log", channel.to_lowercase()))
+    }
+
+    async fn read_channel(&self, channel: &str) -> Result<Option<String>, Error> {
+        match fs::read_to_string(self.channel_path(channel)).await {
+            Ok(contents) => Ok(Some(contents)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn build_index(&self, channel: &str) -> Result<ChannelIndex, Error> {
+        let mut index = ChannelIndex::default();
+
+        let Some(contents) = self.read_channel(channel).await? else {
+            return Ok(index);
+        };
+
+        let mut offset = 0;
+
+        for line in contents.split_inclusive('\n') {
+            let text = line.trim_end_matches(['\r', '\n']);
+
+            if let Some(message) = parse_line(text) {
+                index.push(&message, offset, text.len());
+            }
+
+            offset += line.len() as u64;
+        }
+
+        index.entries.sort_by_key(|entry| entry.timestamp);
+        index.len = offset;
+
+        Ok(index)
+    }
+
+    /// Returns the index of the channel, building it first if needed.
+    async fn index<'a>(
+        &self,
+        channels: &'a mut HashMap<String, ChannelIndex>,
+        channel: &str,
+    ) -> Result<&'a mut ChannelIndex, Error> {
+        let channel = channel.to_lowercase();
+
+        if !channels.contains_key(&channel) {
+            let index = self.build_index(&channel).await?;
+            channels.insert(channel.clone(), index);
+        }
+
+        Ok(channels.get_mut(&channel).unwrap())
+    }
+
+    /// Writes the messages that haven't been stored yet to the channel's log.
+    /// Messages without a `message_id` are skipped. Returns the number of
+    /// messages written.
+    pub async fn insert(&self, channel: &str, messages: &[ServerMessage]) -> Result<usize, Error> {
+        let mut channels = self.channels.lock().await;
+        let index = self.index(&mut channels, channel).await?;
+
+        let mut buffer = String::new();
+        let mut ids = HashSet::new();
+        let mut written = Vec::new();
+
+        for message in messages {
+            let Some(id) = message.message_id() else {
+                continue;
+            };
+
+            if index.ids.contains(id) || !ids.insert(id) {
+                continue;
+            }
+
+            let raw = message.as_raw_irc();
+            let offset = index.len + buffer.len() as u64;
+
+            written.push((message, offset, raw.len()));
+            buffer.push_str(&raw);
+            buffer.push('\n');
+        }
+
+        if written.is_empty() {
+            return Ok(0);
+        }
+
+        fs::create_dir_all(&self.dir).await?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.channel_path(channel))
+            .await?;
+
+        file.write_all(buffer.as_bytes()).await?;
+        file.flush().await?;
+
+        for (message, offset, len) in &written {
+            index.push(message, *offset, *len);
+        }
+
+        index.entries.sort_by_key(|entry| entry.timestamp);
+        index.len += buffer.len() as u64;
+
+        Ok(written.len())
+    }
+
+    /// Returns up to `limit` of the most recent messages stored for the
+    /// channel, ordered by their server timestamp. All returned messages are
+    /// marked as recent.
+    pub async fn recent(&self, channel: &str, limit: usize) -> Result<Vec<ServerMessage>, Error> {
+        let path = self.channel_path(channel);
+
+        let entries = {
+            let mut channels = self.channels.lock().await;
+            let index = self.index(&mut channels, channel).await?;
+            let skip = index.entries.len().saturating_sub(limit);
+
+            index.entries[skip..].to_vec()
+        };
+
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut file = File::open(path).await?;
+        let mut messages = Vec::with_capacity(entries.len());
+
+        for entry in entries {
+            let mut line = vec![0; entry.len];
+
+            file.seek(SeekFrom::Start(entry.offset)).await?;
+            file.read_exact(&mut line).await?;
+
+            let Some(mut message) = std::str::from_utf8(&line).ok().and_then(parse_line) else {
+                continue;
+            };
+
+            match message {
+                ServerMessage::Privmsg(ref mut msg) => msg.is_recent = true,
+                ServerMessage::UserNotice(ref mut msg) => msg.is_recent = true,
+                _ => (),
+            }
+
+            messages.push(message);
+        }
+
+        Ok(messages)
+    }
+
+    /// Returns up to `limit` of the most recent messages in the channel whose
+    /// text or sender contains `query`, ignoring case.
+    pub async fn search(
+        &self,
+        channel: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<ServerMessage>, Error> {
+        let query = query.to_lowercase();
+
+        let Some(contents) = self.read_channel(channel).await? else {
+            return Ok(Vec::new());
+        };
+
+        let mut matches: Vec<_> = contents
+            .lines()
+            .filter_map(parse_line)
+            .filter(|msg| {
+                let (text, sender) = match msg {
+                    ServerMessage::Privmsg(msg) => (Some(&msg.message_text), &msg.sender),
+                    ServerMessage::UserNotice(msg) => (msg.message_text.as_ref(), &msg.sender),
+                    _ => return false,
+                };
+
+                text.is_some_and(|text| text.to_lowercase().contains(&query))
+                    || sender.login.contains(&query)
+                    || sender.name.to_lowercase().contains(&query)
+            })
+            .collect();
+
+        matches.sort_by_key(|msg| msg.server_timestamp().unwrap_or_default());
+
+        let skip = matches.len().saturating_sub(limit);
+
+        Ok(matches.split_off(skip))
+    }
+}
@@ -0,0 +1,496 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, SystemTime};
+
+use anyhow::anyhow;
+use percent_encoding::percent_decode_str;
+use sha2::{Digest, Sha256};
+use tauri::http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL, CONTENT_TYPE};
+use tauri::http::{Request, Response, StatusCode, Uri};
+use tauri::{AppHandle, Manager, Runtime, UriSchemeContext, UriSchemeResponder, async_runtime};
+use tokio::fs;
+use tokio::sync::Mutex;
+
+use crate::error::Error;
+
+/// Scheme images are served through, with the percent-encoded source url as
+/// the path.
+pub const SCHEME: &str = "hyperion-img";
+
+/// Size the cache is trimmed down to once exceeded.
+pub const MAX_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Largest image that is downloaded.
+const MAX_IMAGE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Hosts images are downloaded from. Kept in sync with `src/lib/images.ts`.
+const ALLOWED_HOSTS: &[&str] = &[
+    "static-cdn.jtvnw.net",
+    "d3aqoihi2n8ty8.cloudfront.net",
+    "cdn.7tv.app",
+    "cdn.betterttv.net",
+    "cdn.frankerfacez.com",
+];
+
+/// Images are fetched without the Twitch headers of [`crate::HTTP`] since
+/// they are served by third parties.
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .unwrap()
+});
+
+struct Entry {
+    size: u64,
+    last_used: SystemTime,
+}
+
+#[derive(Default)]
+struct Index {
+    entries: HashMap<String, Entry>,
+    size: u64,
+}
+
+/// Emote, badge and paint images stored on disk, evicting the least recently
+/// used images when the cache grows past its size limit.
+pub struct ImageCache {
+    dir: PathBuf,
+    max_size: u64,
+    index: Mutex<Index>,
+    /// Locks held while an image is downloaded so concurrent requests for it
+    /// wait for the same download.
+    in_flight: std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    /// Counter for naming temporary files uniquely.
+    next_temp: AtomicU64,
+    /// Incremented when the cache is cleared, so downloads started before
+    /// don't add their images back.
+    generation: AtomicU64,
+}
+
+impl ImageCache {
+    pub fn new(dir: PathBuf, max_size: u64) -> Self {
+        let mut index = Index::default();
+
+        if let Err(err) = std::fs::create_dir_all(&dir) {
+            tracing::error!(%err, "Failed to create image cache directory");
+        }
+
+        for entry in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+
+            if !metadata.is_file() {
+                continue;
+            }
+
+            // Left behind by downloads that were interrupted
+            if entry.path().extension().is_some_and(|ext| ext == "tmp") {
+                let _ = std::fs::remove_file(entry.path());
+                continue;
+            }
+
+            let size = metadata.len();
+
+            index.size += size;
+            index.entries.insert(
+                entry.file_name().to_string_lossy().into_owned(),
+                Entry {
+                    size,
+                    last_used: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                },
+            );
+        }
+
+        tracing::info!(
+            "Loaded {} cached images ({} bytes)",
+            index.entries.len(),
+            index.size
+        );
+
+        Self {
+            dir,
+            max_size,
+            index: Mutex::new(index),
+            in_flight: std::sync::Mutex::default(),
+            next_temp: AtomicU64::default(),
+            generation: AtomicU64::default(),
+        }
+    }
+
+    fn key(url: &str) -> String {
+        format!("{:x}", Sha256::digest(url.as_bytes()))
+    }
+
+    /// Returns the image at the url, downloading and storing it if it isn't
+    /// cached yet.
+    pub async fn get(&self, url: &str) -> Result<Vec<u8>, Error> {
+        let key = Self::key(url);
+
+        if let Some(bytes) = self.read(&key).await {
+            return Ok(bytes);
+        }
+
+        let lock = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+
+        let result = {
+            let _guard = lock.lock().await;
+
+            // The image may have been stored while waiting for another download
+            match self.read(&key).await {
+                Some(bytes) => Ok(bytes),
+                None => self.download(url, &key).await,
+            }
+        };
+
+        let mut in_flight = self.in_flight.lock().unwrap();
+
+        // Only the map and this request hold the lock if no one else is waiting
+        if Arc::strong_count(&lock) == 2 {
+            in_flight.remove(&key);
+        }
+
+        drop(lock);
+
+        result
+    }
+
+    /// Reads a cached image, returning `None` if it isn't cached.
+    async fn read(&self, key: &str) -> Option<Vec<u8>> {
+        let path = self.dir.join(key);
+
+        if !self.index.lock().await.entries.contains_key(key) {
+            return None;
+        }
+
+        match fs::read(&path).await {
+            Ok(bytes) => {
+                self.touch(key, path).await;
+                Some(bytes)
+            }
+            Err(err) => {
+                tracing::warn!(%err, "Failed to read cached image, downloading again");
+                self.forget(key).await;
+                None
+            }
+        }
+    }
+
+    async fn download(&self, url: &str, key: &str) -> Result<Vec<u8>, Error> {
+        let generation = self.generation.load(Ordering::Acquire);
+        let mut response = CLIENT.get(url).send().await?.error_for_status()?;
+
+        let is_image = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_none_or(|value| value.starts_with("image/"));
+
+        if !is_image {
+            return Err(Error::Generic(anyhow!("Response is not an image")));
+        }
+
+        if response.content_length().unwrap_or_default() > MAX_IMAGE_SIZE {
+            return Err(Error::Generic(anyhow!("Image is too large")));
+        }
+
+        let mut bytes = Vec::new();
+
+        while let Some(chunk) = response.chunk().await? {
+            if (bytes.len() + chunk.len()) as u64 > MAX_IMAGE_SIZE {
+                return Err(Error::Generic(anyhow!("Image is too large")));
+            }
+
+            bytes.extend_from_slice(&chunk);
+        }
+
+        if content_type(&bytes).is_none() {
+            return Err(Error::Generic(anyhow!("Unrecognized image format")));
+        }
+
+        // Written to a temporary file first so a concurrent read never sees a
+        // partial image
+        let temp = self.dir.join(format!(
+            "{key}.{}.tmp",
+            self.next_temp.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        fs::write(&temp, &bytes).await?;
+
+        let path = self.dir.join(key);
+
+        if let Err(err) = fs::rename(&temp, &path).await {
+            let _ = fs::remove_file(&temp).await;
+            return Err(err.into());
+        }
+
+        let key = key.to_string();
+
+        let mut index = self.index.lock().await;
+        let size = bytes.len() as u64;
+
+        // The cache was cleared during the download, which didn't see the
+        // image since it wasn't in the index yet
+        if self.generation.load(Ordering::Acquire) != generation {
+            let _ = fs::remove_file(&path).await;
+            return Ok(bytes);
+        }
+
+        if let Some(previous) = index.entries.insert(
+            key,
+            Entry {
+                size,
+                last_used: SystemTime::now(),
+            },
+        ) {
+            index.size -= previous.size;
+        }
+
+        index.size += size;
+        self.evict(&mut index).await;
+
+        Ok(bytes)
+    }
+
+    async fn touch(&self, key: &str, path: PathBuf) {
+        let now = SystemTime::now();
+
+        if let Some(entry) = self.index.lock().await.entries.get_mut(key) {
+            entry.last_used = now;
+        }
+
+        // The modification time records the last use across restarts
+        async_runtime::spawn_blocking(move || {
+            if let Err(err) = File::options()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_modified(now))
+            {
+                tracing::debug!(%err, "Failed to update cached image modification time");
+            }
+        });
+    }
+
+    async fn forget(&self, key: &str) {
+        let mut index = self.index.lock().await;
+
+        if let Some(entry) = index.entries.remove(key) {
+            index.size -= entry.size;
+        }
+    }
+
+    /// Removes the least recently used images until the cache fits within its
+    /// size limit.
+    async fn evict(&self, index: &mut Index) {
+        if index.size <= self.max_size {
+            return;
+        }
+
+        let mut entries: Vec<_> = index
+            .entries
+            .iter()
+            .map(|(key, entry)| (entry.last_used, key.clone()))
+            .collect();
+
+        entries.sort_unstable();
+
+        let mut evicted = 0;
+
+        for (_, key) in entries {
+            if index.size <= self.max_size {
+                break;
+            }
+
+            if let Some(entry) = index.entries.remove(&key) {
+                index.size -= entry.size;
+                evicted += 1;
+            }
+
+            if let Err(err) = fs::remove_file(self.dir.join(&key)).await {
+                tracing::warn!(%err, "Failed to remove cached image");
+            }
+        }
+
+        tracing::debug!("Evicted {evicted} images from the cache");
+    }
+
+    /// Total size of the cached images in bytes.
+    pub async fn size(&self) -> u64 {
+        self.index.lock().await.size
+    }
+
+    pub async fn clear(&self) -> Result<(), Error> {
+        let mut index = self.index.lock().await;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+
+        for key in index.entries.keys() {
+            remove_file(&self.dir.join(key)).await?;
+        }
+
+        *index = Index::default();
+
+        Ok(())
+    }
+}
+
+async fn remove_file(path: &Path) -> Result<(), Error> {
+    match fs::remove_file(path).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Detects the format of an image from its magic bytes.
+fn content_type(bytes: &[u8]) -> Option<&'static str> {
+    let brand = bytes.get(4..12);
+
+    let content_type = if bytes.starts_with(b"GIF8") {
+        "image/gif"
+    } else if bytes.starts_with(b"\x89PNG") {
+        "image/png"
+    } else if bytes.starts_with(b"\xFF\xD8\xFF") {
+        "image/jpeg"
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP".as_slice()) {
+        "image/webp"
+    } else if brand == Some(b"ftypavif".as_slice()) || brand == Some(b"ftypavis".as_slice()) {
+        "image/avif"
+    } else if bytes.starts_with(b"<svg") || bytes.starts_with(b"<?xml") {
+        "image/svg+xml"
+    } else {
+        return None;
+    };
+
+    Some(content_type)
+}
+
+/// Extracts the source url from a request, only allowing images served over
+/// HTTPS from one of the [`ALLOWED_HOSTS`].
+fn source_url(uri: &Uri) -> Option<String> {
+    let path = uri.path().strip_prefix('/')?;
+    let url = percent_decode_str(path).decode_utf8().ok()?;
+    let parsed = reqwest::Url::parse(&url).ok()?;
+
+    let allowed = parsed.scheme() == "https"
+        && parsed
+            .host_str()
+            .is_some_and(|host| ALLOWED_HOSTS.contains(&host));
+
+    allowed.then(|| url.into_owned())
+}
+
+fn status(status: StatusCode) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Vec::new())
+        .unwrap()
+}
+
+async fn serve<R: Runtime>(app_handle: &AppHandle<R>, uri: &Uri) -> Response<Vec<u8>> {
+    let Some(url) = source_url(uri) else {
+        return status(StatusCode::BAD_REQUEST);
+    };
+
+    match app_handle.state::<ImageCache>().get(&url).await {
+        Ok(bytes) => Response::builder()
+            .header(
+                CONTENT_TYPE,
+                content_type(&bytes).unwrap_or("application/octet-stream"),
+            )
+            .header(CACHE_CONTROL, "max-age=31536000, immutable")
+            .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+            .body(bytes)
+            .unwrap(),
+        Err(err) => {
+            tracing::warn!(%err, "Failed to load image from {url}");
+            status(StatusCode::BAD_GATEWAY)
+        }
+    }
+}
+
+pub fn handle_request<R: Runtime>(
+    ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    let app_handle = ctx.app_handle().clone();
+
+    async_runtime::spawn(async move {
+        responder.respond(serve(&app_handle, request.uri()).await);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_content_types() {
+        assert_eq!(content_type(b"GIF89a\x01\x00"), Some("image/gif"));
+        assert_eq!(content_type(b"\x89PNG\r\n\x1a\n"), Some("image/png"));
+        assert_eq!(content_type(b"\xFF\xD8\xFF\xE0"), Some("image/jpeg"));
+        assert_eq!(
+            content_type(b"RIFF\x00\x00\x00\x00WEBPVP8 "),
+            Some("image/webp")
+        );
+        assert_eq!(
+            content_type(b"\x00\x00\x00\x1cftypavif\x00\x00"),
+            Some("image/avif")
+        );
+        assert_eq!(
+            content_type(b"\x00\x00\x00\x1cftypavis\x00\x00"),
+            Some("image/avif")
+        );
+        assert_eq!(
+            content_type(b"<svg xmlns=\"\"></svg>"),
+            Some("image/svg+xml")
+        );
+        assert_eq!(
+            content_type(b"<?xml version=\"1.0\"?><svg></svg>"),
+            Some("image/svg+xml")
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_content() {
+        assert_eq!(content_type(b""), None);
+        assert_eq!(content_type(b"RIFF\x00\x00\x00\x00WAVE"), None);
+        assert_eq!(content_type(b"{\"error\": \"not found\"}"), None);
+        assert_eq!(content_type(b"<!DOCTYPE html><html></html>"), None);
+        assert_eq!(content_type(b"<html><body>Not Found</body></html>"), None);
+    }
+
+    fn uri(url: &str) -> Uri {
+        let encoded =
+            percent_encoding::utf8_percent_encode(url, percent_encoding::NON_ALPHANUMERIC);
+
+        format!("{SCHEME}://localhost/{encoded}").parse().unwrap()
+    }
+
+    #[test]
+    fn only_allows_known_hosts() {
+        let url = "https://cdn.7tv.app/emote/1/1x.webp";
+        assert_eq!(source_url(&uri(url)).as_deref(), Some(url));
+
+        let url = "https://static-cdn.jtvnw.net/emoticons/v2/25/default/dark/1.0";
+        assert_eq!(source_url(&uri(url)).as_deref(), Some(url));
+
+        assert_eq!(source_url(&uri("http://cdn.7tv.app/emote/1/1x.webp")), None);
+        assert_eq!(source_url(&uri("https://example.com/image.png")), None);
+        assert_eq!(
+            source_url(&uri("https://cdn.7tv.app.example.com/image.png")),
+            None
+        );
+        assert_eq!(source_url(&uri("file:///etc/passwd")), None);
+    }
+}
@@ -0,0 +1,186 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tokio::sync::Mutex;
+
+use crate::AppState;
+use crate::error::Error;
+use crate::irc::message::{ServerMessage, WithRaw};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    #[default]
+    Json,
+    /// Sent as raw bytes which arrive in the frontend as an `ArrayBuffer`.
+    MessagePack,
+}
+
+/// How messages are encoded for a single channel.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadOptions {
+    /// Whether to include the raw IRC message of typed messages. Left out by
+    /// default since it roughly doubles the size of each message.
+    #[serde(default)]
+    pub raw: bool,
+    #[serde(default)]
+    pub encoding: Encoding,
+}
+
+impl PayloadOptions {
+    pub fn encode<T: Serialize>(&self, message: &T) -> Result<InvokeResponseBody, Error> {
+        match self.encoding {
+            Encoding::Json => Ok(InvokeResponseBody::Json(serde_json::to_string(message)?)),
+            Encoding::MessagePack => Ok(InvokeResponseBody::Raw(rmp_serde::to_vec_named(message)?)),
+        }
+    }
+
+    /// Encodes a batch of IRC messages, including their raw IRC message if
+    /// requested.
+    pub fn encode_irc(&self, messages: &[ServerMessage]) -> Result<InvokeResponseBody, Error> {
+        if self.raw {
+            let messages: Vec<_> = messages.iter().map(WithRaw::from).collect();
+            self.encode(&messages)
+        } else {
+            self.encode(&messages)
+        }
+    }
+}
+
+/// Frontend channels attached to a single backend stream.
+///
+/// Channels are tied to the lifetime of the webview that created them, so any
+/// number of them can be attached over the lifetime of the app. Each webview
+/// holds at most one channel per stream, which is replaced when the webview is
+/// reloaded and attaches again. Channels that fail to receive a message are
+/// assumed to belong to a webview that no longer exists and are pruned.
+pub struct Subscribers {
+    name: &'static str,
+    channels: std::sync::Mutex<Vec<Subscriber>>,
+}
+
+struct Subscriber {
+    label: String,
+    channel: Channel,
+    options: PayloadOptions,
+}
+
+impl Subscribers {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            channels: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn attach(&self, label: &str, channel: Channel) {
+        self.attach_with(label, channel, PayloadOptions::default());
+    }
+
+    pub fn attach_with(&self, label: &str, channel: Channel, options: PayloadOptions) {
+        tracing::debug!(
+            id = channel.id(),
+            ?options,
+            "Attaching {} channel to {label}",
+            self.name
+        );
+
+        let mut channels = self.channels.lock().unwrap();
+
+        channels.retain(|sub| sub.label != label);
+        channels.push(Subscriber {
+            label: label.to_string(),
+            channel,
+            options,
+        });
+    }
+
+    pub fn detach(&self, id: u32) -> bool {
+        let mut channels = self.channels.lock().unwrap();
+        let len = channels.len();
+
+        channels.retain(|sub| sub.channel.id() != id);
+        channels.len() != len
+    }
+
+    /// Sends a message to every attached channel. The message is only
+    /// serialized once for each distinct set of options.
+    pub fn send<T: Serialize>(&self, message: T) {
+        self.send_with(|options| options.encode(&message));
+    }
+
+    /// Sends a batch of IRC messages to every attached channel.
+    pub fn send_irc(&self, messages: &[ServerMessage]) {
+        self.send_with(|options| options.encode_irc(messages));
+    }
+
+    fn send_with(&self, encode: impl Fn(&PayloadOptions) -> Result<InvokeResponseBody, Error>) {
+        let mut bodies: Vec<(PayloadOptions, InvokeResponseBody)> = Vec::new();
+
+        self.channels.lock().unwrap().retain(|sub| {
+            let body = match bodies.iter().find(|(options, _)| *options == sub.options) {
+                Some((_, body)) => body.clone(),
+                None => match encode(&sub.options) {
+                    Ok(body) => {
+                        bodies.push((sub.options, body.clone()));
+                        body
+                    }
+                    Err(err) => {
+                        tracing::error!(%err, "Failed to serialize {} message", self.name);
+                        return true;
+                    }
+                },
+            };
+
+            match sub.channel.send(body) {
+                Ok(_) => true,
+                Err(err) => {
+                    tracing::warn!(%err, "Pruning dead {} channel of {}", self.name, sub.label);
+                    false
+                }
+            }
+        });
+    }
+}
+
+/// Subscribers for each stream forwarded to the frontend.
+pub struct SubscriberRegistry {
+    pub irc: Arc<Subscribers>,
+    pub eventsub: Arc<Subscribers>,
+    pub seventv: Arc<Subscribers>,
+    pub bttv: Arc<Subscribers>,
+    pub ffz: Arc<Subscribers>,
+}
+
+impl Default for SubscriberRegistry {
+    fn default() -> Self {
+        Self {
+            irc: Arc::new(Subscribers::new("IRC")),
+            eventsub: Arc::new(Subscribers::new("EventSub")),
+            seventv: Arc::new(Subscribers::new("7TV")),
+            bttv: Arc::new(Subscribers::new("BetterTTV")),
+            ffz: Arc::new(Subscribers::new("FrankerFaceZ")),
+        }
+    }
+}
+
+impl SubscriberRegistry {
+    pub fn detach(&self, id: u32) -> bool {
+        // Non-short-circuiting to detach from every stream
+        self.irc.detach(id)
+            | self.eventsub.detach(id)
+            | self.seventv.detach(id)
+            | self.bttv.detach(id)
+            | self.ffz.detach(id)
+    }
+}
+
+#[tauri::command]
+pub async fn detach_channel(state: State<'_, Mutex<AppState>>, id: u32) -> Result<bool, Error> {
+    let state = state.lock().await;
+
+    Ok(state.subscribers.detach(id))
+}
@@ -0,0 +1,255 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::time::Instant;
+
+use super::message::ServerMessage;
+
+#[derive(Debug, Clone, Copy)]
+pub struct BatchConfig {
+    /// How long to collect messages for after the first message of a batch
+    /// is received.
+    pub window: Duration,
+    /// Number of messages after which a batch is sent regardless of the
+    /// window.
+    pub max_messages: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_millis(16),
+            max_messages: 100,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BatchStats {
+    pub batches: u64,
+    pub messages: u64,
+    pub largest: usize,
+    /// Number of batches sent early because of a latency-sensitive message.
+    pub immediate: u64,
+    pub average: f64,
+}
+
+/// Running totals of the batches sent to the frontend.
+#[derive(Debug, Default)]
+pub struct BatchMetrics {
+    batches: AtomicU64,
+    messages: AtomicU64,
+    largest: AtomicUsize,
+    immediate: AtomicU64,
+}
+
+impl BatchMetrics {
+    fn record(&self, size: usize, immediate: bool) {
+        self.batches.fetch_add(1, Ordering::Relaxed);
+        self.messages.fetch_add(size as u64, Ordering::Relaxed);
+        self.largest.fetch_max(size, Ordering::Relaxed);
+
+        if immediate {
+            self.immediate.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> BatchStats {
+        let batches = self.batches.load(Ordering::Relaxed);
+        let messages = self.messages.load(Ordering::Relaxed);
+
+        BatchStats {
+            batches,
+            messages,
+            largest: self.largest.load(Ordering::Relaxed),
+            immediate: self.immediate.load(Ordering::Relaxed),
+            average: if batches == 0 {
+                0.0
+            } else {
+                messages as f64 / batches as f64
+            },
+        }
+    }
+}
+
+/// Coalesces incoming messages into batches to reduce the number of IPC calls
+/// made to the frontend.
+pub struct Batcher {
+    config: BatchConfig,
+    login: String,
+    pending: Vec<ServerMessage>,
+    deadline: Option<Instant>,
+    urgent: bool,
+}
+
+impl Batcher {
+    pub fn new(config: BatchConfig, login: String) -> Self {
+        Self {
+            config,
+            login: login.to_lowercase(),
+            pending: Vec::with_capacity(config.max_messages),
+            deadline: None,
+            urgent: false,
+        }
+    }
+
+    /// Whether the message should be shown without waiting for the window
+    /// to elapse, such as whispers and mentions.
+    fn is_urgent(&self, message: &ServerMessage) -> bool {
+        match message {
+            ServerMessage::Whisper(_) => true,
+            ServerMessage::Privmsg(msg) => {
+                msg.reply
+                    .as_ref()
+                    .is_some_and(|reply| reply.parent.user.login == self.login)
+                    || msg.message_text.to_lowercase().contains(&self.login)
+            }
+            _ => false,
+        }
+    }
+
+    /// Adds a message to the current batch. Returns `true` if the batch should
+    /// be sent right away.
+    pub fn push(&mut self, message: ServerMessage) -> bool {
+        if self.pending.is_empty() {
+            self.deadline = Some(Instant::now() + self.config.window);
+        }
+
+        self.urgent |= self.is_urgent(&message);
+        self.pending.push(message);
+
+        self.urgent || self.pending.len() >= self.config.max_messages
+    }
+
+    /// Replaces the config, which applies from the next batch onwards.
+    pub fn set_config(&mut self, config: BatchConfig) {
+        self.config = config;
+    }
+
+    /// The time at which the current batch should be sent, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Takes the current batch, recording it in `metrics`.
+    pub fn take(&mut self, metrics: &BatchMetrics) -> Vec<ServerMessage> {
+        let batch = std::mem::replace(
+            &mut self.pending,
+            Vec::with_capacity(self.config.max_messages),
+        );
+
+        if !batch.is_empty() {
+            metrics.record(batch.len(), self.urgent);
+        }
+
+        self.deadline = None;
+        self.urgent = false;
+
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irc::message::IrcMessage;
+
+    fn parse(line: &str) -> ServerMessage {
+        ServerMessage::try_from(IrcMessage::parse(line).unwrap()).unwrap()
+    }
+
+    fn privmsg(text: &str) -> ServerMessage {
+        parse(&format!(
+            "@badge-info=;badges=;color=;display-name=Foo;emotes=;id=1;mod=0;room-id=1;subscriber=0;tmi-sent-ts=1700000000000;user-id=2 :foo!foo@foo.tmi.twitch.tv PRIVMSG #chan :{text}"
+        ))
+    }
+
+    fn whisper() -> ServerMessage {
+        parse(
+            "@badges=;color=;display-name=Foo;emotes=;message-id=1;thread-id=1_2;user-id=2 :foo!foo@foo.tmi.twitch.tv WHISPER me :hello",
+        )
+    }
+
+    fn with_max(max_messages: usize) -> Batcher {
+        let config = BatchConfig {
+            window: Duration::from_millis(16),
+            max_messages,
+        };
+
+        Batcher::new(config, "Me".to_string())
+    }
+
+    #[test]
+    fn waits_for_the_window() {
+        let mut batcher = with_max(10);
+
+        assert_eq!(batcher.deadline(), None);
+        assert!(!batcher.push(privmsg("hello")));
+
+        let deadline = batcher.deadline().unwrap();
+
+        // The deadline is set by the first message of the batch
+        assert!(!batcher.push(privmsg("world")));
+        assert_eq!(batcher.deadline(), Some(deadline));
+    }
+
+    #[test]
+    fn flushes_full_batches() {
+        let mut batcher = with_max(3);
+
+        assert!(!batcher.push(privmsg("a")));
+        assert!(!batcher.push(privmsg("b")));
+        assert!(batcher.push(privmsg("c")));
+    }
+
+    #[test]
+    fn flushes_urgent_messages() {
+        let mut batcher = with_max(10);
+        assert!(batcher.push(whisper()));
+
+        let mut batcher = with_max(10);
+        assert!(batcher.push(privmsg("hey @me")));
+
+        let mut batcher = with_max(10);
+        assert!(!batcher.push(privmsg("hey you")));
+    }
+
+    #[test]
+    fn takes_batches_and_records_metrics() {
+        let metrics = BatchMetrics::default();
+        let mut batcher = with_max(10);
+
+        batcher.push(privmsg("a"));
+        batcher.push(privmsg("b"));
+
+        assert_eq!(batcher.take(&metrics).len(), 2);
+        assert_eq!(batcher.deadline(), None);
+
+        batcher.push(whisper());
+        assert_eq!(batcher.take(&metrics).len(), 1);
+
+        // Empty batches aren't recorded
+        assert!(batcher.take(&metrics).is_empty());
+
+        let stats = metrics.snapshot();
+
+        assert_eq!(stats.batches, 2);
+        assert_eq!(stats.messages, 3);
+        assert_eq!(stats.largest, 2);
+        assert_eq!(stats.immediate, 1);
+        assert_eq!(stats.average, 1.5);
+    }
+
+    #[test]
+    fn applies_config_changes() {
+        let mut batcher = with_max(10);
+
+        batcher.set_config(BatchConfig {
+            window: Duration::from_millis(16),
+            max_messages: 1,
+        });
+
+        assert!(batcher.push(privmsg("a")));
+    }
+}
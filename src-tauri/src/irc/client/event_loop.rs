@@ -4,6 +4,7 @@ use std::sync::{Arc, Weak};
 use tokio::sync::{mpsc, oneshot};
 
 use super::pool_connection::PoolConnection;
+use super::{ConnectionStatus, PoolStatus};
 use crate::irc;
 use crate::irc::ClientConfig;
 use crate::irc::connection::event_loop::ConnectionLoopCommand;
@@ -25,6 +26,9 @@ pub(crate) enum ClientLoopCommand {
         source_connection_id: usize,
         message: Box<ConnectionIncomingMessage>,
     },
+    Status {
+        return_sender: oneshot::Sender<PoolStatus>,
+    },
 }
 
 pub(crate) struct ClientLoopWorker {
@@ -79,6 +83,35 @@ impl ClientLoopWorker {
                 source_connection_id,
                 message,
             } => self.on_incoming_message(source_connection_id, *message),
+            ClientLoopCommand::Status { return_sender } => {
+                return_sender.send(self.status()).ok();
+            }
+        }
+    }
+
+    fn status(&self) -> PoolStatus {
+        let connections = self
+            .connections
+            .iter()
+            .map(|c| {
+                let mut wanted_channels: Vec<_> = c.wanted_channels.iter().cloned().collect();
+                let mut server_channels: Vec<_> = c.server_channels.iter().cloned().collect();
+
+                wanted_channels.sort_unstable();
+                server_channels.sort_unstable();
+
+                ConnectionStatus {
+                    id: c.id,
+                    wanted_channels,
+                    server_channels,
+                    last_message_at: c.last_message_at,
+                }
+            })
+            .collect();
+
+        PoolStatus {
+            connections,
+            whisper_connection: self.current_whisper_connection_id,
         }
     }
 
@@ -211,6 +244,14 @@ impl ClientLoopWorker {
     ) {
         match message {
             ConnectionIncomingMessage::IncomingMessage(message) => {
+                if let Some(conn) = self
+                    .connections
+                    .iter_mut()
+                    .find(|c| c.id == source_connection_id)
+                {
+                    conn.last_message_at = Some(crate::unix_millis());
+                }
+
                 let is_whisper = matches!(*message, ServerMessage::Whisper(_));
 
                 if is_whisper {
@@ -4,13 +4,33 @@ mod pool_connection;
 use std::sync::Arc;
 
 use event_loop::{ClientLoopCommand, ClientLoopWorker};
+use serde::Serialize;
 use tokio::sync::{mpsc, oneshot};
 
 use super::ClientConfig;
 use super::message::ServerMessage;
 
+/// Snapshot of a single connection in the pool.
+#[derive(Debug, Serialize)]
+pub struct ConnectionStatus {
+    id: usize,
+    /// Channels the connection was asked to join.
+    wanted_channels: Vec<String>,
+    /// Channels the server confirmed the connection has joined.
+    server_channels: Vec<String>,
+    last_message_at: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PoolStatus {
+    connections: Vec<ConnectionStatus>,
+    /// Id of the connection whispers are accepted from.
+    whisper_connection: Option<usize>,
+}
+
 #[derive(Debug, Clone)]
 pub struct IrcClient {
+    config: Arc<ClientConfig>,
     client_loop_tx: Arc<mpsc::UnboundedSender<ClientLoopCommand>>,
 }
 
@@ -23,17 +43,35 @@ impl IrcClient {
         let (client_incoming_messages_tx, client_incoming_messages_rx) = mpsc::unbounded_channel();
 
         ClientLoopWorker::spawn(
-            config,
+            Arc::clone(&config),
             Arc::downgrade(&client_loop_tx),
             client_loop_rx,
             client_incoming_messages_tx,
         );
 
-        (client_incoming_messages_rx, Self { client_loop_tx })
+        (
+            client_incoming_messages_rx,
+            Self {
+                config,
+                client_loop_tx,
+            },
+        )
     }
 }
 
 impl IrcClient {
+    /// Replaces the token used to authenticate new connections. Existing
+    /// connections stay authenticated with the previous token.
+    pub fn set_token(&self, token: String) {
+        *self.config.token.write().unwrap() = token;
+    }
+
+    /// Whether the client loop has shut down, after which the client can no
+    /// longer be used.
+    pub fn is_closed(&self) -> bool {
+        self.client_loop_tx.is_closed()
+    }
+
     pub async fn connect(&self) {
         let (return_tx, return_rx) = oneshot::channel();
 
@@ -57,4 +95,16 @@ impl IrcClient {
             .send(ClientLoopCommand::Part { channel_login })
             .unwrap();
     }
+
+    pub async fn status(&self) -> PoolStatus {
+        let (return_tx, return_rx) = oneshot::channel();
+
+        self.client_loop_tx
+            .send(ClientLoopCommand::Status {
+                return_sender: return_tx,
+            })
+            .unwrap();
+
+        return_rx.await.unwrap()
+    }
 }
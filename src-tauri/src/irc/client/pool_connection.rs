@@ -15,6 +15,8 @@ pub(crate) struct PoolConnection {
     pub wanted_channels: HashSet<String>,
     pub server_channels: HashSet<String>,
     pub message_send_times: VecDeque<Instant>,
+    /// Unix timestamp in milliseconds of the last message received.
+    pub last_message_at: Option<u64>,
 }
 
 impl PoolConnection {
@@ -33,6 +35,7 @@ impl PoolConnection {
             wanted_channels: HashSet::new(),
             server_channels: HashSet::new(),
             message_send_times: VecDeque::with_capacity(message_send_times_max_entries),
+            last_message_at: None,
             tx_kill_incoming: Some(tx_kill_incoming),
         }
     }
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 use tokio::sync::Semaphore;
@@ -6,7 +6,8 @@ use tokio::sync::Semaphore;
 #[derive(Debug)]
 pub struct ClientConfig {
     pub login: String,
-    pub token: String,
+    /// Token new connections authenticate with, replaced when it's refreshed.
+    pub token: RwLock<String>,
     pub max_channels_per_connection: usize,
     pub max_waiting_messages_per_connection: usize,
     pub connection_rate_limiter: Arc<Semaphore>,
@@ -18,7 +19,7 @@ impl ClientConfig {
     pub fn new(login: String, token: String) -> ClientConfig {
         ClientConfig {
             login,
-            token,
+            token: RwLock::new(token),
             max_channels_per_connection: 90,
             max_waiting_messages_per_connection: 5,
             connection_rate_limiter: Arc::new(Semaphore::new(1)),
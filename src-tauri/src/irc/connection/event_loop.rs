@@ -86,7 +86,7 @@ impl ConnectionLoopWorker {
     ) {
         let res = try {
             let login = config.login.clone();
-            let token = config.token.clone();
+            let token = config.token.read().unwrap().clone();
 
             let rate_limit_permit = Arc::clone(&config.connection_rate_limiter)
                 .acquire_owned()
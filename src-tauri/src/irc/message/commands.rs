@@ -8,7 +8,7 @@ use thiserror::Error;
 
 use super::prefix::IrcPrefix;
 use super::{
-    AsRawIrc, Badge, BasicUser, Emote, IrcMessage, Reply, ReplyParent, ReplyThread, Source,
+    AsRawIrc, Badge, BasicUser, Cheer, Emote, IrcMessage, Reply, ReplyParent, ReplyThread, Source,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -18,6 +18,7 @@ pub struct ClearChatMessage {
     pub action: ClearChatAction,
     pub is_recent: bool,
     pub server_timestamp: u64,
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -102,6 +103,7 @@ pub struct ClearMsgMessage {
     pub is_action: bool,
     pub is_recent: bool,
     pub server_timestamp: u64,
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -143,6 +145,7 @@ pub struct GlobalUserStateMessage {
     pub badges: Vec<Badge>,
     pub emote_sets: HashSet<String>,
     pub name_color: String,
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -176,6 +179,7 @@ impl From<GlobalUserStateMessage> for IrcMessage {
 pub struct JoinMessage {
     pub channel_login: String,
     pub user_login: String,
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -209,6 +213,7 @@ pub struct NoticeMessage {
     pub deleted: bool,
     pub is_recent: bool,
     pub recent_timestamp: Option<u64>,
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -244,6 +249,7 @@ impl From<NoticeMessage> for IrcMessage {
 pub struct PartMessage {
     pub channel_login: String,
     pub user_login: String,
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -271,6 +277,7 @@ impl From<PartMessage> for IrcMessage {
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PingMessage {
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -294,6 +301,7 @@ impl From<PingMessage> for IrcMessage {
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PongMessage {
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -331,6 +339,12 @@ pub struct PrivmsgMessage {
     pub badge_info: Vec<Badge>,
     pub badges: Vec<Badge>,
     pub bits: Option<u64>,
+    /// Cheers in the message, resolved from the cheermotes of the channel.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cheers: Vec<Cheer>,
+    /// 7TV badges of the sender.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seventv_badges: Vec<Badge>,
     pub name_color: String,
     pub emotes: Vec<Emote>,
     pub message_id: String,
@@ -339,6 +353,7 @@ pub struct PrivmsgMessage {
     pub source_only: Option<bool>,
     pub source: Option<Source>,
     pub server_timestamp: u64,
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -358,13 +373,16 @@ impl TryFrom<IrcMessage> for PrivmsgMessage {
             channel_login: raw.try_get_channel_login()?.to_owned(),
             channel_id: raw.try_get_nonempty_tag_value("room-id")?.to_owned(),
             sender: BasicUser {
-                id: raw.try_get_nonempty_tag_value("user-id")?.to_owned(),
+                // Empty for messages imported from text logs
+                id: raw.try_get_tag_value("user-id")?.to_owned(),
                 login: raw.try_get_prefix_nickname()?.to_owned(),
                 name: raw.try_get_nonempty_tag_value("display-name")?.to_owned(),
             },
             badge_info: raw.try_get_badges("badge-info")?,
             badges: raw.try_get_badges("badges")?,
             bits: raw.try_get_optional_number("bits")?,
+            cheers: Vec::new(),
+            seventv_badges: Vec::new(),
             name_color: raw.try_get_color("color")?.to_owned(),
             emotes: raw.try_get_emotes("emotes", message_text)?,
             server_timestamp: raw.try_get_timestamp("tmi-sent-ts")?,
@@ -396,6 +414,7 @@ impl From<PrivmsgMessage> for IrcMessage {
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ReconnectMessage {
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -427,6 +446,7 @@ pub struct RoomStateMessage {
     pub slow_mode: Option<u64>,
     pub subscribers_only: Option<bool>,
     pub is_recent: bool,
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -477,6 +497,7 @@ pub struct UserNoticeMessage {
     pub source_only: Option<bool>,
     pub source: Option<Source>,
     pub server_timestamp: u64,
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -832,6 +853,7 @@ pub struct UserStateMessage {
     pub badges: Vec<Badge>,
     pub emote_sets: HashSet<String>,
     pub name_color: String,
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -870,6 +892,7 @@ pub struct WhisperMessage {
     pub name_color: String,
     pub badges: Vec<Badge>,
     pub emotes: Vec<Emote>,
+    #[serde(skip_serializing)]
     pub raw: IrcMessage,
 }
 
@@ -1159,6 +1182,8 @@ impl IrcMessageParseExt for IrcMessage {
             badges.push(Badge {
                 name: name.to_owned(),
                 version: version.to_owned(),
+                title: None,
+                image_url: None,
             });
         }
 
@@ -1370,6 +1395,44 @@ impl ServerMessage {
         }
     }
 
+    /// Returns the login of the channel the message was sent in, if any.
+    pub fn channel_login(&self) -> Option<&str> {
+        match self {
+            ServerMessage::ClearChat(msg) => Some(&msg.channel_login),
+            ServerMessage::ClearMsg(msg) => Some(&msg.channel_login),
+            ServerMessage::Join(msg) => Some(&msg.channel_login),
+            ServerMessage::Notice(msg) => msg.channel_login.as_deref(),
+            ServerMessage::Part(msg) => Some(&msg.channel_login),
+            ServerMessage::Privmsg(msg) => Some(&msg.channel_login),
+            ServerMessage::RoomState(msg) => Some(&msg.channel_login),
+            ServerMessage::UserNotice(msg) => Some(&msg.channel_login),
+            ServerMessage::UserState(msg) => Some(&msg.channel_login),
+            _ => None,
+        }
+    }
+
+    /// Returns the unique id of the message if it represents a chat message.
+    pub fn message_id(&self) -> Option<&str> {
+        match self {
+            ServerMessage::Privmsg(msg) => Some(&msg.message_id),
+            ServerMessage::UserNotice(msg) => Some(&msg.message_id),
+            ServerMessage::Whisper(msg) => Some(&msg.message_id),
+            _ => None,
+        }
+    }
+
+    /// Returns the `tmi-sent-ts` timestamp of the message in milliseconds, if
+    /// present.
+    pub fn server_timestamp(&self) -> Option<u64> {
+        match self {
+            ServerMessage::ClearChat(msg) => Some(msg.server_timestamp),
+            ServerMessage::ClearMsg(msg) => Some(msg.server_timestamp),
+            ServerMessage::Privmsg(msg) => Some(msg.server_timestamp),
+            ServerMessage::UserNotice(msg) => Some(msg.server_timestamp),
+            _ => None,
+        }
+    }
+
     pub(crate) fn new_generic(message: IrcMessage) -> ServerMessage {
         ServerMessage::Generic(HiddenIrcMessage(message))
     }
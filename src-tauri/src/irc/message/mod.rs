@@ -49,6 +49,26 @@ pub trait AsRawIrc {
     }
 }
 
+/// A typed message serialized along with the raw IRC message it was parsed
+/// from, which is otherwise left out.
+#[derive(Serialize)]
+pub struct WithRaw<'a> {
+    #[serde(flatten)]
+    message: &'a ServerMessage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw: Option<&'a IrcMessage>,
+}
+
+impl<'a> From<&'a ServerMessage> for WithRaw<'a> {
+    fn from(message: &'a ServerMessage) -> Self {
+        Self {
+            message,
+            // Generic messages are already serialized as raw IRC
+            raw: (!matches!(message, ServerMessage::Generic(_))).then(|| message.raw()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct IrcMessage {
     pub tags: IrcTags,
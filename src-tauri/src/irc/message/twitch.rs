@@ -20,6 +20,21 @@ pub struct Emote {
 pub struct Badge {
     pub name: String,
     pub version: String,
+    /// Title of the badge, filled in from the badge catalog.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+}
+
+/// A cheer in a message, resolved to the matching tier of its cheermote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cheer {
+    pub prefix: String,
+    pub bits: u64,
+    pub color: String,
+    /// Candidate urls for each pixel density, in `srcset` syntax.
+    pub srcset: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
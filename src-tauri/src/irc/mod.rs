@@ -1,3 +1,4 @@
+mod batch;
 pub mod client;
 mod config;
 mod connection;
@@ -5,26 +6,64 @@ mod error;
 pub mod message;
 pub mod websocket;
 
+use std::time::Duration;
+
+pub use batch::{BatchConfig, BatchMetrics};
+use batch::{BatchStats, Batcher};
 pub use client::IrcClient;
 use config::ClientConfig;
 use error::Error;
-use message::ServerMessage;
 use tauri::ipc::Channel;
-use tauri::{State, async_runtime};
+use tauri::{AppHandle, Manager, State, Webview, async_runtime};
 use tokio::sync::Mutex;
+use tokio::time::Instant;
 
 use crate::AppState;
 use crate::api::get_access_token;
+use crate::badges::BadgeCatalog;
 use crate::error::Error as AppError;
+use crate::eventsub::chat::ChatSources;
+use crate::history::MessageBuffer;
+use crate::ipc::PayloadOptions;
 use crate::irc::message::IrcMessage;
 
 #[tracing::instrument(skip_all)]
 #[tauri::command]
 pub async fn connect_irc(
+    app_handle: AppHandle,
+    webview: Webview,
     state: State<'_, Mutex<AppState>>,
-    channel: Channel<ServerMessage>,
+    channel: Channel,
+    options: Option<PayloadOptions>,
+    batch_window: Option<u64>,
+    batch_size: Option<usize>,
 ) -> Result<(), AppError> {
     let mut guard = state.lock().await;
+    let subscribers = guard.subscribers.irc.clone();
+
+    subscribers.attach_with(webview.label(), channel, options.unwrap_or_default());
+
+    let mut batch_config = BatchConfig::default();
+
+    if let Some(window) = batch_window {
+        batch_config.window = Duration::from_millis(window);
+    }
+
+    if let Some(size) = batch_size {
+        batch_config.max_messages = size.max(1);
+    }
+
+    guard.irc_batch.send_replace(batch_config);
+
+    match guard.irc {
+        Some(ref irc) if !irc.is_closed() => {
+            tracing::info!("Reusing existing IRC connection");
+            return Ok(());
+        }
+        Some(_) => tracing::warn!("IRC client shut down, reconnecting"),
+        None => (),
+    }
+
     let token = get_access_token(&guard)?;
     let login = token.login.to_string();
 
@@ -36,14 +75,53 @@ pub async fn connect_irc(
     );
 
     let (mut incoming, client) = IrcClient::new(config);
+    let mut batch_rx = guard.irc_batch.subscribe();
+    let mut batcher = Batcher::new(*batch_rx.borrow_and_update(), login);
 
     async_runtime::spawn(async move {
-        while let Some(message) = incoming.recv().await {
-            let IrcMessage { tags, command, .. } = message.raw();
+        let metrics = app_handle.state::<BatchMetrics>();
+
+        loop {
+            let deadline = batcher.deadline();
+
+            let flush = tokio::select! {
+                message = incoming.recv() => {
+                    let Some(mut message) = message else {
+                        break;
+                    };
 
-            tracing::trace!(?tags, "Received {command} message");
+                    let IrcMessage { tags, command, .. } = message.raw();
 
-            channel.send(message).unwrap();
+                    tracing::trace!(?tags, "Received {command} message");
+
+                    if !app_handle.state::<ChatSources>().accepts_irc(&message) {
+                        continue;
+                    }
+
+                    app_handle
+                        .state::<BadgeCatalog>()
+                        .enrich(&app_handle, &mut message);
+
+                    app_handle.state::<MessageBuffer>().push(&message).await;
+
+                    batcher.push(message)
+                }
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => true,
+                Ok(()) = batch_rx.changed() => {
+                    batcher.set_config(*batch_rx.borrow_and_update());
+                    false
+                }
+            };
+
+            if flush {
+                subscribers.send_irc(&batcher.take(&metrics));
+            }
+        }
+
+        let remaining = batcher.take(&metrics);
+
+        if !remaining.is_empty() {
+            subscribers.send_irc(&remaining);
         }
     });
 
@@ -52,3 +130,8 @@ pub async fn connect_irc(
 
     Ok(())
 }
+
+#[tauri::command]
+pub fn get_batch_metrics(metrics: State<'_, BatchMetrics>) -> BatchStats {
+    metrics.snapshot()
+}
@@ -2,9 +2,21 @@
 #![allow(clippy::result_large_err)]
 
 use std::sync::{Arc, LazyLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use auth::DeviceLogin;
+use badges::BadgeCatalog;
+use bttv::BttvClient;
+use emotes::EmoteRegistry;
 use eventsub::EventSubClient;
-use irc::IrcClient;
+use eventsub::chat::ChatSources;
+#[cfg(debug_assertions)]
+use eventsub::mock::MockServer;
+use ffz::FfzClient;
+use history::{ChatStore, MessageBuffer};
+use images::ImageCache;
+use ipc::SubscriberRegistry;
+use irc::{BatchConfig, BatchMetrics, IrcClient};
 use reqwest::header::HeaderMap;
 use seventv::SeventTvClient;
 use tauri::async_runtime::{self, Mutex};
@@ -12,21 +24,38 @@ use tauri::ipc::Invoke;
 use tauri::{Manager, WindowEvent};
 use tauri_plugin_cache::{CacheConfig, CompressionMethod};
 use tauri_plugin_svelte::ManagerExt;
+use tokio::sync::watch;
 use twitch_api::HelixClient;
-use twitch_api::twitch_oauth2::{AccessToken, UserToken};
+use twitch_api::twitch_oauth2::{AccessToken, RefreshToken, UserToken};
 
 mod api;
+mod auth;
+mod badges;
+#[cfg(any(test, feature = "bench"))]
+#[doc(hidden)]
+pub mod bench;
+mod bttv;
+mod cache;
 mod commands;
+mod emotes;
 mod error;
 mod eventsub;
+mod ffz;
+mod history;
+mod images;
+mod ipc;
 mod irc;
 mod json;
 mod log;
 mod server;
 mod seventv;
+mod socket;
 
 const CLIENT_ID: &str = "kimne78kx3ncx6brgo4mv6wki5h1ko";
 
+/// Maximum number of messages kept in memory for each joined channel.
+const CHANNEL_BUFFER_CAPACITY: usize = 1000;
+
 pub static HTTP: LazyLock<reqwest::Client> = LazyLock::new(|| {
     let mut headers = HeaderMap::new();
     headers.insert("Client-Id", CLIENT_ID.parse().unwrap());
@@ -38,12 +67,33 @@ pub static HTTP: LazyLock<reqwest::Client> = LazyLock::new(|| {
         .unwrap()
 });
 
+/// Exponential backoff delay before the given reconnection attempt, capped at
+/// a minute.
+pub(crate) fn reconnect_delay(attempt: u32) -> Duration {
+    Duration::from_secs(1 << attempt.saturating_sub(1).min(6)).min(Duration::from_secs(60))
+}
+
+/// Current time in milliseconds since the Unix epoch.
+pub(crate) fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
 pub struct AppState {
     helix: HelixClient<'static, reqwest::Client>,
     token: Option<UserToken>,
     irc: Option<IrcClient>,
+    /// Batching applied to IRC messages, updated whenever a webview attaches.
+    irc_batch: watch::Sender<BatchConfig>,
     eventsub: Option<Arc<EventSubClient>>,
+    #[cfg(debug_assertions)]
+    mock_eventsub: Option<Arc<MockServer>>,
     seventv: Option<Arc<SeventTvClient>>,
+    bttv: Option<Arc<BttvClient>>,
+    ffz: Option<Arc<FfzClient>>,
+    subscribers: SubscriberRegistry,
 }
 
 impl Default for AppState {
@@ -52,8 +102,14 @@ impl Default for AppState {
             helix: HelixClient::new(),
             token: None,
             irc: None,
+            irc_batch: watch::Sender::new(BatchConfig::default()),
             eventsub: None,
+            #[cfg(debug_assertions)]
+            mock_eventsub: None,
             seventv: None,
+            bttv: None,
+            ffz: None,
+            subscribers: SubscriberRegistry::default(),
         }
     }
 }
@@ -101,15 +157,34 @@ pub fn run() {
             app_handle.plugin(svelte)?;
 
             async_runtime::block_on(async {
-                let stored_token = app_handle
-                    .svelte()
-                    .get_raw("storage", "user")
+                let stored_user = app_handle.svelte().get_raw("storage", "user");
+
+                let stored_token = stored_user
+                    .as_ref()
                     .and_then(|user| user["token"].as_str().map(|t| t.to_string()));
 
+                let refresh_token = stored_user
+                    .as_ref()
+                    .and_then(|user| user["refreshToken"].as_str())
+                    .map(|t| RefreshToken::from(t.to_string()));
+
                 let access_token = if let Some(token) = stored_token {
-                    UserToken::from_token(&state.helix, AccessToken::from(token))
-                        .await
-                        .ok()
+                    let validated = UserToken::from_existing(
+                        &state.helix,
+                        AccessToken::from(token),
+                        refresh_token.clone(),
+                        None,
+                    )
+                    .await;
+
+                    match (validated, refresh_token) {
+                        (Ok(token), _) => Some(token),
+                        // The token expired while the app was closed
+                        (Err(_), Some(refresh_token)) => {
+                            auth::refresh_existing(&state.helix, &refresh_token).await
+                        }
+                        (Err(_), None) => None,
+                    }
                 } else {
                     None
                 };
@@ -125,8 +200,23 @@ pub fn run() {
             });
 
             app.manage(Mutex::new(state));
+            app.manage(ChatStore::new(
+                app_handle.path().app_data_dir()?.join("history"),
+            ));
+            app.manage(MessageBuffer::new(CHANNEL_BUFFER_CAPACITY));
+            app.manage(BatchMetrics::default());
+            app.manage(ChatSources::default());
+            app.manage(EmoteRegistry::default());
+            app.manage(BadgeCatalog::default());
+            app.manage(DeviceLogin::default());
+            app.manage(ImageCache::new(
+                app_handle.path().app_cache_dir()?.join("images"),
+                images::MAX_SIZE,
+            ));
             app.manage(system);
 
+            auth::spawn_validator(app_handle.clone());
+
             Ok(())
         })
         .on_window_event(|window, event| {
@@ -153,6 +243,7 @@ pub fn run() {
                 }
             }
         })
+        .register_asynchronous_uri_scheme_protocol(images::SCHEME, images::handle_request)
         .invoke_handler(get_handler())
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -164,11 +255,35 @@ fn get_handler() -> impl Fn(Invoke) -> bool {
         api::leave,
         api::rejoin,
         api::fetch_user_emotes,
+        auth::cancel_device_login,
+        auth::get_token_info,
+        auth::start_device_login,
+        badges::fetch_channel_badges,
+        badges::fetch_cheermotes,
+        badges::fetch_global_badges,
+        bttv::connect_bttv,
+        commands::clear_image_cache,
         commands::fetch_recent_messages,
         commands::get_cache_size,
+        commands::get_connection_status,
         commands::get_debug_info,
+        emotes::fetch_channel_emotes,
+        emotes::fetch_global_emotes,
+        emotes::get_channel_emotes,
+        emotes::resolve_emotes,
         eventsub::connect_eventsub,
+        eventsub::get_eventsub_usage,
+        #[cfg(debug_assertions)]
+        eventsub::start_mock_eventsub,
+        #[cfg(debug_assertions)]
+        eventsub::trigger_mock_eventsub,
+        ffz::connect_ffz,
+        history::get_channel_buffer,
+        history::import_logs,
+        history::search_history,
+        ipc::detach_channel,
         irc::connect_irc,
+        irc::get_batch_metrics,
         log::log,
         log::update_log_level,
         server::start_server,
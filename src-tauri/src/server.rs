@@ -152,7 +152,7 @@ async fn handle_url(app_handle: AppHandle, url: String) {
 
     let state = app_handle.state::<Mutex<AppState>>();
 
-    if let Some(token_info) = set_access_token(state, token.clone()).await {
+    if let Some(token_info) = set_access_token(state, token.clone(), None).await {
         app_handle.emit("tokeninfo", token_info).unwrap();
     }
 }
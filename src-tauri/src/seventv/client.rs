@@ -1,46 +1,117 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
 
 use anyhow::anyhow;
 use futures::future::join_all;
 use futures::{SinkExt, StreamExt};
-use serde::Deserialize;
-use serde_json::json;
-use tokio::sync::{Mutex, mpsc};
-use tokio_tungstenite::connect_async;
+use serde::Serialize;
+use tokio::net::TcpStream;
+use tokio::sync::{Mutex, mpsc, oneshot};
+use tokio::time::Instant;
 use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
 use tracing::Instrument;
 
+use super::error::Error as SeventvError;
+use super::message::{Ack, ClientMessage, Dispatch, Opcode, ServerMessage};
 use crate::error::Error;
 
 const SEVENTV_WS_URI: &str = "wss://events.7tv.io/v3";
 
-#[derive(Deserialize)]
-struct WebSocketMessage {
-    op: u8,
-    d: serde_json::Value,
+/// Extra time allowed on top of the heartbeat interval before the connection
+/// is considered dead.
+const HEARTBEAT_GRACE: Duration = Duration::from_secs(5);
+
+/// How long to wait for a command to be acknowledged.
+const ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Stream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Why a connection to the Event API ended.
+#[derive(Debug, Clone, Copy)]
+enum Disconnect {
+    /// The server asked for a reconnect.
+    Requested,
+    /// The resumed session doesn't hold the same subscriptions as the client.
+    Desynced,
+    /// No message, heartbeats included, was received in time.
+    HeartbeatTimeout,
+    Closed,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubscriptionState {
+    /// Waiting for a connection to be sent on.
+    Queued,
+    /// Sent and waiting to be acknowledged.
+    Pending,
+    /// Acknowledged on the current session.
+    Active,
+}
+
+struct Subscription {
+    condition: serde_json::Value,
+    state: SubscriptionState,
+}
+
+/// A subscribe or unsubscribe command waiting for an acknowledgement.
+struct PendingCommand {
+    id: u64,
+    op: Opcode,
+    key: String,
+    kind: String,
+    condition: serde_json::Value,
+    result: oneshot::Sender<Result<(), SeventvError>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubscriptionStatus {
+    channel: String,
+    #[serde(rename = "type")]
+    kind: String,
+    condition: serde_json::Value,
+    state: SubscriptionState,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConnectionStatus {
+    session_id: Option<String>,
+    connected: bool,
+    subscriptions: Vec<SubscriptionStatus>,
+    last_message_at: Option<u64>,
 }
 
 pub struct SeventTvClient {
     session_id: Arc<Mutex<Option<String>>>,
-    subscriptions: Arc<Mutex<HashMap<String, serde_json::Value>>>,
-    sender: mpsc::UnboundedSender<serde_json::Value>,
+    subscriptions: Arc<Mutex<HashMap<String, Subscription>>>,
+    /// Commands sent to the server in order, oldest first.
+    pending: std::sync::Mutex<VecDeque<PendingCommand>>,
+    next_command: AtomicU64,
+    sender: mpsc::UnboundedSender<Dispatch>,
     connected: AtomicBool,
+    /// Unix timestamp in milliseconds of the last message received, or 0.
+    last_message: AtomicU64,
     message_tx: mpsc::UnboundedSender<Message>,
     message_rx: Arc<Mutex<Option<mpsc::UnboundedReceiver<Message>>>>,
 }
 
 impl SeventTvClient {
-    pub fn new() -> (mpsc::UnboundedReceiver<serde_json::Value>, Self) {
-        let (sender, receiver) = mpsc::unbounded_channel::<serde_json::Value>();
+    pub fn new() -> (mpsc::UnboundedReceiver<Dispatch>, Self) {
+        let (sender, receiver) = mpsc::unbounded_channel::<Dispatch>();
         let (message_tx, message_rx) = mpsc::unbounded_channel();
 
         let client = Self {
             subscriptions: Arc::new(Mutex::new(HashMap::new())),
             session_id: Arc::new(Mutex::new(None)),
+            pending: std::sync::Mutex::new(VecDeque::new()),
+            next_command: AtomicU64::default(),
             sender,
             connected: AtomicBool::default(),
+            last_message: AtomicU64::default(),
             message_tx,
             message_rx: Arc::new(Mutex::new(Some(message_rx))),
         };
@@ -50,176 +121,557 @@ impl SeventTvClient {
 
     #[tracing::instrument(name = "7tv_connect", skip_all)]
     pub async fn connect(self: Arc<Self>) -> Result<(), Error> {
-        let this = Arc::clone(&self);
-
         let mut message_rx = {
-            let mut guard = this.message_rx.lock().await;
+            let mut guard = self.message_rx.lock().await;
 
             guard
                 .take()
                 .ok_or_else(|| Error::Generic(anyhow!("Message receiver already taken")))?
         };
 
-        tokio::spawn(async move {
-            loop {
-                tracing::info!("Connecting to 7TV Event API");
+        tokio::spawn(
+            async move {
+                let mut attempt = 0;
 
-                let mut stream = match connect_async(SEVENTV_WS_URI).await {
-                    Ok((stream, _)) => stream,
-                    Err(err) => {
-                        tracing::error!(%err, "Failed to connect to 7TV Event API");
-                        return Err::<(), _>(Error::WebSocket(err))
-                    },
-                };
+                loop {
+                    if attempt > 0 {
+                        let delay = crate::reconnect_delay(attempt);
 
-                tracing::info!("Connected to 7TV Event API");
+                        tracing::info!(
+                            "Reconnecting to 7TV Event API in {delay:?} (attempt {attempt})"
+                        );
+                        tokio::time::sleep(delay).await;
+                    }
 
-                {
-                    let session_id = this.session_id.lock().await;
+                    tracing::info!("Connecting to 7TV Event API");
 
-                    if let Some(id) = &*session_id {
+                    let mut stream = match connect_async(SEVENTV_WS_URI).await {
+                        Ok((stream, _)) => stream,
+                        Err(err) => {
+                            tracing::error!(%err, "Failed to connect to 7TV Event API");
+
+                            attempt += 1;
+                            continue;
+                        }
+                    };
+
+                    tracing::info!("Connected to 7TV Event API");
+
+                    let mut resuming = false;
+
+                    if let Some(id) = self.session_id.lock().await.clone() {
                         tracing::info!(%id, "Resuming 7TV session");
 
-                        let payload = json!({
-                            "op": 34,
-                            "d": {
-                                "session_id": id
-                            }
-                        });
+                        let payload = ClientMessage::resume(&id).to_json();
 
-                        if let Err(err) = stream.send(Message::Text(payload.to_string().into())).await {
-                            tracing::error!(%err, "Error sending resume message");
+                        match stream.send(Message::Text(payload.into())).await {
+                            Ok(_) => resuming = true,
+                            Err(err) => tracing::error!(%err, "Error sending resume message"),
                         }
                     }
-                }
 
-                self.connected.store(true, Ordering::Relaxed);
+                    let reason = self
+                        .process_stream(stream, &mut message_rx, &mut attempt, resuming)
+                        .await;
 
-                'recv: loop {
-                    let this = Arc::clone(&this);
+                    self.connected.store(false, Ordering::Relaxed);
 
-                    tokio::select! {
-                        Some(data) = message_rx.recv() => {
-                            if let Err(err) = stream.send(data).await {
-                                tracing::error!(%err, "Error sending message");
-                                break 'recv;
-                            }
+                    // Commands that weren't sent yet are restored from the
+                    // subscriptions once connected again
+                    while message_rx.try_recv().is_ok() {}
+                    self.requeue().await;
+
+                    match reason {
+                        Disconnect::Requested | Disconnect::Desynced => {
+                            tracing::info!(?reason, "Reconnecting to 7TV Event API");
                         }
-                        Some(Ok(message)) = stream.next() => {
-                            match message {
-                                Message::Text(text) => {
-                                    if let Ok(msg) = serde_json::from_str::<WebSocketMessage>(&text) {
-                                        match msg.op {
-                                            0 => {
-                                                if let Err(err) = this.sender.send(msg.d) {
-                                                    tracing::error!(%err, "Error sending payload");
-                                                }
-                                            }
-                                            1 => {
-                                                if let Some(id) = msg.d["session_id"].as_str() {
-                                                    let mut session = this.session_id.lock().await;
-                                                    *session = Some(id.to_string());
-
-                                                    tracing::info!(%id, "Hello received, session established");
-                                                }
-                                            }
-                                            5 => {
-                                                tracing::debug!(payload = ?msg.d.to_string(), "Opcode acknowledged");
-
-                                                if let Some(success) = msg.d["data"]["success"].as_bool() && !success {
-                                                    let to_restore: Vec<_> = {
-                                                        let mut subscriptions = this.subscriptions.lock().await;
-
-                                                        tracing::warn!(
-                                                            "Resume unsuccessful, restoring {} events",
-                                                            subscriptions.len()
-                                                        );
-
-                                                        subscriptions.drain().collect()
-                                                    };
-
-                                                    for (key, condition) in to_restore {
-                                                        let (channel, event) = key.split_once(':').unwrap();
-
-                                                        self.subscribe(channel, event, &condition).await;
-                                                    }
-                                                }
-                                            }
-                                            7 => {
-                                                tracing::info!(payload = ?msg.d.to_string(), "End of stream reached");
-                                            }
-                                            _ => {}
-                                        }
-                                    }
-                                }
-                                Message::Close(cf) => {
-                                    if let Some(frame) = cf {
-                                        tracing::warn!(%frame, "Event API connection closed");
-                                    }
-
-                                    this.connected.store(false, Ordering::Relaxed);
-                                    break 'recv;
-                                }
-                                _ => (),
-                            }
+                        reason => {
+                            tracing::warn!(?reason, "Disconnected from 7TV Event API");
+                            attempt += 1;
                         }
                     }
                 }
             }
-        }.in_current_span());
+            .in_current_span(),
+        );
 
         Ok(())
     }
 
+    /// Processes messages on a connection until it ends, returning why it
+    /// did. `attempt` is reset once the server says hello.
+    async fn process_stream(
+        &self,
+        mut stream: Stream,
+        message_rx: &mut mpsc::UnboundedReceiver<Message>,
+        attempt: &mut u32,
+        resuming: bool,
+    ) -> Disconnect {
+        // Unknown until the hello message is received
+        let mut timeout = None;
+        let mut deadline = Instant::now();
+
+        loop {
+            let message = tokio::select! {
+                Some(data) = message_rx.recv() => {
+                    if let Err(err) = stream.send(data).await {
+                        tracing::error!(%err, "Error sending message");
+                        return Disconnect::Error;
+                    }
+
+                    continue;
+                }
+                _ = tokio::time::sleep_until(deadline), if timeout.is_some() => {
+                    tracing::warn!("No 7TV message received in {:?}", timeout.unwrap());
+                    return Disconnect::HeartbeatTimeout;
+                }
+                message = stream.next() => message,
+            };
+
+            let message = match message {
+                Some(Ok(message)) => message,
+                Some(Err(err)) => {
+                    tracing::error!(%err, "7TV connection error");
+                    return Disconnect::Error;
+                }
+                None => return Disconnect::Closed,
+            };
+
+            self.last_message
+                .store(crate::unix_millis(), Ordering::Relaxed);
+
+            if let Some(timeout) = timeout {
+                deadline = Instant::now() + timeout;
+            }
+
+            let text = match message {
+                Message::Text(text) => text,
+                Message::Close(frame) => {
+                    if let Some(frame) = frame {
+                        tracing::warn!(%frame, "Event API connection closed");
+                    }
+
+                    return Disconnect::Closed;
+                }
+                _ => continue,
+            };
+
+            let message = match ServerMessage::parse(&text) {
+                Ok(message) => message,
+                Err(err) => {
+                    tracing::warn!(%err, "Failed to parse 7TV message");
+                    continue;
+                }
+            };
+
+            match message {
+                ServerMessage::Dispatch(dispatch) => {
+                    if let Err(err) = self.sender.send(dispatch) {
+                        tracing::error!(%err, "Error sending payload");
+                    }
+                }
+                ServerMessage::Hello(hello) => {
+                    *self.session_id.lock().await = Some(hello.session_id.clone());
+                    *attempt = 0;
+
+                    self.connected.store(true, Ordering::Relaxed);
+
+                    let interval = Duration::from_millis(hello.heartbeat_interval);
+
+                    timeout = Some(interval + HEARTBEAT_GRACE);
+                    deadline = Instant::now() + interval + HEARTBEAT_GRACE;
+
+                    tracing::info!(
+                        id = %hello.session_id,
+                        limit = hello.subscription_limit,
+                        "Hello received, session established with {interval:?} heartbeats"
+                    );
+
+                    // A resumed session restores once the resume is acknowledged
+                    if !resuming {
+                        self.restore(false).await;
+                    }
+                }
+                ServerMessage::Heartbeat(heartbeat) => {
+                    tracing::trace!(count = heartbeat.count, "Heartbeat received");
+                }
+                ServerMessage::Reconnect(reconnect) => {
+                    tracing::info!(reason = %reconnect.reason, "Reconnect requested");
+                    return Disconnect::Requested;
+                }
+                ServerMessage::Ack(ack) => {
+                    tracing::debug!(command = %ack.command, data = %ack.data, "Opcode acknowledged");
+
+                    if let Some(reason) = self.handle_ack(ack).await {
+                        return reason;
+                    }
+                }
+                ServerMessage::Error(error) => {
+                    tracing::error!(fields = %error.fields, "7TV error: {}", error.message);
+
+                    let command = self.take_pending(|_| true);
+
+                    if let Some(command) = command {
+                        self.fail(
+                            command,
+                            SeventvError::Rejected {
+                                message: error.message,
+                            },
+                        )
+                        .await;
+                    }
+                }
+                ServerMessage::EndOfStream(eos) => {
+                    tracing::warn!(code = eos.code, "End of stream reached: {}", eos.message);
+
+                    let err = SeventvError::from_close(eos.code, eos.message);
+
+                    // These are caused by the last command sent
+                    if matches!(
+                        err,
+                        SeventvError::RateLimited
+                            | SeventvError::AlreadySubscribed
+                            | SeventvError::NotSubscribed
+                    ) && let Some(command) = self.take_pending(|_| true)
+                    {
+                        self.fail(command, err).await;
+                    }
+
+                    return Disconnect::Closed;
+                }
+            }
+        }
+    }
+
+    async fn handle_ack(&self, ack: Ack) -> Option<Disconnect> {
+        let op = match ack.command.as_str() {
+            "SUBSCRIBE" => Opcode::Subscribe,
+            "UNSUBSCRIBE" => Opcode::Unsubscribe,
+            "RESUME" => return self.verify_resume(&ack.data).await,
+            _ => return None,
+        };
+
+        let kind = ack.data["type"].as_str();
+        let condition = ack.data.get("condition");
+
+        // Prefer an exact match but fall back to the oldest command of the
+        // same kind in case the server normalized the condition
+        let command = self
+            .take_pending(|c| {
+                c.op == op && kind == Some(c.kind.as_str()) && condition == Some(&c.condition)
+            })
+            .or_else(|| self.take_pending(|c| c.op == op && kind.is_none_or(|k| k == c.kind)));
+
+        let Some(command) = command else {
+            tracing::debug!("Received {} ack without a pending command", ack.command);
+            return None;
+        };
+
+        if op == Opcode::Subscribe
+            && let Some(subscription) = self.subscriptions.lock().await.get_mut(&command.key)
+            && subscription.condition == command.condition
+        {
+            subscription.state = SubscriptionState::Active;
+        }
+
+        let _ = command.result.send(Ok(()));
+
+        None
+    }
+
+    /// Checks that a resumed session holds the subscriptions the client
+    /// thinks are active, starting over with a new session if it doesn't.
+    async fn verify_resume(&self, data: &serde_json::Value) -> Option<Disconnect> {
+        if data["success"] != true {
+            tracing::warn!("Resume unsuccessful, restoring subscriptions");
+            self.restore(false).await;
+
+            return None;
+        }
+
+        let active = self
+            .subscriptions
+            .lock()
+            .await
+            .values()
+            .filter(|sub| sub.state == SubscriptionState::Active)
+            .count();
+
+        if let Some(restored) = data["subscriptions_restored"].as_u64()
+            && restored as usize != active
+        {
+            tracing::warn!(
+                restored,
+                active,
+                "Resumed session is out of sync, starting a new one"
+            );
+
+            *self.session_id.lock().await = None;
+
+            return Some(Disconnect::Desynced);
+        }
+
+        tracing::info!("Session resumed with {active} subscriptions");
+        self.restore(true).await;
+
+        None
+    }
+
+    /// Sends the subscribe commands for queued subscriptions, and for active
+    /// ones too unless `queued_only` is set.
+    async fn restore(&self, queued_only: bool) {
+        let to_restore: Vec<_> = {
+            let mut subscriptions = self.subscriptions.lock().await;
+
+            subscriptions
+                .iter_mut()
+                .filter(|(_, sub)| {
+                    sub.state == SubscriptionState::Queued
+                        || (!queued_only && sub.state == SubscriptionState::Active)
+                })
+                .map(|(key, sub)| {
+                    sub.state = SubscriptionState::Pending;
+                    (key.clone(), sub.condition.clone())
+                })
+                .collect()
+        };
+
+        if !to_restore.is_empty() {
+            tracing::info!("Restoring {} subscriptions", to_restore.len());
+        }
+
+        for (key, condition) in to_restore {
+            let (_, event) = key.split_once(':').unwrap();
+
+            // Only failures to send are reported here, the rest are handled
+            // when the server replies
+            if let Err(err) = self.send_command(Opcode::Subscribe, &key, event, &condition) {
+                tracing::error!(%err, "Failed to restore subscription to {event}");
+            }
+        }
+    }
+
+    /// Fails every command waiting for an acknowledgement after the
+    /// connection was lost, queueing their subscriptions to be restored.
+    async fn requeue(&self) {
+        let pending: Vec<_> = self.pending.lock().unwrap().drain(..).collect();
+
+        let mut subscriptions = self.subscriptions.lock().await;
+
+        for subscription in subscriptions.values_mut() {
+            if subscription.state == SubscriptionState::Pending {
+                subscription.state = SubscriptionState::Queued;
+            }
+        }
+
+        for command in pending {
+            let _ = command.result.send(Err(SeventvError::Disconnected));
+        }
+    }
+
+    /// Resolves a command with an error, forgetting its subscription unless
+    /// the server already has it.
+    async fn fail(&self, command: PendingCommand, err: SeventvError) {
+        tracing::error!(%err, "7TV {:?} command for {} failed", command.op, command.key);
+
+        if command.op == Opcode::Subscribe {
+            let mut subscriptions = self.subscriptions.lock().await;
+
+            let matches = subscriptions
+                .get(&command.key)
+                .is_some_and(|sub| sub.condition == command.condition);
+
+            if matches && matches!(err, SeventvError::AlreadySubscribed) {
+                subscriptions.get_mut(&command.key).unwrap().state = SubscriptionState::Active;
+            } else if matches {
+                subscriptions.remove(&command.key);
+            }
+        }
+
+        let _ = command.result.send(Err(err));
+    }
+
+    fn take_pending(&self, predicate: impl Fn(&PendingCommand) -> bool) -> Option<PendingCommand> {
+        let mut pending = self.pending.lock().unwrap();
+        let index = pending.iter().position(predicate)?;
+
+        pending.remove(index)
+    }
+
+    /// Sends a subscribe or unsubscribe command, returning its id and a
+    /// receiver for its outcome.
+    fn send_command(
+        &self,
+        op: Opcode,
+        key: &str,
+        kind: &str,
+        condition: &serde_json::Value,
+    ) -> Result<(u64, oneshot::Receiver<Result<(), SeventvError>>), SeventvError> {
+        let payload = match op {
+            Opcode::Unsubscribe => ClientMessage::unsubscribe(kind, condition).to_json(),
+            _ => ClientMessage::subscribe(kind, condition).to_json(),
+        };
+
+        let id = self.next_command.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+
+        // Queued before sending so the ack can't arrive first
+        self.pending.lock().unwrap().push_back(PendingCommand {
+            id,
+            op,
+            key: key.to_string(),
+            kind: kind.to_string(),
+            condition: condition.clone(),
+            result: sender,
+        });
+
+        if self.message_tx.send(Message::Text(payload.into())).is_err() {
+            self.take_pending(|c| c.id == id);
+            return Err(SeventvError::NotConnected);
+        }
+
+        Ok((id, receiver))
+    }
+
+    async fn wait_for_ack(
+        &self,
+        id: u64,
+        receiver: oneshot::Receiver<Result<(), SeventvError>>,
+    ) -> Result<(), SeventvError> {
+        match tokio::time::timeout(ACK_TIMEOUT, receiver).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(SeventvError::Disconnected),
+            Err(_) => {
+                self.take_pending(|c| c.id == id);
+                Err(SeventvError::Timeout)
+            }
+        }
+    }
+
     pub fn connected(&self) -> bool {
         self.connected.load(Ordering::Relaxed)
     }
 
+    pub async fn connection_status(&self) -> ConnectionStatus {
+        let mut subscriptions: Vec<_> = self
+            .subscriptions
+            .lock()
+            .await
+            .iter()
+            .filter_map(|(key, subscription)| {
+                let (channel, event) = key.split_once(':')?;
+
+                Some(SubscriptionStatus {
+                    channel: channel.to_string(),
+                    kind: event.to_string(),
+                    condition: subscription.condition.clone(),
+                    state: subscription.state,
+                })
+            })
+            .collect();
+
+        subscriptions.sort_by(|a, b| (&a.channel, &a.kind).cmp(&(&b.channel, &b.kind)));
+
+        ConnectionStatus {
+            session_id: self.session_id.lock().await.clone(),
+            connected: self.connected(),
+            subscriptions,
+            last_message_at: match self.last_message.load(Ordering::Relaxed) {
+                0 => None,
+                millis => Some(millis),
+            },
+        }
+    }
+
+    /// Subscribes to an event, resolving once the server acknowledges it. If
+    /// disconnected, the subscription is queued and sent after reconnecting,
+    /// which is reported by returning [`SubscriptionState::Queued`].
     #[tracing::instrument(name = "7tv_subscribe", skip(self, condition), fields(%condition))]
-    pub async fn subscribe(&self, channel: &str, event: &str, condition: &serde_json::Value) {
-        let payload = json!({
-            "op": 35,
-            "d": {
-                "type": event,
-                "condition": condition
-            }
-        });
+    pub async fn subscribe(
+        &self,
+        channel: &str,
+        event: &str,
+        condition: &serde_json::Value,
+    ) -> Result<SubscriptionState, SeventvError> {
+        let key = format!("{channel}:{event}");
+        let connected = self.connected();
 
-        match self
-            .message_tx
-            .send(Message::Text(payload.to_string().into()))
         {
-            Ok(_) => {
+            let mut subscriptions = self.subscriptions.lock().await;
+
+            if let Some(sub) = subscriptions
+                .get(&key)
+                .filter(|sub| sub.condition == *condition)
+            {
+                tracing::trace!("Already subscribed");
+                return Ok(sub.state);
+            }
+
+            let state = if connected {
+                SubscriptionState::Pending
+            } else {
+                SubscriptionState::Queued
+            };
+
+            subscriptions.insert(
+                key.clone(),
+                Subscription {
+                    condition: condition.clone(),
+                    state,
+                },
+            );
+        }
+
+        if !connected {
+            tracing::debug!("Not connected, subscription queued");
+            return Ok(SubscriptionState::Queued);
+        }
+
+        let result = match self.send_command(Opcode::Subscribe, &key, event, condition) {
+            Ok((id, receiver)) => self.wait_for_ack(id, receiver).await,
+            Err(err) => Err(err),
+        };
+
+        match &result {
+            Ok(()) => tracing::trace!("Subscription created"),
+            Err(SeventvError::NotConnected | SeventvError::Timeout) => {
                 let mut subscriptions = self.subscriptions.lock().await;
-                subscriptions.insert(format!("{channel}:{event}"), condition.clone());
 
-                tracing::trace!("Subscription created");
-            }
-            Err(err) => {
-                tracing::error!(%err, "Failed to send subscription message");
+                if subscriptions
+                    .get(&key)
+                    .is_some_and(|sub| sub.state == SubscriptionState::Pending)
+                {
+                    subscriptions.remove(&key);
+                }
             }
+            // Rejections are cleaned up when received and disconnections are
+            // restored
+            Err(_) => (),
         }
+
+        result.map(|()| SubscriptionState::Active)
     }
 
-    pub async fn unsubscribe(&self, channel: &str, event: &str) {
-        let mut subscriptions = self.subscriptions.lock().await;
+    pub async fn unsubscribe(&self, channel: &str, event: &str) -> Result<(), SeventvError> {
+        let key = format!("{channel}:{event}");
 
-        if let Some(condition) = subscriptions.remove(&format!("{channel}:{event}")) {
-            let payload = json!({
-                "op": 36,
-                "d": {
-                    "type": event,
-                    "condition": condition
-                }
-            });
+        let Some(subscription) = self.subscriptions.lock().await.remove(&key) else {
+            return Ok(());
+        };
 
-            let _ = self
-                .message_tx
-                .send(Message::Text(payload.to_string().into()));
+        // The server doesn't know about queued subscriptions and will drop
+        // the rest when the session isn't resumed
+        if subscription.state == SubscriptionState::Queued || !self.connected() {
+            return Ok(());
         }
+
+        let (id, receiver) =
+            self.send_command(Opcode::Unsubscribe, &key, event, &subscription.condition)?;
+
+        self.wait_for_ack(id, receiver).await
     }
 
-    pub async fn unsubscribe_all(&self, channel: &str) {
+    pub async fn unsubscribe_all(&self, channel: &str) -> Result<(), SeventvError> {
         let prefix = format!("{channel}:");
 
         let events = {
@@ -234,6 +686,6 @@ impl SeventTvClient {
 
         let futures = events.iter().map(|event| self.unsubscribe(channel, event));
 
-        join_all(futures).await;
+        join_all(futures).await.into_iter().collect()
     }
 }
@@ -0,0 +1,44 @@
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Error {
+    /// The command couldn't be sent because the client task stopped
+    #[error("No 7TV connection")]
+    NotConnected,
+    /// The connection was lost before the command was acknowledged. The
+    /// subscription is kept and restored after reconnecting.
+    #[error("7TV connection lost before the command was acknowledged")]
+    Disconnected,
+    /// No acknowledgement was received in time
+    #[error("7TV didn't acknowledge the command in time")]
+    Timeout,
+    /// The server replied with an error
+    #[error("7TV rejected the command: {message}")]
+    Rejected { message: String },
+    /// Too many commands were sent
+    #[error("Rate limited by 7TV")]
+    RateLimited,
+    /// A subscription with the same type and condition already exists
+    #[error("Already subscribed to the 7TV event")]
+    AlreadySubscribed,
+    /// There was no subscription to remove
+    #[error("Not subscribed to the 7TV event")]
+    NotSubscribed,
+    /// The server ended the stream for any other reason
+    #[error("7TV closed the connection with code {code}: {message}")]
+    Closed { code: u16, message: String },
+}
+
+impl Error {
+    /// Maps the close code of an end of stream message to an error.
+    pub fn from_close(code: u16, message: String) -> Self {
+        match code {
+            4005 => Self::RateLimited,
+            4009 => Self::AlreadySubscribed,
+            4010 => Self::NotSubscribed,
+            _ => Self::Closed { code, message },
+        }
+    }
+}
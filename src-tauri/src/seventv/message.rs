@@ -0,0 +1,461 @@
+use serde::de::Error as DeError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Operation codes of the 7TV Event API.
+///
+/// Codes below 32 are sent by the server and the rest by the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Opcode {
+    Dispatch = 0,
+    Hello = 1,
+    Heartbeat = 2,
+    Reconnect = 4,
+    Ack = 5,
+    Error = 6,
+    EndOfStream = 7,
+    Identify = 33,
+    Resume = 34,
+    Subscribe = 35,
+    Unsubscribe = 36,
+    Signal = 37,
+    Bridge = 38,
+}
+
+impl TryFrom<u8> for Opcode {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, u8> {
+        Ok(match value {
+            0 => Self::Dispatch,
+            1 => Self::Hello,
+            2 => Self::Heartbeat,
+            4 => Self::Reconnect,
+            5 => Self::Ack,
+            6 => Self::Error,
+            7 => Self::EndOfStream,
+            33 => Self::Identify,
+            34 => Self::Resume,
+            35 => Self::Subscribe,
+            36 => Self::Unsubscribe,
+            37 => Self::Signal,
+            38 => Self::Bridge,
+            _ => return Err(value),
+        })
+    }
+}
+
+impl Serialize for Opcode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+impl<'de> Deserialize<'de> for Opcode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let op = u8::deserialize(deserializer)?;
+
+        Self::try_from(op).map_err(|op| D::Error::custom(format!("unknown opcode {op}")))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMessage {
+    op: Opcode,
+    #[serde(default)]
+    d: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Hello {
+    /// Interval in milliseconds between heartbeats sent by the server.
+    pub heartbeat_interval: u64,
+    pub session_id: String,
+    #[serde(default)]
+    pub subscription_limit: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Heartbeat {
+    pub count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Reconnect {
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Ack {
+    /// Name of the acknowledged command, e.g. `SUBSCRIBE`.
+    pub command: String,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ErrorPayload {
+    pub message: String,
+    #[serde(default)]
+    pub fields: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EndOfStream {
+    pub code: u16,
+    #[serde(default)]
+    pub message: String,
+}
+
+/// A message sent by the server.
+#[derive(Debug)]
+pub enum ServerMessage {
+    Dispatch(Dispatch),
+    Hello(Hello),
+    Heartbeat(Heartbeat),
+    Reconnect(Reconnect),
+    Ack(Ack),
+    Error(ErrorPayload),
+    EndOfStream(EndOfStream),
+}
+
+impl ServerMessage {
+    pub fn parse(text: &str) -> Result<Self, serde_json::Error> {
+        use serde_json::from_value;
+
+        let RawMessage { op, d } = serde_json::from_str(text)?;
+
+        Ok(match op {
+            Opcode::Dispatch => Self::Dispatch(from_value(d)?),
+            Opcode::Hello => Self::Hello(from_value(d)?),
+            Opcode::Heartbeat => Self::Heartbeat(from_value(d)?),
+            Opcode::Reconnect => Self::Reconnect(from_value(d)?),
+            Opcode::Ack => Self::Ack(from_value(d)?),
+            Opcode::Error => Self::Error(from_value(d)?),
+            Opcode::EndOfStream => Self::EndOfStream(from_value(d)?),
+            op => {
+                return Err(serde_json::Error::custom(format!(
+                    "unexpected client opcode {op:?}"
+                )));
+            }
+        })
+    }
+}
+
+/// A message sent by the client.
+#[derive(Debug, Serialize)]
+pub struct ClientMessage<T> {
+    op: Opcode,
+    d: T,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubscriptionData<'a> {
+    #[serde(rename = "type")]
+    pub kind: &'a str,
+    pub condition: &'a serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResumeData<'a> {
+    pub session_id: &'a str,
+}
+
+impl<'a> ClientMessage<SubscriptionData<'a>> {
+    pub fn subscribe(kind: &'a str, condition: &'a serde_json::Value) -> Self {
+        Self {
+            op: Opcode::Subscribe,
+            d: SubscriptionData { kind, condition },
+        }
+    }
+
+    pub fn unsubscribe(kind: &'a str, condition: &'a serde_json::Value) -> Self {
+        Self {
+            op: Opcode::Unsubscribe,
+            d: SubscriptionData { kind, condition },
+        }
+    }
+}
+
+impl<'a> ClientMessage<ResumeData<'a>> {
+    pub fn resume(session_id: &'a str) -> Self {
+        Self {
+            op: Opcode::Resume,
+            d: ResumeData { session_id },
+        }
+    }
+}
+
+impl<T: Serialize> ClientMessage<T> {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("client messages are always serializable")
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserConnection {
+    pub id: String,
+    pub platform: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(default)]
+    pub emote_set_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserStyle {
+    #[serde(default)]
+    pub color: i64,
+    #[serde(default)]
+    pub badge_id: Option<String>,
+    #[serde(default)]
+    pub paint_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct User {
+    pub id: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(default)]
+    pub avatar_url: String,
+    #[serde(default)]
+    pub role_ids: Vec<String>,
+    #[serde(default)]
+    pub connections: Vec<UserConnection>,
+    #[serde(default)]
+    pub style: UserStyle,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostFile {
+    pub name: String,
+    #[serde(default)]
+    pub static_name: String,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub frame_count: u32,
+    pub format: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Host {
+    pub url: String,
+    pub files: Vec<HostFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmoteData {
+    pub name: String,
+    #[serde(default)]
+    pub flags: u32,
+    #[serde(default)]
+    pub listed: bool,
+    #[serde(default)]
+    pub animated: bool,
+    pub host: Host,
+    #[serde(default)]
+    pub owner: Option<User>,
+}
+
+/// An emote as it appears in an emote set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmoteChange {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub flags: u32,
+    /// Missing from removed emotes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<EmoteData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeField<T> {
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    #[serde(default)]
+    pub nested: bool,
+    pub old_value: Option<T>,
+    pub value: Option<T>,
+}
+
+/// The changes made to an object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeMap<T> {
+    pub id: String,
+    pub kind: u8,
+    #[serde(default)]
+    pub actor: User,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pushed: Option<Vec<ChangeField<T>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pulled: Option<Vec<ChangeField<T>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<Vec<ChangeField<T>>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectRef {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+/// A changed field of a user. Connection changes are nested one level deep,
+/// anything else is passed through untouched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum UserChange {
+    Nested(Vec<ChangeField<ObjectRef>>),
+    Value(serde_json::Value),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BadgeData {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub tooltip: String,
+    pub host: Host,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaintShadow {
+    pub color: i64,
+    pub radius: f64,
+    pub x_offset: f64,
+    pub y_offset: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaintStop {
+    pub color: i64,
+    pub at: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaintData {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<i64>,
+    pub function: String,
+    #[serde(default)]
+    pub repeat: bool,
+    #[serde(default)]
+    pub angle: i32,
+    #[serde(default)]
+    pub shape: String,
+    #[serde(default)]
+    pub image_url: String,
+    #[serde(default)]
+    pub shadows: Vec<PaintShadow>,
+    #[serde(default)]
+    pub stops: Vec<PaintStop>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "data", rename_all = "UPPERCASE")]
+pub enum CosmeticData {
+    Badge(BadgeData),
+    Paint(PaintData),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cosmetic {
+    pub id: String,
+    #[serde(flatten)]
+    pub data: CosmeticData,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EntitlementKind {
+    Badge,
+    Paint,
+    EmoteSet,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entitlement {
+    pub id: String,
+    pub kind: EntitlementKind,
+    pub ref_id: String,
+    pub user: User,
+}
+
+/// The body of a dispatch, deserialized according to its type.
+///
+/// Bodies carrying a created or deleted object serialize to the object
+/// itself.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum DispatchBody {
+    CosmeticCreate(Cosmetic),
+    EmoteSetUpdate(ChangeMap<EmoteChange>),
+    EntitlementCreate(Entitlement),
+    UserUpdate(ChangeMap<UserChange>),
+    /// A dispatch of an unsupported type or one that failed to deserialize,
+    /// passed through untouched.
+    Opaque(serde_json::Value),
+}
+
+fn typed<T: for<'a> Deserialize<'a>>(
+    body: &serde_json::Value,
+    variant: fn(T) -> DispatchBody,
+) -> Result<DispatchBody, serde_json::Error> {
+    T::deserialize(body).map(variant)
+}
+
+impl DispatchBody {
+    pub fn parse(kind: &str, mut body: serde_json::Value) -> Self {
+        if let Some(object) = body.get_mut("object") {
+            body = object.take();
+        }
+
+        let result = match kind {
+            "cosmetic.create" => typed(&body, Self::CosmeticCreate),
+            "emote_set.update" => typed(&body, Self::EmoteSetUpdate),
+            "entitlement.create" => typed(&body, Self::EntitlementCreate),
+            "user.update" => typed(&body, Self::UserUpdate),
+            _ => return Self::Opaque(body),
+        };
+
+        result.unwrap_or_else(|err| {
+            tracing::warn!(%err, "Failed to deserialize {kind} dispatch");
+            Self::Opaque(body)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Dispatch {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub body: DispatchBody,
+}
+
+impl<'de> Deserialize<'de> for Dispatch {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Raw {
+            #[serde(rename = "type")]
+            kind: String,
+            #[serde(default)]
+            body: serde_json::Value,
+        }
+
+        let Raw { kind, body } = Raw::deserialize(deserializer)?;
+        let body = DispatchBody::parse(&kind, body);
+
+        Ok(Self { kind, body })
+    }
+}
@@ -1,30 +1,41 @@
 pub mod client;
+pub mod error;
+pub mod message;
 
 use std::sync::Arc;
 
-pub use client::SeventTvClient;
+pub use client::{SeventTvClient, SubscriptionState};
+use message::DispatchBody;
 use serde_json::json;
 use tauri::ipc::Channel;
-use tauri::{AppHandle, Manager, State, async_runtime};
+use tauri::{AppHandle, Manager, State, Webview, async_runtime};
 use tokio::sync::Mutex;
 
 use crate::AppState;
+use crate::badges::BadgeCatalog;
+use crate::emotes::EmoteRegistry;
 use crate::error::Error;
 
 #[tauri::command]
 pub async fn connect_seventv(
     app_handle: AppHandle,
+    webview: Webview,
     state: State<'_, Mutex<AppState>>,
-    channel: Channel<serde_json::Value>,
+    channel: Channel,
 ) -> Result<(), Error> {
     let mut state = state.lock().await;
+    let subscribers = state.subscribers.seventv.clone();
 
-    if let Some(client) = &state.seventv
-        && client.connected()
-    {
+    subscribers.attach(webview.label(), channel);
+
+    // The client reconnects on its own, so it's reused even if it's currently
+    // disconnected.
+    if state.seventv.is_some() {
+        tracing::info!("Reusing existing 7TV connection");
         return Ok(());
     }
 
+    let registry_handle = app_handle.clone();
     let (mut incoming, client) = SeventTvClient::new();
     let client = Arc::new(client);
 
@@ -42,7 +53,17 @@ pub async fn connect_seventv(
 
     async_runtime::spawn(async move {
         while let Some(message) = incoming.recv().await {
-            channel.send(message).unwrap();
+            registry_handle
+                .state::<BadgeCatalog>()
+                .apply_seventv(&registry_handle, &message.body);
+
+            if let DispatchBody::EmoteSetUpdate(ref changes) = message.body {
+                registry_handle
+                    .state::<EmoteRegistry>()
+                    .apply_seventv(&registry_handle, changes);
+            }
+
+            subscribers.send(message);
         }
     });
 
@@ -55,16 +76,23 @@ pub async fn resub_emote_set(
     channel: String,
     set_id: String,
 ) -> Result<(), Error> {
-    let state = state.lock().await;
-
-    let Some(ref seventv) = state.seventv else {
+    let Some(seventv) = state.lock().await.seventv.clone() else {
         return Ok(());
     };
 
-    seventv.unsubscribe(&channel, "emote_set.*").await;
-    seventv
+    // The previous set may already be gone, so the new one is subscribed to
+    // regardless
+    if let Err(err) = seventv.unsubscribe(&channel, "emote_set.*").await {
+        tracing::warn!(%err, "Failed to unsubscribe from previous emote set");
+    }
+
+    let subscription = seventv
         .subscribe(&channel, "emote_set.*", &json!({ "object_id": set_id }))
-        .await;
+        .await?;
+
+    if subscription == SubscriptionState::Queued {
+        tracing::debug!("Emote set subscription queued until reconnected");
+    }
 
     Ok(())
 }
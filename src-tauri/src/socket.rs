@@ -0,0 +1,182 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use futures::{SinkExt, StreamExt};
+use tokio::sync::{Mutex, mpsc};
+use tokio::time::Instant;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::Instrument;
+
+use crate::error::Error;
+
+/// How long a connection has to stay up for the reconnection backoff to be
+/// reset.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// A client of a websocket that is reconnected to whenever it drops.
+pub trait SocketHandler: Send + Sync + 'static {
+    /// Name of the service, used in logs.
+    const NAME: &'static str;
+    const URL: &'static str;
+
+    /// How often the server is pinged. The connection is considered dead if
+    /// nothing, not even a pong, is received before the next ping.
+    const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+    fn socket(&self) -> &Socket;
+
+    /// Messages sent after (re)connecting, such as joining channels again.
+    fn on_connect(&self) -> impl Future<Output = Vec<Message>> + Send;
+
+    /// Handles a text message, returning `false` to reconnect.
+    fn on_text(&self, text: &str) -> impl Future<Output = bool> + Send;
+}
+
+/// The connection state and outgoing messages of a [`SocketHandler`].
+pub struct Socket {
+    connected: AtomicBool,
+    message_tx: mpsc::UnboundedSender<Message>,
+    message_rx: Mutex<Option<mpsc::UnboundedReceiver<Message>>>,
+}
+
+impl Socket {
+    pub fn new() -> Self {
+        let (message_tx, message_rx) = mpsc::unbounded_channel();
+
+        Self {
+            connected: AtomicBool::default(),
+            message_tx,
+            message_rx: Mutex::new(Some(message_rx)),
+        }
+    }
+
+    pub fn connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Sends a message if connected. Messages that should survive a
+    /// reconnect are sent again by [`SocketHandler::on_connect`] instead.
+    pub fn send(&self, message: Message) {
+        if self.connected() {
+            let _ = self.message_tx.send(message);
+        }
+    }
+}
+
+/// Spawns the task that keeps the handler connected.
+pub async fn connect<H: SocketHandler>(handler: Arc<H>) -> Result<(), Error> {
+    let message_rx = handler
+        .socket()
+        .message_rx
+        .lock()
+        .await
+        .take()
+        .ok_or_else(|| Error::Generic(anyhow!("Message receiver already taken")))?;
+
+    tokio::spawn(run(handler, message_rx).in_current_span());
+
+    Ok(())
+}
+
+async fn run<H: SocketHandler>(handler: Arc<H>, mut message_rx: mpsc::UnboundedReceiver<Message>) {
+    let name = H::NAME;
+    let mut attempt = 0;
+
+    loop {
+        if attempt > 0 {
+            let delay = crate::reconnect_delay(attempt);
+
+            tracing::info!("Reconnecting to {name} in {delay:?} (attempt {attempt})");
+            tokio::time::sleep(delay).await;
+        }
+
+        tracing::info!("Connecting to {name}");
+
+        let mut stream = match connect_async(H::URL).await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                tracing::error!(%err, "Failed to connect to {name}");
+
+                attempt += 1;
+                continue;
+            }
+        };
+
+        tracing::info!("Connected to {name}");
+
+        let connected_at = Instant::now();
+        handler.socket().connected.store(true, Ordering::Relaxed);
+
+        for message in handler.on_connect().await {
+            if let Err(err) = stream.send(message).await {
+                tracing::error!(%err, "Error sending {name} message");
+            }
+        }
+
+        let mut awaiting_pong = false;
+        let mut ping =
+            tokio::time::interval_at(Instant::now() + H::PING_INTERVAL, H::PING_INTERVAL);
+
+        loop {
+            tokio::select! {
+                Some(data) = message_rx.recv() => {
+                    if let Err(err) = stream.send(data).await {
+                        tracing::error!(%err, "Error sending message");
+                        break;
+                    }
+                }
+                _ = ping.tick() => {
+                    if awaiting_pong {
+                        tracing::warn!("{name} stopped responding");
+                        break;
+                    }
+
+                    if let Err(err) = stream.send(Message::Ping(Default::default())).await {
+                        tracing::error!(%err, "Error sending ping");
+                        break;
+                    }
+
+                    awaiting_pong = true;
+                }
+                message = stream.next() => {
+                    awaiting_pong = false;
+
+                    match message {
+                        Some(Ok(Message::Text(text))) => {
+                            if !handler.on_text(&text).await {
+                                break;
+                            }
+                        }
+                        Some(Ok(Message::Close(frame))) => {
+                            if let Some(frame) = frame {
+                                tracing::warn!(%frame, "{name} connection closed");
+                            }
+
+                            break;
+                        }
+                        Some(Ok(_)) => (),
+                        Some(Err(err)) => {
+                            tracing::error!(%err, "{name} connection error");
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        handler.socket().connected.store(false, Ordering::Relaxed);
+
+        // Anything still queued is sent again by `on_connect`
+        while message_rx.try_recv().is_ok() {}
+
+        attempt = if connected_at.elapsed() >= STABLE_AFTER {
+            1
+        } else {
+            attempt + 1
+        };
+    }
+}
@@ -1,20 +1,36 @@
+import { decode } from "@msgpack/msgpack";
 import { invoke, Channel as IpcChannel } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import type { UnlistenFn } from "@tauri-apps/api/event";
 import { SvelteMap } from "svelte/reactivity";
+import { goto } from "$app/navigation";
+import { resolve } from "$app/paths";
 import { handlers } from "./handlers";
 import { History } from "./history.svelte";
 import { log } from "./log";
+import { settings } from "./settings";
 import { BadgeManager } from "./managers/badge-manager";
 import { ChannelManager } from "./managers/channel-manager";
 import { EmoteManager } from "./managers/emote-manager";
 import { SplitLayout } from "./split-layout";
+import { storage } from "./stores";
 import { TwitchClient } from "./twitch/client";
+import type { BttvEvent } from "./bttv";
 import type { EmoteSet } from "./emotes";
+import type { FfzEvent } from "./ffz";
 import type { Badge } from "./graphql/twitch";
 import type { Channel } from "./models/channel.svelte";
 import type { CurrentUser } from "./models/current-user.svelte";
 import type { DispatchPayload, Paint } from "./seventv";
 import type { Theme } from "./themes";
-import type { NotificationPayload } from "./twitch/eventsub";
+import type { TokenInfo } from "./twitch";
+import type {
+	EventSubEndpoints,
+	EventSubStatus,
+	NotificationPayload,
+	Revocation,
+	SubscriptionResult,
+} from "./twitch/eventsub";
 import type { IrcMessage } from "./twitch/irc";
 
 class App {
@@ -76,34 +92,140 @@ class App {
 	public readonly u2b = new SvelteMap<string, Badge | undefined>();
 	public readonly u2p = new SvelteMap<string, Paint | undefined>();
 
+	/**
+	 * The latest status of each EventSub session, keyed by session index.
+	 */
+	public readonly eventsub = new SvelteMap<number, EventSubStatus>();
+
+	#ipcChannels: IpcChannel<any>[] = [];
+	#unlisteners: UnlistenFn[] = [];
+
 	public async connect() {
 		if (!this.user || this.connected) return;
 
-		const ircChannel = new IpcChannel<IrcMessage>(async (message) => {
-			await this.#handle(message.type, message);
+		const ircChannel = new IpcChannel<IrcMessage[] | ArrayBuffer>(async (payload) => {
+			const messages =
+				payload instanceof ArrayBuffer ? (decode(payload) as IrcMessage[]) : payload;
+
+			for (const message of messages) {
+				await this.#handle(message.type, message);
+			}
 		});
 
 		const eventsubChannel = new IpcChannel<NotificationPayload>(async (message) => {
-			await this.#handle(message.subscription.type, message.event);
+			if (message.event.kind === "opaque") {
+				log.debug(`Received untyped ${message.subscription.type} event`);
+			}
+
+			await this.#handle(message.subscription.type, message.event.data);
 		});
 
 		const seventvChannel = new IpcChannel<DispatchPayload>(async (message) => {
-			await this.#handle(
-				message.type,
-				"object" in message.body ? message.body.object : message.body,
-			);
+			await this.#handle(message.type, message.body);
 		});
 
+		const bttvChannel = new IpcChannel<BttvEvent>(async (event) => {
+			await this.#handle(event.type, event);
+		});
+
+		const ffzChannel = new IpcChannel<FfzEvent>(async (event) => {
+			await this.#handle(event.type, event);
+		});
+
+		this.#ipcChannels = [ircChannel, eventsubChannel, seventvChannel, bttvChannel, ffzChannel];
+
+		this.#unlisteners.push(
+			await listen<EventSubStatus | Revocation>("eventsubstatus", (event) => {
+				if (event.payload.type === "revoked") {
+					this.#revoke(event.payload);
+				} else {
+					this.eventsub.set(event.payload.session, event.payload);
+				}
+			}),
+			await listen<{ channel: string; results: SubscriptionResult[] }>(
+				"subscriptionresults",
+				(event) => {
+					const channel = this.channels.getByLogin(event.payload.channel);
+					if (channel) channel.subscriptions = event.payload.results;
+				},
+			),
+			await listen<TokenInfo>("tokenrefreshed", async (event) => {
+				if (!storage.state.user) return;
+
+				log.info("Access token refreshed");
+
+				this.twitch.token = event.payload.access_token;
+				storage.state.user.token = event.payload.access_token;
+				storage.state.user.refreshToken = event.payload.refresh_token ?? undefined;
+
+				await storage.saveNow();
+			}),
+			await listen("tokenexpired", async () => {
+				log.warn("Access token expired, logging out");
+				await goto(resolve("/auth/logout"));
+			}),
+		);
+
 		await Promise.all([
-			invoke("connect_irc", { channel: ircChannel }),
-			invoke("connect_eventsub", { channel: eventsubChannel }),
+			invoke("connect_irc", {
+				channel: ircChannel,
+				options: {
+					raw: settings.state["advanced.ipc.raw"],
+					encoding: settings.state["advanced.ipc.encoding"],
+				},
+			}),
+			invoke("connect_eventsub", {
+				channel: eventsubChannel,
+				endpoints: await this.#eventsubEndpoints(),
+			}),
 			invoke("connect_seventv", { channel: seventvChannel }),
+			settings.state["chat.emotes.bttv"] && invoke("connect_bttv", { channel: bttvChannel }),
+			settings.state["chat.emotes.ffz"] && invoke("connect_ffz", { channel: ffzChannel }),
 		]);
 
 		this.connected = true;
 		log.info("All connections established");
 	}
 
+	/**
+	 * Detaches the channels of this webview from the backend streams.
+	 */
+	public async disconnect() {
+		if (!this.connected) return;
+
+		await Promise.all(
+			this.#ipcChannels.map((channel) => invoke("detach_channel", { id: channel.id })),
+		);
+
+		for (const unlisten of this.#unlisteners) unlisten();
+
+		this.#ipcChannels = [];
+		this.#unlisteners = [];
+		this.connected = false;
+		log.info("Detached from all connections");
+	}
+
+	async #eventsubEndpoints() {
+		if (import.meta.env.DEV && settings.state["advanced.eventsub.mock"]) {
+			return invoke<EventSubEndpoints>("start_mock_eventsub");
+		}
+
+		const websocket = settings.state["advanced.eventsub.websocketUrl"];
+		const subscriptions = settings.state["advanced.eventsub.subscriptionsUrl"];
+
+		return websocket && subscriptions ? { websocket, subscriptions } : null;
+	}
+
+	#revoke(revocation: Revocation) {
+		log.warn(
+			`${revocation.event} subscription for ${revocation.channel} revoked: ${revocation.reason}`,
+		);
+
+		if (revocation.reason === "moderator_removed" && this.user) {
+			this.user.moderating.delete(revocation.condition.broadcaster_user_id);
+		}
+	}
+
 	async #handle(key: string, payload: any) {
 		await handlers.get(key)?.handle(payload);
 	}
@@ -0,0 +1,26 @@
+import type { BttvEmote } from "./emotes";
+
+export interface EmoteCreate {
+	channel_id: string;
+	emote: BttvEmote;
+}
+
+export interface EmoteUpdate {
+	channel_id: string;
+	emote: BttvEmote;
+}
+
+export interface EmoteDelete {
+	channel_id: string;
+	emote_id: string;
+}
+
+export interface BttvEventMap {
+	"bttv.emote_create": EmoteCreate;
+	"bttv.emote_update": EmoteUpdate;
+	"bttv.emote_delete": EmoteDelete;
+}
+
+export type BttvEvent = {
+	[K in keyof BttvEventMap]: { type: K } & BttvEventMap[K];
+}[keyof BttvEventMap];
@@ -69,6 +69,32 @@ export interface EmoteSet {
 	readonly global?: boolean;
 }
 
+export interface CheermoteImage {
+	url: string;
+	dpiScale: number;
+}
+
+export interface CheermoteTier {
+	id: string;
+
+	/**
+	 * The minimum number of bits to use the tier.
+	 */
+	bits: number;
+
+	color: string;
+
+	/**
+	 * The animated images of the tier, ordered by pixel density.
+	 */
+	images: CheermoteImage[];
+}
+
+export interface Cheermote {
+	prefix: string;
+	tiers: CheermoteTier[];
+}
+
 export interface FfzEmote {
 	id: number;
 	name: string;
@@ -77,14 +103,6 @@ export interface FfzEmote {
 	urls: Record<number, string>;
 }
 
-export interface FfzEmoteSet {
-	emoticons: FfzEmote[];
-}
-
-export interface GlobalSet {
-	sets: Record<number, FfzEmoteSet>;
-}
-
 export interface BttvEmote {
 	id: string;
 	code: string;
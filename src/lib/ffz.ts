@@ -0,0 +1,14 @@
+import type { FfzEmote } from "./emotes";
+
+export interface RoomUpdate {
+	channel_id: string;
+	emotes: FfzEmote[];
+}
+
+export interface FfzEventMap {
+	"ffz.room_update": RoomUpdate;
+}
+
+export type FfzEvent = {
+	[K in keyof FfzEventMap]: { type: K } & FfzEventMap[K];
+}[keyof FfzEventMap];
@@ -1,6 +1,5 @@
 import { initGraphQLTada } from "gql.tada";
-import type { FragmentOf, ResultOf } from "gql.tada";
-import type { NonNullableDeep } from ".";
+import type { FragmentOf } from "gql.tada";
 
 const gql = initGraphQLTada<{
 	disableMasking: true;
@@ -50,22 +49,6 @@ export type Emote = FragmentOf<typeof emoteDetailsFragment>;
 
 // Queries
 
-export const activeEmoteSetQuery = gql(
-	`query GetActiveEmoteSet($id: String!, $details: Boolean!) {
-		users {
-			userByConnection(platform: TWITCH, platformId: $id) {
-				style {
-					activeEmoteSet {
-						id
-						...EmoteSetDetails @include(if: $details)
-					}
-				}
-			}
-		}
-	}`,
-	[emoteSetDetailsFragment],
-);
-
 export const emoteQuery = gql(
 	`query GetEmote($id: Id!) {
 		emotes {
@@ -85,17 +68,6 @@ export const emoteQuery = gql(
 	[emoteDetailsFragment],
 );
 
-export const globalEmoteSetQuery = gql(
-	`query GetGlobalEmoteSet {
-		emoteSets {
-			global: emoteSet(id: "01HKQT8EWR000ESSWF3625XCS4") {
-				...EmoteSetDetails
-			}
-		}
-	}`,
-	[emoteSetDetailsFragment],
-);
-
 export const userIdQuery = gql(`
 	query GetUserID($id: String!) {
 		users {
@@ -122,13 +94,3 @@ export const userEmoteSetsQuery = gql(
 	}`,
 	[emoteSetDetailsFragment],
 );
-
-// Types
-
-export type ActiveEmoteSet<D = true> = Extract<
-	NonNullableDeep<
-		ResultOf<typeof activeEmoteSetQuery>,
-		"users.userByConnection.style.activeEmoteSet"
-	>,
-	D extends true ? { name: string } : any
->;
@@ -22,23 +22,6 @@ const badgeDetailsFragment = gql(`
 	}
 `);
 
-const cheermoteDetailsFragment = gql(`
-	fragment CheermoteDetails on Cheermote {
-		id
-		prefix
-		tiers {
-			id
-			bits
-			color
-			images(theme: DARK, isAnimated: true) {
-				id
-				dpiScale
-				url
-			}
-		}
-	}
-`);
-
 const guestStarDetailsFragment = gql(`
 	fragment GuestStarDetails on Channel {
 		guestStarSessionCall {
@@ -89,30 +72,6 @@ const userDetailsFragment = gql(`
 
 // Queries
 
-export const channelBadgesQuery = gql(
-	`query GetChannelBadges($id: ID!) {
-		user(id: $id) {
-			broadcastBadges {
-				...BadgeDetails
-			}
-		}
-	}`,
-	[badgeDetailsFragment],
-);
-
-export const cheermoteQuery = gql(
-	`query GetCheermotes($id: ID!) {
-		user(id: $id) {
-			cheer {
-				emotes(type: [FIRST_PARTY, THIRD_PARTY, CUSTOM]) {
-					...CheermoteDetails
-				}
-			}
-		}
-	}`,
-	[cheermoteDetailsFragment],
-);
-
 export const clipQuery = gql(`
 	query GetClip($slug: ID!) {
 		clip(slug: $slug) {
@@ -143,15 +102,6 @@ export const foundersQuery = gql(`
 	}
 `);
 
-export const globalBadgesQuery = gql(
-	`query GetGlobalBadges {
-		badges {
-			...BadgeDetails
-		}
-	}`,
-	[badgeDetailsFragment],
-);
-
 export const guestsQuery = gql(
 	`query GetGuests($id: ID!) {
 		channel(id: $id) {
@@ -267,8 +217,6 @@ export const vipsQuery = gql(`
 // Types
 
 export type Badge = FragmentOf<typeof badgeDetailsFragment>;
-export type Cheermote = FragmentOf<typeof cheermoteDetailsFragment>;
-export type CheermoteTier = Cheermote["tiers"][number];
 export type Stream = FragmentOf<typeof streamDetailsFragment>;
 export type User = FragmentOf<typeof userDetailsFragment>;
 
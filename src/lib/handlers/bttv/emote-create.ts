@@ -0,0 +1,16 @@
+import { app } from "$lib/app.svelte";
+import { transformBttvEmote } from "$lib/emotes";
+import { defineHandler } from "../helper";
+
+export default defineHandler({
+	name: "bttv.emote_create",
+	handle(data) {
+		const channel = app.channels.get(data.channel_id);
+		if (!channel) return;
+
+		const emote = transformBttvEmote(data.emote);
+
+		channel.emotes.set(emote.name, emote);
+		channel.chat.addSystemMessage(`BetterTTV emote ${emote.name} was added.`);
+	},
+});
@@ -0,0 +1,19 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+export default defineHandler({
+	name: "bttv.emote_delete",
+	handle(data) {
+		const channel = app.channels.get(data.channel_id);
+		if (!channel) return;
+
+		const emote = channel.emotes
+			.values()
+			.find((e) => e.provider === "BetterTTV" && e.id === data.emote_id);
+
+		if (!emote) return;
+
+		channel.emotes.delete(emote.name);
+		channel.chat.addSystemMessage(`BetterTTV emote ${emote.name} was removed.`);
+	},
+});
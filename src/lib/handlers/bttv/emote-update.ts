@@ -0,0 +1,23 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+export default defineHandler({
+	name: "bttv.emote_update",
+	handle(data) {
+		const channel = app.channels.get(data.channel_id);
+		if (!channel) return;
+
+		const emote = channel.emotes
+			.values()
+			.find((e) => e.provider === "BetterTTV" && e.id === data.emote.id);
+
+		if (!emote || emote.name === data.emote.code) return;
+
+		const oldName = emote.name;
+		emote.name = data.emote.code;
+
+		channel.emotes.delete(oldName);
+		channel.emotes.set(emote.name, emote);
+		channel.chat.addSystemMessage(`BetterTTV emote ${oldName} was renamed to ${emote.name}.`);
+	},
+});
@@ -0,0 +1,16 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+export default defineHandler({
+	name: "channel.ad_break.begin",
+	handle(data) {
+		const channel = app.channels.get(data.broadcaster_user_id);
+		if (!channel) return;
+
+		const kind = data.is_automatic ? "An automatic" : "A";
+
+		channel.chat.addSystemMessage(
+			`${kind} ${data.duration_seconds} second ad break has started.`,
+		);
+	},
+});
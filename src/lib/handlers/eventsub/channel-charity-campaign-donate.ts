@@ -0,0 +1,17 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+export default defineHandler({
+	name: "channel.charity_campaign.donate",
+	handle(data) {
+		const channel = app.channels.get(data.broadcaster_user_id);
+		if (!channel) return;
+
+		const { value, decimal_places, currency } = data.amount;
+		const amount = new Intl.NumberFormat(undefined, { style: "currency", currency }).format(
+			value / 10 ** decimal_places,
+		);
+
+		channel.chat.addSystemMessage(`${data.user_name} donated ${amount} to ${data.charity_name}.`);
+	},
+});
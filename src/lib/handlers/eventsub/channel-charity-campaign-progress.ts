@@ -0,0 +1,30 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+// Progress is sent for every donation, which are already announced, so only
+// reaching the target is.
+const reached = new Set<string>();
+
+export default defineHandler({
+	name: "channel.charity_campaign.progress",
+	handle(data) {
+		const channel = app.channels.get(data.broadcaster_user_id);
+		if (!channel || reached.has(data.id)) return;
+
+		const { current_amount: current, target_amount: target } = data;
+		const value = (amount: typeof current) => amount.value / 10 ** amount.decimal_places;
+
+		if (value(target) <= 0 || value(current) < value(target)) return;
+
+		reached.add(data.id);
+
+		const amount = new Intl.NumberFormat(undefined, {
+			style: "currency",
+			currency: target.currency,
+		}).format(value(target));
+
+		channel.chat.addSystemMessage(
+			`The charity campaign for ${data.charity_name} reached its ${amount} goal!`,
+		);
+	},
+});
@@ -0,0 +1,12 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+export default defineHandler({
+	name: "channel.hype_train.begin",
+	handle(data) {
+		const channel = app.channels.get(data.broadcaster_user_id);
+		if (!channel) return;
+
+		channel.chat.addSystemMessage("A Hype Train has started!");
+	},
+});
@@ -0,0 +1,20 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+// Progress is sent for every contribution, so only level ups are announced.
+const levels = new Map<string, { id: string; level: number }>();
+
+export default defineHandler({
+	name: "channel.hype_train.progress",
+	handle(data) {
+		const previous = levels.get(data.broadcaster_user_id);
+		const level = previous?.id === data.id ? previous.level : 1;
+
+		levels.set(data.broadcaster_user_id, { id: data.id, level: Math.max(level, data.level) });
+
+		const channel = app.channels.get(data.broadcaster_user_id);
+		if (!channel || data.level <= level) return;
+
+		channel.chat.addSystemMessage(`The Hype Train reached level ${data.level}!`);
+	},
+});
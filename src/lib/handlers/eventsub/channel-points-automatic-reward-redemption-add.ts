@@ -0,0 +1,29 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+import type { AutomaticRewardType } from "$lib/twitch/eventsub";
+
+const rewards: Record<AutomaticRewardType, string> = {
+	single_message_bypass_sub_mode: "Send a Message in Sub-Only Mode",
+	send_highlighted_message: "Highlight My Message",
+	random_sub_emote_unlock: "Unlock a Random Sub Emote",
+	chosen_sub_emote_unlock: "Choose an Emote to Unlock",
+	chosen_modified_sub_emote_unlock: "Modify a Single Emote",
+	message_effect: "Message Effects",
+	gigantify_an_emote: "Gigantify an Emote",
+	celebration: "On-Screen Celebration",
+};
+
+export default defineHandler({
+	name: "channel.channel_points_automatic_reward_redemption.add",
+	handle(data) {
+		const channel = app.channels.get(data.broadcaster_user_id);
+		if (!channel) return;
+
+		const title = rewards[data.reward.type] ?? data.reward.type;
+		const emote = data.reward.emote ? `: ${data.reward.emote.name}` : "";
+
+		channel.chat.addSystemMessage(
+			`${data.user_name} redeemed ${title} (${data.reward.channel_points})${emote}`,
+		);
+	},
+});
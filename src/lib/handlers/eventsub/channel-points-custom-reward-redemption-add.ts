@@ -0,0 +1,16 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+export default defineHandler({
+	name: "channel.channel_points_custom_reward_redemption.add",
+	handle(data) {
+		const channel = app.channels.get(data.broadcaster_user_id);
+		if (!channel) return;
+
+		const input = data.user_input ? `: ${data.user_input}` : "";
+
+		channel.chat.addSystemMessage(
+			`${data.user_name} redeemed ${data.reward.title} (${data.reward.cost})${input}`,
+		);
+	},
+});
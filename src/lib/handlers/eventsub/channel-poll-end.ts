@@ -0,0 +1,20 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+export default defineHandler({
+	name: "channel.poll.end",
+	handle(data) {
+		const channel = app.channels.get(data.broadcaster_user_id);
+		if (!channel || data.status === "archived") return;
+
+		const winner = data.choices.reduce((top, choice) =>
+			(choice.votes ?? 0) > (top.votes ?? 0) ? choice : top,
+		);
+
+		channel.chat.addSystemMessage(
+			data.status === "terminated"
+				? `The poll "${data.title}" was ended early.`
+				: `The poll "${data.title}" has ended. Winner: ${winner.title}`,
+		);
+	},
+});
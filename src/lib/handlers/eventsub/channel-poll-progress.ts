@@ -0,0 +1,22 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+// Progress is sent for every vote, so only changes in the lead are announced.
+const leaders = new Map<string, { id: string; choice: string | null }>();
+
+export default defineHandler({
+	name: "channel.poll.progress",
+	handle(data) {
+		const [first, second] = [...data.choices].sort((a, b) => (b.votes ?? 0) - (a.votes ?? 0));
+		const leader = first && (first.votes ?? 0) > (second?.votes ?? 0) ? first : null;
+
+		const previous = leaders.get(data.broadcaster_user_id);
+		leaders.set(data.broadcaster_user_id, { id: data.id, choice: leader?.id ?? null });
+
+		const channel = app.channels.get(data.broadcaster_user_id);
+		if (!channel || !leader) return;
+		if (previous?.id === data.id && previous.choice === leader.id) return;
+
+		channel.chat.addSystemMessage(`${leader.title} is now leading the poll "${data.title}".`);
+	},
+});
@@ -0,0 +1,18 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+export default defineHandler({
+	name: "channel.prediction.end",
+	handle(data) {
+		const channel = app.channels.get(data.broadcaster_user_id);
+		if (!channel) return;
+
+		const winner = data.outcomes.find((outcome) => outcome.id === data.winning_outcome_id);
+
+		channel.chat.addSystemMessage(
+			winner
+				? `The prediction "${data.title}" has ended. Result: ${winner.title}`
+				: `The prediction "${data.title}" was canceled.`,
+		);
+	},
+});
@@ -0,0 +1,28 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+// Progress is sent for every prediction made, so only changes in the lead are
+// announced.
+const leaders = new Map<string, { id: string; outcome: string | null }>();
+
+export default defineHandler({
+	name: "channel.prediction.progress",
+	handle(data) {
+		const [first, second] = [...data.outcomes].sort(
+			(a, b) => (b.channel_points ?? 0) - (a.channel_points ?? 0),
+		);
+		const leader =
+			first && (first.channel_points ?? 0) > (second?.channel_points ?? 0) ? first : null;
+
+		const previous = leaders.get(data.broadcaster_user_id);
+		leaders.set(data.broadcaster_user_id, { id: data.id, outcome: leader?.id ?? null });
+
+		const channel = app.channels.get(data.broadcaster_user_id);
+		if (!channel || !leader) return;
+		if (previous?.id === data.id && previous.outcome === leader.id) return;
+
+		channel.chat.addSystemMessage(
+			`${leader.title} is now leading the prediction "${data.title}".`,
+		);
+	},
+});
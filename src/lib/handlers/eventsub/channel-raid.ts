@@ -0,0 +1,28 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+// A raid between two joined channels is delivered once for each of their
+// subscriptions.
+const recent = new Set<string>();
+
+export default defineHandler({
+	name: "channel.raid",
+	handle(data) {
+		const key = `${data.from_broadcaster_user_id}:${data.to_broadcaster_user_id}`;
+		if (recent.has(key)) return;
+
+		recent.add(key);
+		setTimeout(() => recent.delete(key), 60_000);
+
+		const incoming = app.channels.get(data.to_broadcaster_user_id);
+		const outgoing = app.channels.get(data.from_broadcaster_user_id);
+
+		incoming?.chat.addSystemMessage(
+			`${data.from_broadcaster_user_name} is raiding with a party of ${data.viewers}.`,
+		);
+
+		outgoing?.chat.addSystemMessage(
+			`Raiding ${data.to_broadcaster_user_name} with a party of ${data.viewers}.`,
+		);
+	},
+});
@@ -0,0 +1,14 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+export default defineHandler({
+	name: "channel.shoutout.create",
+	handle(data) {
+		const channel = app.channels.get(data.broadcaster_user_id);
+		if (!channel) return;
+
+		channel.chat.addSystemMessage(
+			`${data.moderator_user_name} gave a shoutout to ${data.to_broadcaster_user_name}.`,
+		);
+	},
+});
@@ -0,0 +1,14 @@
+import { app } from "$lib/app.svelte";
+import { defineHandler } from "../helper";
+
+export default defineHandler({
+	name: "channel.shoutout.receive",
+	handle(data) {
+		const channel = app.channels.get(data.broadcaster_user_id);
+		if (!channel) return;
+
+		channel.chat.addSystemMessage(
+			`${data.from_broadcaster_user_name} gave the channel a shoutout to ${data.viewer_count} viewers.`,
+		);
+	},
+});
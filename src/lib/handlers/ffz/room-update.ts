@@ -0,0 +1,14 @@
+import { app } from "$lib/app.svelte";
+import { transformFfzEmote } from "$lib/emotes";
+import { defineHandler } from "../helper";
+
+export default defineHandler({
+	name: "ffz.room_update",
+	handle(data) {
+		const channel = app.channels.get(data.channel_id);
+		if (!channel) return;
+
+		channel.emotes.clear("FrankerFaceZ");
+		channel.emotes.addAll(data.emotes.map(transformFfzEmote));
+	},
+});
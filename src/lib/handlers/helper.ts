@@ -1,8 +1,15 @@
+import type { BttvEventMap } from "$lib/bttv";
+import type { FfzEventMap } from "$lib/ffz";
 import type { SevenTvEventMap } from "$lib/seventv";
 import type { SubscriptionEventMap } from "$lib/twitch/eventsub";
 import type { IrcMessageMap } from "$lib/twitch/irc";
 
-type HandlerKey = keyof IrcMessageMap | keyof SubscriptionEventMap | keyof SevenTvEventMap;
+type HandlerKey =
+	| keyof IrcMessageMap
+	| keyof SubscriptionEventMap
+	| keyof SevenTvEventMap
+	| keyof BttvEventMap
+	| keyof FfzEventMap;
 
 type HandlerData<K> = K extends keyof IrcMessageMap
 	? IrcMessageMap[K]
@@ -10,7 +17,11 @@ type HandlerData<K> = K extends keyof IrcMessageMap
 		? SubscriptionEventMap[K]
 		: K extends keyof SevenTvEventMap
 			? SevenTvEventMap[K]
-			: never;
+			: K extends keyof BttvEventMap
+				? BttvEventMap[K]
+				: K extends keyof FfzEventMap
+					? FfzEventMap[K]
+					: never;
 
 export interface Handler<K> {
 	name: K;
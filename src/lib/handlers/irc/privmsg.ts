@@ -15,13 +15,16 @@ export default defineHandler({
 		message.author.username = data.sender.login;
 		message.author.displayName = data.sender.name;
 
-		message.viewer ??= await channel.viewers.fetch(data.sender.id);
-		message.viewer.broadcaster = badges.some((b) => b.name.startsWith("broadcaster"));
-		message.viewer.moderator = message.viewer.broadcaster || data.is_mod;
-		message.viewer.subscriber = data.is_subscriber;
-		message.viewer.vip = badges.some((b) => b.name.startsWith("vip"));
-		message.viewer.returning = data.is_returning_chatter;
-		message.viewer.new = data.is_first_msg;
+		// Messages imported from text logs don't have a user id to look up
+		if (data.sender.id) {
+			message.viewer ??= await channel.viewers.fetch(data.sender.id);
+			message.viewer.broadcaster = badges.some((b) => b.name.startsWith("broadcaster"));
+			message.viewer.moderator = message.viewer.broadcaster || data.is_mod;
+			message.viewer.subscriber = data.is_subscriber;
+			message.viewer.vip = badges.some((b) => b.name.startsWith("vip"));
+			message.viewer.returning = data.is_returning_chatter;
+			message.viewer.new = data.is_first_msg;
+		}
 
 		if (data.source) {
 			await message.setSource(data.source);
@@ -1,4 +1,5 @@
 import { app } from "$lib/app.svelte";
+import { cached } from "$lib/images";
 import { Badge } from "$lib/models/badge";
 import { defineHandler } from "../helper";
 
@@ -45,7 +46,7 @@ export default defineHandler({
 			}
 
 			case "URL": {
-				args.push(cosmetic.data.image_url);
+				args.push(cached(cosmetic.data.image_url));
 				break;
 			}
 		}
@@ -1,4 +1,3 @@
-import * as cache from "tauri-plugin-cache-api";
 import { app } from "$lib/app.svelte";
 import type { Emote } from "$lib/emotes";
 import { SystemMessage } from "$lib/models/message/system-message";
@@ -99,9 +98,6 @@ export default defineHandler({
 			}
 
 			channel.chat.addMessage(message);
-
-			await cache.remove(`emotes:${channel.id}`);
-			await cache.set(`emotes:${channel.id}`, channel.emotes.values().toArray());
 		}
 		// Personal set was updated
 		else {
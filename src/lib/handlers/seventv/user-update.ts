@@ -38,8 +38,12 @@ export default defineHandler({
 				name: child.value.name,
 				actor,
 			};
+		}
+
+		// Reloads the channel emotes so the registry tracks the new set
+		await channel.emotes.fetch(true);
 
-			await channel.emotes.fetch7tv();
+		if (channel.emoteSetId) {
 			await invoke("resub_emote_set", {
 				channel: channel.user.username,
 				setId: channel.emoteSetId,
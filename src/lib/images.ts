@@ -0,0 +1,42 @@
+import { convertFileSrc } from "@tauri-apps/api/core";
+
+/**
+ * Hosts the backend caches images from. Kept in sync with `ALLOWED_HOSTS` in
+ * `src-tauri/src/images.rs`.
+ */
+const CACHED_HOSTS = new Set([
+	"static-cdn.jtvnw.net",
+	"d3aqoihi2n8ty8.cloudfront.net",
+	"cdn.7tv.app",
+	"cdn.betterttv.net",
+	"cdn.frankerfacez.com",
+]);
+
+/**
+ * Rewrites an image url to be served from the local image cache. Urls from
+ * other hosts are returned unchanged.
+ */
+export function cached(url: string) {
+	if (!URL.canParse(url)) return url;
+
+	const { protocol, hostname } = new URL(url);
+
+	if (protocol !== "https:" || !CACHED_HOSTS.has(hostname)) {
+		return url;
+	}
+
+	return convertFileSrc(url, "hyperion-img");
+}
+
+/**
+ * Rewrites every candidate of a srcset to be served from the local image
+ * cache.
+ */
+export function cachedSrcset(srcset: string[]) {
+	return srcset
+		.map((candidate) => {
+			const [url, density] = candidate.split(" ");
+			return `${cached(url)} ${density}`;
+		})
+		.join(", ");
+}
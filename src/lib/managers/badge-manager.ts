@@ -1,11 +1,10 @@
 import { betterFetch as fetch } from "@better-fetch/fetch";
+import { invoke } from "@tauri-apps/api/core";
 import { SvelteMap } from "svelte/reactivity";
 import * as cache from "tauri-plugin-cache-api";
 import { ApiError } from "$lib/errors/api-error";
-import { sendTwitch } from "$lib/graphql";
-import { globalBadgesQuery } from "$lib/graphql/twitch";
 import { Badge } from "$lib/models/badge";
-import type { BttvBadge, FfzBadge } from "$lib/models/badge";
+import type { BadgeData, BttvBadge, FfzBadge } from "$lib/models/badge";
 import { getOrInsert, getOrInsertComputed } from "$lib/util";
 
 interface BttvUser {
@@ -29,19 +28,11 @@ export class BadgeManager extends SvelteMap<string, Badge> {
 	}
 
 	/**
-	 * Retrieves the list of global badges and caches them for later use.
+	 * Retrieves the list of global badges.
 	 */
 	public async fetchTwitch(force = false) {
-		let badges = await cache.get<Badge[]>("global_badges");
-
-		if (!badges || force) {
-			const { badges: data } = await sendTwitch(globalBadgesQuery);
-			badges = data?.flatMap((b) => (b ? [Badge.fromGql(b)] : [])) ?? [];
-
-			// Twitch adds new badges fairly often, so ttl is lower than the
-			// global emote cache
-			await cache.set("global_badges", badges, { ttl: 3 * 24 * 60 * 60 });
-		}
+		const data = await invoke<BadgeData[]>("fetch_global_badges", { force });
+		const badges = data.map((badge) => new Badge(badge));
 
 		for (const badge of badges) {
 			this.set(badge.id, badge);
@@ -1,37 +1,18 @@
 import { SvelteMap } from "svelte/reactivity";
 import type { Emote, EmoteProvider } from "$lib/emotes";
-import { settings } from "$lib/settings";
 
 export abstract class BaseEmoteManager extends SvelteMap<string, Emote> {
-	public abstract fetchFfz(): Promise<Emote[]>;
-	public abstract fetchBttv(): Promise<Emote[]>;
-	public abstract fetch7tv(): Promise<Emote[]>;
-
 	/**
-	 * Retrieves the list of emotes from the providers enabled in the settings and
-	 * caches them for later use.
+	 * Retrieves the list of emotes from the emote registry in the backend,
+	 * which caches them for later use.
 	 */
-	public async fetch() {
-		const promises: Promise<Emote[]>[] = [];
-
-		if (settings.state["chat.emotes.ffz"]) {
-			promises.push(this.fetchFfz());
-		}
-
-		if (settings.state["chat.emotes.bttv"]) {
-			promises.push(this.fetchBttv());
-		}
-
-		if (settings.state["chat.emotes.seventv"]) {
-			promises.push(this.fetch7tv());
-		}
-
-		const emotes = await Promise.all(promises);
-		return emotes.flat();
-	}
+	public abstract fetch(force?: boolean): Promise<Emote[]>;
 
 	public addAll(emotes: Iterable<Emote>) {
 		for (const emote of emotes) {
+			// Twitch emotes are taken from the emote tags of messages instead
+			if (emote.provider === "Twitch") continue;
+
 			this.set(emote.name, emote);
 		}
 
@@ -1,11 +1,6 @@
-import { betterFetch as fetch } from "@better-fetch/fetch";
-import * as cache from "tauri-plugin-cache-api";
+import { invoke } from "@tauri-apps/api/core";
 import { app } from "$lib/app.svelte";
-import { transform7tvEmote, transformBttvEmote, transformFfzEmote } from "$lib/emotes";
-import type { BttvEmote, Emote, GlobalSet } from "$lib/emotes";
-import { ApiError } from "$lib/errors/api-error";
-import { send7tv } from "$lib/graphql";
-import { globalEmoteSetQuery } from "$lib/graphql/7tv";
+import type { Emote } from "$lib/emotes";
 import { BaseEmoteManager } from "./base-emote-manager";
 
 // Still want to find a better way to do this
@@ -39,87 +34,25 @@ const GLOBAL_SETS = {
 
 export class EmoteManager extends BaseEmoteManager {
 	public override async fetch(force = false) {
-		let emotes = await cache.get<Emote[]>("global_emotes");
+		const emotes = await invoke<Emote[]>("fetch_global_emotes", { force });
 
-		if (force || !emotes) {
-			if (force) this.clear();
+		if (force) this.clear();
 
-			emotes = await super.fetch();
-			await cache.set("global_emotes", emotes, { ttl: 7 * 24 * 60 * 60 });
-		} else {
-			this.#addGlobalSet(
-				GLOBAL_SETS.ffz,
-				emotes.filter((e) => e.provider === "FrankerFaceZ"),
-			);
-
-			this.#addGlobalSet(
-				GLOBAL_SETS.bttv,
-				emotes.filter((e) => e.provider === "BetterTTV"),
-			);
-
-			this.#addGlobalSet(
-				GLOBAL_SETS.seventv,
-				emotes.filter((e) => e.provider === "7TV"),
-			);
-
-			this.addAll(emotes);
-		}
-
-		return emotes;
-	}
-
-	/**
-	 * Retrieves the list of global FrankerFaceZ emotes.
-	 */
-	public override async fetchFfz() {
-		const { data, error } = await fetch<GlobalSet>(
-			"https://api.frankerfacez.com/v1/set/global",
+		this.#addGlobalSet(
+			GLOBAL_SETS.ffz,
+			emotes.filter((e) => e.provider === "FrankerFaceZ"),
 		);
 
-		if (error) {
-			throw new ApiError(error.status, error.statusText);
-		}
-
-		// 3 is the global set id
-		const emotes = data.sets[3].emoticons.map(transformFfzEmote);
-
-		this.#addGlobalSet(GLOBAL_SETS.ffz, emotes);
-		this.addAll(emotes);
-
-		return emotes;
-	}
-
-	/**
-	 * Retrieves the list of global BetterTTV emotes.
-	 */
-	public override async fetchBttv() {
-		const { data, error } = await fetch<BttvEmote[]>(
-			"https://api.betterttv.net/3/cached/emotes/global",
+		this.#addGlobalSet(
+			GLOBAL_SETS.bttv,
+			emotes.filter((e) => e.provider === "BetterTTV"),
 		);
 
-		if (error) {
-			throw new ApiError(error.status, error.statusText);
-		}
-
-		const emotes = data.map(transformBttvEmote);
-
-		this.#addGlobalSet(GLOBAL_SETS.bttv, emotes);
-		this.addAll(emotes);
-
-		return emotes;
-	}
-
-	/**
-	 * Retrieves the list of global 7TV emotes.
-	 */
-	public override async fetch7tv() {
-		const { emoteSets } = await send7tv(globalEmoteSetQuery);
-
-		const emotes = emoteSets.global!.emotes.items.map((item) =>
-			transform7tvEmote(item.emote, item.alias),
+		this.#addGlobalSet(
+			GLOBAL_SETS.seventv,
+			emotes.filter((e) => e.provider === "7TV"),
 		);
 
-		this.#addGlobalSet(GLOBAL_SETS.seventv, emotes);
 		this.addAll(emotes);
 
 		return emotes;
@@ -14,7 +14,7 @@ export interface FfzBadge {
 	urls: Record<string, string>;
 }
 
-interface BadgeData {
+export interface BadgeData {
 	setId: string;
 	version: string;
 	title: string;
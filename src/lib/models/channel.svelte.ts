@@ -1,8 +1,8 @@
 import { invoke } from "@tauri-apps/api/core";
-import * as cache from "tauri-plugin-cache-api";
-import { channelBadgesQuery, cheermoteQuery, streamQuery } from "$lib/graphql/twitch";
-import type { Cheermote } from "$lib/graphql/twitch";
+import type { Cheermote } from "$lib/emotes";
+import { streamQuery } from "$lib/graphql/twitch";
 import { ChannelEmoteManager } from "$lib/managers/channel-emote-manager";
+import { handlers } from "$lib/handlers";
 import { fetch7tvId } from "$lib/seventv";
 import { storage } from "$lib/stores";
 import { app } from "../app.svelte";
@@ -10,7 +10,10 @@ import { ViewerManager } from "../managers/viewer-manager";
 import { settings } from "../settings";
 import type { StreamMarker } from "../twitch/api";
 import type { TwitchClient } from "../twitch/client";
+import type { SubscriptionResult } from "../twitch/eventsub";
+import type { IrcMessage } from "../twitch/irc";
 import { Badge } from "./badge";
+import type { BadgeData } from "./badge";
 import { Chat } from "./chat.svelte";
 import { Stream } from "./stream.svelte";
 import { Viewer } from "./viewer.svelte";
@@ -51,6 +54,11 @@ export class Channel {
 	 */
 	public stream = $state<Stream | null>(null);
 
+	/**
+	 * The outcome of subscribing to each EventSub event in the channel.
+	 */
+	public subscriptions = $state<SubscriptionResult[]>([]);
+
 	/**
 	 * Whether the channel is joined.
 	 */
@@ -119,19 +127,35 @@ export class Channel {
 		this.seventvId = seventvId;
 		await this.stream?.fetchGuests();
 
+		// Restore the backlog kept by the backend if the webview was reloaded.
+		// This needs to be fetched before joining so live messages aren't
+		// mistaken for a backlog.
+		const buffered = await invoke<IrcMessage[]>("get_channel_buffer", {
+			channel: this.user.username,
+			beforeId: null,
+		});
+
 		// Don't resolve to avoid blocking the UI
-		void invoke("join", {
+		invoke("join", {
 			id: this.id,
 			stvId: this.seventvId,
 			setId: this.emoteSetId,
 			login: this.user.username,
 			isMod: app.user?.moderating.has(this.id),
+			chatSource: this.#chatSource(),
+		}).catch((error) => {
+			this.chat.addSystemMessage(`Failed to join the channel: ${error}`);
 		});
 
-		if (settings.state["chat.messages.history.enabled"]) {
+		if (buffered.length) {
+			for (const message of buffered) {
+				await handlers.get(message.type)?.handle(message);
+			}
+		} else if (settings.state["chat.messages.history.enabled"]) {
 			await invoke("fetch_recent_messages", {
 				channel: this.user.username,
 				limit: settings.state["chat.messages.history.limit"],
+				customUrl: settings.state["chat.messages.history.customUrl"] || null,
 			});
 		}
 	}
@@ -152,7 +176,11 @@ export class Channel {
 	}
 
 	public async rejoin() {
-		await invoke("rejoin", { channel: this.user.username });
+		await invoke("rejoin", {
+			id: this.id,
+			channel: this.user.username,
+			chatSource: this.#chatSource(),
+		});
 
 		if (app.user) {
 			app.user.banned.delete(this.id);
@@ -173,18 +201,12 @@ export class Channel {
 	public async fetchBadges(force = false) {
 		if (!force && this.badges.size) return;
 
-		let badges = await cache.get<Badge[]>(`badges:${this.id}`);
+		const badges = await invoke<BadgeData[]>("fetch_channel_badges", { id: this.id, force });
 
-		if (force || !badges) {
-			if (force) this.badges.clear();
+		if (force) this.badges.clear();
 
-			const { user } = await this.client.send(channelBadgesQuery, { id: this.id });
-			badges = user?.broadcastBadges?.flatMap((b) => (b ? [Badge.fromGql(b)] : [])) ?? [];
-
-			await cache.set(`badges:${this.id}`, badges);
-		}
-
-		for (const badge of badges) {
+		for (const data of badges) {
+			const badge = new Badge(data);
 			this.badges.set(badge.id, badge);
 		}
 
@@ -196,18 +218,11 @@ export class Channel {
 	 * use.
 	 */
 	public async fetchCheermotes(force = false) {
-		let cheermotes = await cache.get<Cheermote[]>(`cheermotes:${this.id}`);
-
-		if (force || !cheermotes) {
-			if (force) this.cheermotes.length = 0;
-
-			const { user } = await this.client.send(cheermoteQuery, { id: this.id });
-
-			cheermotes = user?.cheer?.emotes.filter((e) => e != null) ?? [];
-			await cache.set(`cheermotes:${this.id}`, cheermotes);
-		}
+		const cheermotes = await invoke<Cheermote[]>("fetch_cheermotes", { id: this.id, force });
 
+		this.cheermotes.length = 0;
 		this.cheermotes.push(...cheermotes);
+
 		return this.cheermotes;
 	}
 
@@ -269,4 +284,12 @@ export class Channel {
 			},
 		});
 	}
+
+	#chatSource() {
+		const channels = settings.state["chat.eventsubChannels"]
+			.split(",")
+			.map((login) => login.trim().toLowerCase());
+
+		return channels.includes(this.user.username) ? "eventsub" : "irc";
+	}
 }
@@ -22,7 +22,7 @@ export class CurrentUser extends User {
 	/**
 	 * The ids of the channels the current user moderates for.
 	 */
-	public readonly moderating = new Set<string>();
+	public readonly moderating = new SvelteSet<string>();
 
 	/**
 	 * The whisper threads the current user is involved in.
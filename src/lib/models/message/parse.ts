@@ -1,7 +1,6 @@
 import { parse as parseTld } from "tldts";
 import { app } from "$lib/app.svelte";
-import type { Emote } from "$lib/emotes";
-import type { CheermoteTier } from "$lib/graphql/twitch";
+import type { CheermoteTier, Emote } from "$lib/emotes";
 import type { Range } from "$lib/twitch/irc";
 import type { User } from "../user.svelte";
 import type { UserMessage } from "./user-message";
@@ -92,8 +91,8 @@ export function parse(message: UserMessage): Node[] {
 		const ircEmote = ircEmotes.find((e) => e.code === part);
 		const emote =
 			message.author.emotes.get(part) ??
-			app.emotes.get(part) ??
-			message.channel.emotes.get(part);
+			message.channel.emotes.get(part) ??
+			app.emotes.get(part);
 
 		if (url && tld?.domain && tld.isIcann) {
 			nodes.push({
@@ -169,18 +168,18 @@ export function parse(message: UserMessage): Node[] {
 			const foundIdx = ircEmotes.indexOf(ircEmote);
 			ircEmotes.splice(foundIdx, 1);
 		} else if (emote) {
-			if (emote.zeroWidth) {
-				let prevNode = nodes.at(-1);
-				let index = -1;
+			let prevNode = nodes.at(-1);
+			let index = -1;
 
-				while (prevNode?.type === "text" && !prevNode.data.trim()) {
-					index--;
-					prevNode = nodes.at(index);
-				}
+			while (prevNode?.type === "text" && !prevNode.data.trim()) {
+				index--;
+				prevNode = nodes.at(index);
+			}
 
-				if (prevNode?.type === "emote") {
-					prevNode.data.layers.push(emote);
-				}
+			// Zero-width emotes are shown on their own if there's nothing to
+			// layer them on
+			if (emote.zeroWidth && prevNode?.type === "emote") {
+				prevNode.data.layers.push(emote);
 			} else {
 				nodes.push({
 					...base,
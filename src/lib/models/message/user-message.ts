@@ -31,8 +31,9 @@ function createPartialUser(channel: Channel, sender: BasicUser, color: string) {
 		roles: null,
 	});
 
-	const viewer = new Viewer(channel, user);
-	channel.viewers.set(user.id, viewer);
+	if (user.id) {
+		channel.viewers.set(user.id, new Viewer(channel, user));
+	}
 
 	return user;
 }
@@ -112,7 +113,7 @@ export class UserMessage extends TextualMessage {
 	) {
 		super(channel, data);
 
-		const viewer = channel.viewers.get(data.sender.id);
+		const viewer = data.sender.id ? channel.viewers.get(data.sender.id) : undefined;
 
 		this.id = data.message_id;
 
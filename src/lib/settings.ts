@@ -35,6 +35,7 @@ export interface Settings {
 	"chat.hideScrollbar": boolean;
 	"chat.newSeparator": boolean;
 	"chat.embeds": boolean;
+	"chat.eventsubChannels": string;
 	"chat.badges.ffz": boolean;
 	"chat.badges.bttv": boolean;
 	"chat.badges.seventv": boolean;
@@ -50,6 +51,7 @@ export interface Settings {
 	"chat.messages.duplicateBypass": boolean;
 	"chat.messages.history.enabled": boolean;
 	"chat.messages.history.limit": number;
+	"chat.messages.history.customUrl": string;
 	"chat.messages.history.separator": boolean;
 	"chat.messages.timestamps.show": boolean;
 	"chat.messages.timestamps.format": "auto" | "12" | "24" | "custom";
@@ -61,6 +63,11 @@ export interface Settings {
 
 	"advanced.singleConnection": boolean;
 	"advanced.logs.level": "error" | "warn" | "info" | "debug" | "trace";
+	"advanced.ipc.encoding": "json" | "messagepack";
+	"advanced.ipc.raw": boolean;
+	"advanced.eventsub.mock": boolean;
+	"advanced.eventsub.websocketUrl": string;
+	"advanced.eventsub.subscriptionsUrl": string;
 }
 
 export const defaultHighlightTypes: Record<HighlightType, HighlightConfig> = {
@@ -84,6 +91,7 @@ export const defaults: Settings = {
 	"chat.hideScrollbar": false,
 	"chat.newSeparator": false,
 	"chat.embeds": true,
+	"chat.eventsubChannels": "",
 	"chat.badges.ffz": true,
 	"chat.badges.bttv": true,
 	"chat.badges.seventv": true,
@@ -99,6 +107,7 @@ export const defaults: Settings = {
 	"chat.messages.duplicateBypass": true,
 	"chat.messages.history.enabled": true,
 	"chat.messages.history.limit": 250,
+	"chat.messages.history.customUrl": "",
 	"chat.messages.history.separator": true,
 	"chat.messages.timestamps.show": true,
 	"chat.messages.timestamps.format": "auto",
@@ -108,6 +117,11 @@ export const defaults: Settings = {
 	"highlights.keywords": [],
 	"advanced.singleConnection": false,
 	"advanced.logs.level": "info",
+	"advanced.ipc.encoding": "json",
+	"advanced.ipc.raw": false,
+	"advanced.eventsub.mock": false,
+	"advanced.eventsub.websocketUrl": "",
+	"advanced.eventsub.subscriptionsUrl": "",
 };
 
 export const settings = new RuneStore<Settings & Record<string, any>>("settings", defaults, {
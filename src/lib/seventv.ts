@@ -135,9 +135,13 @@ interface ChangeMap<T, N = false> {
 	updated?: ChangeField<"updated", T, N>[];
 }
 
+/**
+ * A dispatch from the 7TV Event API. Bodies carrying a created or deleted
+ * object are unwrapped to the object itself by the backend.
+ */
 export interface DispatchPayload {
 	type: string;
-	body: { object: unknown } | ChangeMap<unknown>;
+	body: unknown;
 }
 
 export interface SevenTvEventMap {
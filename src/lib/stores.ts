@@ -6,6 +6,7 @@ import type { SplitNode } from "./split-layout";
 interface AccountUser {
 	id: string;
 	token: string;
+	refreshToken?: string;
 	data: User;
 }
 
@@ -236,6 +236,192 @@ export type ChannelModerate =
 	| WarnAction
 	| SharedChatAction;
 
+export interface ChannelAdBreakBegin extends WithBroadcaster, Prefix<WithBasicUser, "requester"> {
+	duration_seconds: number;
+	started_at: string;
+	is_automatic: boolean;
+}
+
+export interface CharityAmount {
+	value: number;
+	decimal_places: number;
+	currency: string;
+}
+
+export interface ChannelCharityCampaignDonate extends WithBroadcaster, WithBasicUser {
+	id: string;
+	campaign_id: string;
+	charity_name: string;
+	amount: CharityAmount;
+}
+
+export interface ChannelCharityCampaign extends WithBroadcaster {
+	id: string;
+	charity_name: string;
+	current_amount: CharityAmount;
+	target_amount: CharityAmount;
+}
+
+export interface ChannelCharityCampaignStart extends ChannelCharityCampaign {
+	started_at: string;
+}
+
+export type ChannelCharityCampaignProgress = ChannelCharityCampaign;
+
+export interface ChannelCharityCampaignStop extends ChannelCharityCampaign {
+	stopped_at: string;
+}
+
+export interface ChannelFollow extends WithBroadcaster, WithBasicUser {
+	followed_at: string;
+}
+
+export interface HypeTrainContribution extends WithBasicUser {
+	type: "bits" | "subscription" | "other";
+	total: number;
+}
+
+export interface ChannelHypeTrain extends WithBroadcaster {
+	id: string;
+	total: number;
+	level: number;
+	top_contributions: HypeTrainContribution[];
+	type: "regular" | "golden_kappa" | "shared";
+	started_at: string;
+}
+
+export interface ChannelHypeTrainBegin extends ChannelHypeTrain {
+	progress: number;
+	goal: number;
+	all_time_high_level: number;
+	expires_at: string;
+}
+
+export interface ChannelHypeTrainProgress extends ChannelHypeTrain {
+	progress: number;
+	goal: number;
+	expires_at: string;
+}
+
+export interface ChannelHypeTrainEnd extends ChannelHypeTrain {
+	ended_at: string;
+	cooldown_ends_at: string;
+}
+
+export type AutomaticRewardType =
+	| "single_message_bypass_sub_mode"
+	| "send_highlighted_message"
+	| "random_sub_emote_unlock"
+	| "chosen_sub_emote_unlock"
+	| "chosen_modified_sub_emote_unlock"
+	| "message_effect"
+	| "gigantify_an_emote"
+	| "celebration";
+
+export interface AutomaticReward {
+	type: AutomaticRewardType;
+	channel_points: number;
+	emote: { id: string; name: string } | null;
+}
+
+export interface ChannelPointsAutomaticRewardRedemptionAdd extends WithBroadcaster, WithBasicUser {
+	id: string;
+	reward: AutomaticReward;
+	message: Omit<StructuredMessage, "message_id">;
+	redeemed_at: string;
+}
+
+export interface ChannelPointsReward {
+	id: string;
+	title: string;
+	cost: number;
+	prompt: string;
+}
+
+export interface ChannelPointsCustomRewardRedemptionAdd extends WithBroadcaster, WithBasicUser {
+	id: string;
+	user_input: string;
+	status: "unknown" | "unfulfilled" | "fulfilled" | "canceled";
+	reward: ChannelPointsReward;
+	redeemed_at: string;
+}
+
+export interface PollChoice {
+	id: string;
+	title: string;
+	bits_votes?: number;
+	channel_points_votes?: number;
+	votes?: number;
+}
+
+export interface ChannelPoll extends WithBroadcaster {
+	id: string;
+	title: string;
+	choices: PollChoice[];
+	started_at: string;
+}
+
+export interface ChannelPollBegin extends ChannelPoll {
+	ends_at: string;
+}
+
+export type ChannelPollProgress = ChannelPollBegin;
+
+export interface ChannelPollEnd extends ChannelPoll {
+	status: "completed" | "archived" | "terminated";
+	ended_at: string;
+}
+
+export interface PredictionOutcome {
+	id: string;
+	title: string;
+	color: "blue" | "pink";
+	users?: number;
+	channel_points?: number;
+}
+
+export interface ChannelPrediction extends WithBroadcaster {
+	id: string;
+	title: string;
+	outcomes: PredictionOutcome[];
+	started_at: string;
+}
+
+export interface ChannelPredictionBegin extends ChannelPrediction {
+	locks_at: string;
+}
+
+export type ChannelPredictionProgress = ChannelPredictionBegin;
+
+export interface ChannelPredictionLock extends ChannelPrediction {
+	locked_at: string;
+}
+
+export interface ChannelPredictionEnd extends ChannelPrediction {
+	winning_outcome_id: string | null;
+	status: "resolved" | "canceled";
+	ended_at: string;
+}
+
+export interface ChannelRaid
+	extends Prefix<WithBasicUser, "from_broadcaster">, Prefix<WithBasicUser, "to_broadcaster"> {
+	viewers: number;
+}
+
+export interface ChannelShoutoutCreate
+	extends WithBroadcaster, WithModerator, Prefix<WithBasicUser, "to_broadcaster"> {
+	viewer_count: number;
+	started_at: string;
+	cooldown_ends_at: string;
+	target_cooldown_ends_at: string;
+}
+
+export interface ChannelShoutoutReceive
+	extends WithBroadcaster, Prefix<WithBasicUser, "from_broadcaster"> {
+	viewer_count: number;
+	started_at: string;
+}
+
 export interface ChannelSubscriptionEnd extends WithBroadcaster, WithBasicUser {
 	tier: string;
 	is_gift: boolean;
@@ -325,9 +511,30 @@ export interface StreamOnline extends WithBroadcaster {
 export interface SubscriptionEventMap {
 	"automod.message.hold": AutoModMessageHold;
 	"automod.message.update": AutoModMessageUpdate;
+	"channel.ad_break.begin": ChannelAdBreakBegin;
+	"channel.channel_points_automatic_reward_redemption.add": ChannelPointsAutomaticRewardRedemptionAdd;
+	"channel.channel_points_custom_reward_redemption.add": ChannelPointsCustomRewardRedemptionAdd;
+	"channel.charity_campaign.donate": ChannelCharityCampaignDonate;
+	"channel.charity_campaign.progress": ChannelCharityCampaignProgress;
+	"channel.charity_campaign.start": ChannelCharityCampaignStart;
+	"channel.charity_campaign.stop": ChannelCharityCampaignStop;
 	"channel.chat.user_message_hold": ChannelChatUserMessageHold;
 	"channel.chat.user_message_update": ChannelChatUserMessageUpdate;
+	"channel.follow": ChannelFollow;
+	"channel.hype_train.begin": ChannelHypeTrainBegin;
+	"channel.hype_train.end": ChannelHypeTrainEnd;
+	"channel.hype_train.progress": ChannelHypeTrainProgress;
 	"channel.moderate": ChannelModerate;
+	"channel.poll.begin": ChannelPollBegin;
+	"channel.poll.end": ChannelPollEnd;
+	"channel.poll.progress": ChannelPollProgress;
+	"channel.prediction.begin": ChannelPredictionBegin;
+	"channel.prediction.end": ChannelPredictionEnd;
+	"channel.prediction.lock": ChannelPredictionLock;
+	"channel.prediction.progress": ChannelPredictionProgress;
+	"channel.raid": ChannelRaid;
+	"channel.shoutout.create": ChannelShoutoutCreate;
+	"channel.shoutout.receive": ChannelShoutoutReceive;
 	"channel.subscription.end": ChannelSubscriptionEnd;
 	"channel.suspicious_user.message": ChannelSuspiciousUserMessage;
 	"channel.suspicious_user.update": ChannelSuspiciousUserUpdate;
@@ -341,7 +548,65 @@ export interface SubscriptionEventMap {
 
 export type SubscriptionEvent = SubscriptionEventMap[keyof SubscriptionEventMap];
 
+export type ReconnectReason = "requested" | "keepalive_timeout" | "error";
+
+export interface EventSubEndpoints {
+	websocket: string;
+	subscriptions: string;
+}
+
+export type SubscriptionError =
+	| { type: "not_connected" }
+	| { type: "sessions_full"; sessions: number; limit: number }
+	| { type: "cost_limit"; total_cost: number; max_total_cost: number }
+	| { type: "missing_scope"; message: string }
+	| { type: "not_moderator" }
+	| { type: "rate_limited"; reset: number | null }
+	| { type: "conflict" }
+	| { type: "rejected"; status: number; message: string }
+	| { type: "request"; message: string };
+
+export interface SubscriptionResult {
+	event: keyof SubscriptionEventMap;
+	error: SubscriptionError | null;
+}
+
+export type RevocationReason =
+	| "authorization_revoked"
+	| "user_removed"
+	| "moderator_removed"
+	| "version_removed"
+	| "unknown";
+
+export interface Revocation {
+	type: "revoked";
+	session: number;
+	channel: string;
+	event: keyof SubscriptionEventMap;
+	condition: Record<string, string>;
+	reason: RevocationReason;
+	resubscribed: boolean;
+}
+
+export type EventSubStatus =
+	| { type: "connected"; session: number }
+	| { type: "reconnecting"; session: number; reason: ReconnectReason; attempt: number }
+	| { type: "restored"; session: number; subscriptions: number }
+	| { type: "disconnected"; session: number };
+
+/**
+ * The event of a notification. Events the backend has no typed payload for,
+ * or that failed to deserialize, are passed through as-is with the `opaque`
+ * kind.
+ */
+export interface NotificationEvent {
+	kind: string;
+	data: SubscriptionEvent;
+}
+
 export interface NotificationPayload {
-	subscription: { type: string };
-	event: SubscriptionEvent;
+	id: string;
+	timestamp: string;
+	subscription: { type: string; version: string };
+	event: NotificationEvent;
 }
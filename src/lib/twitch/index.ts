@@ -16,6 +16,16 @@ export const COLORS = [
 	"rgb(255, 127, 80)",
 ];
 
+/**
+ * The token the backend authenticates with, sent after logging in and when the
+ * token is refreshed.
+ */
+export interface TokenInfo {
+	user_id: string;
+	access_token: string;
+	refresh_token: string | null;
+}
+
 export const SCOPES = [
 	// Channel
 	"channel:edit:commercial",
@@ -27,11 +37,14 @@ export const SCOPES = [
 	"channel:manage:redemptions",
 	"channel:manage:vips",
 	"channel:moderate",
+	"channel:read:ads",
+	"channel:read:charity",
 	"channel:read:editors",
 	"channel:read:hype_train",
 	"channel:read:polls",
 	"channel:read:predictions",
 	"channel:read:redemptions",
+	"channel:read:subscriptions",
 
 	// Chat
 	"chat:edit",
@@ -49,6 +62,7 @@ export const SCOPES = [
 	"moderator:manage:unban_requests",
 	"moderator:manage:warnings",
 	"moderator:read:chatters",
+	"moderator:read:followers",
 	"moderator:read:moderators",
 	"moderator:read:suspicious_users",
 	"moderator:read:vips",
@@ -103,6 +103,15 @@ export interface BasicUser {
 export interface Badge {
 	name: string;
 	version: string;
+	title?: string;
+	image_url?: string;
+}
+
+export interface Cheer {
+	prefix: string;
+	bits: number;
+	color: string;
+	srcset: string[];
 }
 
 export interface Range {
@@ -170,6 +179,8 @@ export interface PrivmsgMessage extends BaseUserMessage {
 	is_mod: boolean;
 	is_subscriber: boolean;
 	bits: number | null;
+	cheers?: Cheer[];
+	seventv_badges?: Badge[];
 }
 
 export interface RoomStateMessage {
@@ -1,4 +1,5 @@
 import { redirect } from "@sveltejs/kit";
+import { invoke } from "@tauri-apps/api/core";
 import { app } from "$lib/app.svelte";
 import { log } from "$lib/log";
 import { Channel } from "$lib/models/channel.svelte";
@@ -6,6 +7,7 @@ import { CurrentUser } from "$lib/models/current-user.svelte";
 import { Stream } from "$lib/models/stream.svelte";
 import { User } from "$lib/models/user.svelte";
 import { storage } from "$lib/stores";
+import type { TokenInfo } from "$lib/twitch";
 import type { BasicUser } from "$lib/twitch/irc";
 import type { Prefix } from "$lib/util";
 
@@ -25,7 +27,19 @@ export async function load({ url }) {
 		return;
 	}
 
-	app.twitch.token ??= storage.state.user.token;
+	if (!app.twitch.token) {
+		// The backend refreshes the stored token if it expired while closed
+		const info = await invoke<TokenInfo | null>("get_token_info");
+
+		if (info && info.access_token !== storage.state.user.token) {
+			storage.state.user.token = info.access_token;
+			storage.state.user.refreshToken = info.refresh_token ?? undefined;
+
+			await storage.saveNow();
+		}
+
+		app.twitch.token = storage.state.user.token;
+	}
 
 	if (!app.user) {
 		const user = new User(app.twitch, storage.state.user.data);
@@ -4,6 +4,8 @@ import { log } from "$lib/log";
 import { storage } from "$lib/stores";
 
 export async function load() {
+	await app.disconnect();
+
 	storage.state.user = null;
 	storage.state.lastJoined = null;
 
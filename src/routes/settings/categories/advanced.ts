@@ -1,7 +1,7 @@
 import Toolbox from "~icons/ph/toolbox";
 import { app } from "$lib/app.svelte";
 import ClearCache from "../custom/ClearCache.svelte";
-import type { SettingsCategory } from "../types";
+import type { SettingsCategory, SettingsField } from "../types";
 
 export default {
 	order: 9999,
@@ -47,6 +47,62 @@ export default {
 				},
 			],
 		},
+		{
+			type: "group",
+			label: "IPC",
+			fields: [
+				{
+					id: "advanced.ipc.encoding",
+					type: "select",
+					label: "Encoding",
+					description:
+						"Set how chat messages are sent from the backend. MessagePack produces smaller payloads which may help in busy channels. Takes effect after restarting.",
+					items: [
+						{ label: "JSON", value: "json" },
+						{ label: "MessagePack", value: "messagepack" },
+					],
+				},
+				{
+					id: "advanced.ipc.raw",
+					type: "switch",
+					label: "Include raw messages",
+					description:
+						"Include the raw IRC message with every chat message for debugging. Takes effect after restarting.",
+				},
+			],
+		},
+		{
+			type: "group",
+			label: "EventSub",
+			fields: [
+				// The mock server is only available in development builds
+				...(import.meta.env.DEV
+					? [
+							{
+								id: "advanced.eventsub.mock",
+								type: "switch",
+								label: "Use mock server",
+								description:
+									"Connect to a local mock EventSub server instead of Twitch for developing offline. Takes effect after restarting.",
+							} satisfies SettingsField,
+						]
+					: []),
+				{
+					id: "advanced.eventsub.websocketUrl",
+					type: "input",
+					label: "WebSocket URL",
+					description:
+						"Connect to a custom EventSub WebSocket server, such as the Twitch CLI. Leave empty to use Twitch.",
+				},
+				{
+					id: "advanced.eventsub.subscriptionsUrl",
+					type: "input",
+					label: "Subscriptions URL",
+					description:
+						"Manage subscriptions through a custom endpoint. Leave empty to use Twitch.",
+				},
+			],
+		},
 		{
 			type: "group",
 			label: "Cache",
@@ -28,6 +28,14 @@ export default {
 					label: "Enable embeds",
 					description: "Show embedded content for supported links.",
 				},
+				{
+					id: "chat.eventsubChannels",
+					type: "input",
+					label: "Receive chat through EventSub",
+					description:
+						"Receive messages in these channels through EventSub instead of IRC, which includes richer message data. Separate channels with commas. Takes effect after rejoining.",
+					placeholder: "e.g. forsen, xqc",
+				},
 			],
 		},
 		{
@@ -172,6 +180,15 @@ export default {
 							step: 50,
 							disabled: () => !settings.state["chat.messages.history.enabled"],
 						},
+						{
+							id: "chat.messages.history.customUrl",
+							type: "input",
+							label: "Custom recent messages API",
+							description:
+								"Fetch history from a self-hosted instance of the recent-messages API first, falling back to the default one if it's unavailable.",
+							placeholder: "e.g. https://recent-messages.example.com/api/v2/recent-messages",
+							disabled: () => !settings.state["chat.messages.history.enabled"],
+						},
 						{
 							id: "chat.messages.history.separator",
 							type: "switch",